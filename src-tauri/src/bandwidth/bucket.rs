@@ -0,0 +1,179 @@
+use std::time::{Duration, Instant};
+
+use crate::models::BandwidthSchedule;
+
+// ── Token bucket ────────────────────────────────────────────────────────────
+
+/// Seconds of traffic a bucket may send in a burst after being idle.
+const BURST_SECS: f64 = 0.25;
+
+/// Token bucket refilled at `rate` bytes per second. Taking more than is
+/// available leaves the bucket in debt, so a chunk larger than the burst
+/// still goes through; the caller just waits longer for it.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: u64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u64, now: Instant) -> Self {
+        let mut bucket = Self {
+            rate,
+            tokens: 0.0,
+            last: now,
+        };
+        bucket.tokens = bucket.capacity();
+        bucket
+    }
+
+    fn capacity(&self) -> f64 {
+        self.rate as f64 * BURST_SECS
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Change the refill rate, keeping any debt.
+    pub fn set_rate(&mut self, rate: u64) {
+        self.rate = rate;
+        self.tokens = self.tokens.min(self.capacity());
+    }
+
+    /// Take `bytes` at `now`; returns how long the caller has to wait before
+    /// they are covered.
+    pub fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        if self.rate == 0 {
+            return Duration::ZERO;
+        }
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = self.last.max(now);
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.capacity());
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate as f64)
+        }
+    }
+}
+
+// ── Schedule ────────────────────────────────────────────────────────────────
+
+/// Whether `entry` covers `weekday` (0 = Monday) at `minute` after midnight.
+pub fn schedule_active(entry: &BandwidthSchedule, weekday: u8, minute: u16) -> bool {
+    let (start, end) = (entry.start_minute, entry.end_minute);
+    if start <= end {
+        let in_window = minute >= start && minute < end;
+        in_window && (entry.days.is_empty() || entry.days.contains(&weekday))
+    } else if minute >= start {
+        // Evening part of a window that spans midnight.
+        entry.days.is_empty() || entry.days.contains(&weekday)
+    } else if minute < end {
+        // Morning part: the window opened the day before.
+        let previous = (weekday + 6) % 7;
+        entry.days.is_empty() || entry.days.contains(&previous)
+    } else {
+        false
+    }
+}
+
+/// The limit in force for `connection_id` (None = global): the first active
+/// schedule entry for it, else `base`.
+pub fn effective_rate(
+    base: u64,
+    schedule: &[BandwidthSchedule],
+    connection_id: Option<&str>,
+    weekday: u8,
+    minute: u16,
+) -> u64 {
+    schedule
+        .iter()
+        .find(|entry| {
+            entry.connection_id.as_deref() == connection_id
+                && schedule_active(entry, weekday, minute)
+        })
+        .map(|entry| entry.bytes_per_sec)
+        .unwrap_or(base)
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(days: &[u8], start: u16, end: u16, rate: u64, conn: Option<&str>) -> BandwidthSchedule {
+        BandwidthSchedule {
+            days: days.to_vec(),
+            start_minute: start,
+            end_minute: end,
+            bytes_per_sec: rate,
+            connection_id: conn.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_bucket_allows_burst_then_paces() {
+        let t0 = Instant::now();
+        let mut b = TokenBucket::new(1000, t0);
+        assert_eq!(b.take(250, t0), Duration::ZERO, "burst of 0.25 s");
+        assert_eq!(b.take(500, t0), Duration::from_millis(500));
+        // Half a second later the debt is paid off.
+        assert_eq!(b.take(0, t0 + Duration::from_millis(500)), Duration::ZERO);
+        // Idle time refills no more than the burst.
+        let later = t0 + Duration::from_secs(60);
+        assert_eq!(b.take(250, later), Duration::ZERO);
+        assert_eq!(b.take(100, later), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_bucket_rate_change_and_unlimited() {
+        let t0 = Instant::now();
+        let mut b = TokenBucket::new(1000, t0);
+        b.take(1250, t0);
+        b.set_rate(2000);
+        // 1000 bytes of debt at 2000 B/s.
+        assert_eq!(b.take(0, t0), Duration::from_millis(500));
+        b.set_rate(0);
+        assert_eq!(b.take(1_000_000, t0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_schedule_window() {
+        // Mon–Fri 09:00–17:00
+        let e = entry(&[0, 1, 2, 3, 4], 9 * 60, 17 * 60, 100, None);
+        assert!(schedule_active(&e, 0, 9 * 60));
+        assert!(schedule_active(&e, 4, 17 * 60 - 1));
+        assert!(!schedule_active(&e, 4, 17 * 60));
+        assert!(!schedule_active(&e, 5, 12 * 60), "Saturday");
+    }
+
+    #[test]
+    fn test_schedule_spanning_midnight() {
+        // Friday 22:00 – Saturday 06:00
+        let e = entry(&[4], 22 * 60, 6 * 60, 100, None);
+        assert!(schedule_active(&e, 4, 23 * 60));
+        assert!(schedule_active(&e, 5, 5 * 60));
+        assert!(!schedule_active(&e, 4, 5 * 60), "Friday morning belongs to Thursday");
+        assert!(!schedule_active(&e, 5, 23 * 60));
+        // Every day
+        let e = entry(&[], 22 * 60, 6 * 60, 100, None);
+        assert!(schedule_active(&e, 0, 0));
+        assert!(!schedule_active(&e, 0, 12 * 60));
+    }
+
+    #[test]
+    fn test_effective_rate() {
+        let schedule = vec![
+            entry(&[], 9 * 60, 17 * 60, 100, None),
+            entry(&[], 0, 24 * 60, 50, Some("s3-1")),
+        ];
+        assert_eq!(effective_rate(0, &schedule, None, 0, 10 * 60), 100);
+        assert_eq!(effective_rate(0, &schedule, None, 0, 20 * 60), 0);
+        assert_eq!(effective_rate(500, &schedule, Some("s3-1"), 0, 20 * 60), 50);
+        assert_eq!(effective_rate(500, &schedule, Some("sftp-2"), 0, 10 * 60), 500);
+    }
+}
@@ -0,0 +1,207 @@
+pub mod bucket;
+
+pub use bucket::{effective_rate, schedule_active, TokenBucket};
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, Timelike};
+
+use crate::models::{BandwidthLimits, BandwidthSchedule};
+
+// ── Limiter ─────────────────────────────────────────────────────────────────
+
+/// Configured limits plus one token bucket per limit in force, keyed
+/// "global", "conn:<id>" or "op:<id>".
+struct Limiter {
+    limits: BandwidthLimits,
+    buckets: HashMap<String, TokenBucket>,
+}
+
+static LIMITER: Mutex<Option<Limiter>> = Mutex::new(None);
+
+fn with_limiter<R>(f: impl FnOnce(&mut Limiter) -> R) -> R {
+    let mut guard = LIMITER.lock().unwrap_or_else(|e| e.into_inner());
+    let limiter = guard.get_or_insert_with(|| Limiter {
+        limits: BandwidthLimits::default(),
+        buckets: HashMap::new(),
+    });
+    f(limiter)
+}
+
+/// Who is moving data: the operation id and the connection ids involved.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    pub op_id: String,
+    pub connections: Vec<String>,
+}
+
+tokio::task_local! {
+    static SCOPE: Scope;
+}
+
+/// Run `fut` as operation `op_id` on `connections` ("" for local is
+/// ignored), so `throttle` inside it applies their limits as well as the
+/// global one.
+pub async fn scoped<F: Future>(op_id: &str, connections: &[&str], fut: F) -> F::Output {
+    let scope = Scope {
+        op_id: op_id.to_string(),
+        connections: connections
+            .iter()
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string())
+            .collect(),
+    };
+    let result = SCOPE.scope(scope, fut).await;
+    release(op_id);
+    result
+}
+
+/// Forget the bucket and the transfer limit of operation `op_id` once it
+/// has ended.
+pub fn release(op_id: &str) {
+    with_limiter(|l| {
+        l.buckets.remove(&format!("op:{}", op_id));
+        l.limits.transfers.remove(op_id);
+    });
+}
+
+/// The scope of the running task, to carry into tasks it spawns.
+pub fn current_scope() -> Option<Scope> {
+    SCOPE.try_with(Scope::clone).ok()
+}
+
+/// Run `fut` in `scope`, e.g. inside a spawned task.
+pub async fn in_scope<F: Future>(scope: Option<Scope>, fut: F) -> F::Output {
+    match scope {
+        Some(scope) => SCOPE.scope(scope, fut).await,
+        None => fut.await,
+    }
+}
+
+/// Day of the week (0 = Monday) and minute of the day in local time, as
+/// schedule entries use them.
+fn local_time() -> (u8, u16) {
+    let local = chrono::Local::now();
+    let weekday = local.weekday().num_days_from_monday() as u8;
+    let minute = (local.hour() * 60 + local.minute()) as u16;
+    (weekday, minute)
+}
+
+/// Take `bytes` from every bucket that applies to `scope` and return the
+/// longest wait among them.
+fn reserve(scope: Option<&Scope>, bytes: u64) -> Duration {
+    let (weekday, minute) = local_time();
+    let now = Instant::now();
+
+    with_limiter(|l| {
+        let limits = &l.limits;
+        let mut rates = vec![(
+            "global".to_string(),
+            effective_rate(limits.global, &limits.schedule, None, weekday, minute),
+        )];
+        if let Some(scope) = scope {
+            for id in &scope.connections {
+                let base = limits.connections.get(id).copied().unwrap_or(0);
+                let rate = effective_rate(base, &limits.schedule, Some(id), weekday, minute);
+                rates.push((format!("conn:{}", id), rate));
+            }
+            let rate = limits.transfers.get(&scope.op_id).copied().unwrap_or(0);
+            rates.push((format!("op:{}", scope.op_id), rate));
+        }
+
+        let mut wait = Duration::ZERO;
+        for (key, rate) in rates {
+            if rate == 0 {
+                l.buckets.remove(&key);
+                continue;
+            }
+            let bucket = l
+                .buckets
+                .entry(key)
+                .or_insert_with(|| TokenBucket::new(rate, now));
+            if bucket.rate() != rate {
+                bucket.set_rate(rate);
+            }
+            wait = wait.max(bucket.take(bytes, now));
+        }
+        wait
+    })
+}
+
+/// Wait until the limits that apply to the current task allow `bytes` more.
+pub async fn throttle(bytes: u64) {
+    if bytes == 0 {
+        return;
+    }
+    let wait = reserve(current_scope().as_ref(), bytes);
+    if wait > Duration::from_millis(1) {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// `throttle` for blocking code such as local file copies.
+pub fn throttle_blocking(op_id: &str, bytes: u64) {
+    if bytes == 0 {
+        return;
+    }
+    let scope = Scope {
+        op_id: op_id.to_string(),
+        connections: Vec::new(),
+    };
+    let wait = reserve(Some(&scope), bytes);
+    if wait > Duration::from_millis(1) {
+        std::thread::sleep(wait);
+    }
+}
+
+/// Whether a limit currently slows down local operation `op_id`.
+pub fn is_limited(op_id: &str) -> bool {
+    let (weekday, minute) = local_time();
+    with_limiter(|l| {
+        let limits = &l.limits;
+        effective_rate(limits.global, &limits.schedule, None, weekday, minute) > 0
+            || limits.transfers.get(op_id).is_some_and(|rate| *rate > 0)
+    })
+}
+
+// ── Settings ────────────────────────────────────────────────────────────────
+
+/// Set the global limit in bytes per second (0 = unlimited).
+pub fn set_global(bytes_per_sec: u64) {
+    with_limiter(|l| l.limits.global = bytes_per_sec);
+}
+
+/// Set the limit for one connection (0 removes it).
+pub fn set_connection(id: &str, bytes_per_sec: u64) {
+    with_limiter(|l| {
+        if bytes_per_sec == 0 {
+            l.limits.connections.remove(id);
+        } else {
+            l.limits.connections.insert(id.to_string(), bytes_per_sec);
+        }
+    });
+}
+
+/// Set the limit for one transfer by operation id (0 removes it). It can be
+/// set before the transfer starts or while it runs.
+pub fn set_transfer(op_id: &str, bytes_per_sec: u64) {
+    with_limiter(|l| {
+        if bytes_per_sec == 0 {
+            l.limits.transfers.remove(op_id);
+        } else {
+            l.limits.transfers.insert(op_id.to_string(), bytes_per_sec);
+        }
+    });
+}
+
+/// Replace the schedule.
+pub fn set_schedule(schedule: Vec<BandwidthSchedule>) {
+    with_limiter(|l| l.limits.schedule = schedule);
+}
+
+pub fn limits() -> BandwidthLimits {
+    with_limiter(|l| l.limits.clone())
+}
@@ -418,6 +418,7 @@ pub fn extract_archive(
         current_file: String::new(),
         files_done: files_total,
         files_total,
+        retries: 0,
     });
 
     Ok(())
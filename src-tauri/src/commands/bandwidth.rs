@@ -0,0 +1,49 @@
+use crate::bandwidth;
+use crate::models::{BandwidthLimits, BandwidthSchedule, FmError};
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Set a bandwidth limit in bytes per second; 0 removes it. `scope` is
+/// "global", "connection" (`id` = connection id) or "transfer" (`id` =
+/// operation or queue job id). Limits apply to S3, SFTP and local copies
+/// and take effect on the next chunk of running transfers. A transfer limit
+/// is dropped when its operation ends or pauses; queue jobs keep theirs
+/// until they finish.
+#[tauri::command]
+pub fn set_bandwidth_limit(
+    scope: String,
+    id: Option<String>,
+    bytes_per_sec: u64,
+) -> Result<(), FmError> {
+    let id = id.as_deref().unwrap_or("");
+    match scope.as_str() {
+        "global" => bandwidth::set_global(bytes_per_sec),
+        "connection" | "transfer" if id.is_empty() => {
+            return Err(FmError::Other(format!("A {} limit needs an id", scope)));
+        }
+        "connection" => bandwidth::set_connection(id, bytes_per_sec),
+        "transfer" => bandwidth::set_transfer(id, bytes_per_sec),
+        _ => return Err(FmError::Other(format!("Unknown bandwidth scope: {}", scope))),
+    }
+    Ok(())
+}
+
+/// Replace the scheduled limits.
+#[tauri::command]
+pub fn set_bandwidth_schedule(schedule: Vec<BandwidthSchedule>) -> Result<(), FmError> {
+    for entry in &schedule {
+        if entry.start_minute >= 24 * 60 || entry.end_minute > 24 * 60 {
+            return Err(FmError::Other("Schedule times must be within a day".into()));
+        }
+        if entry.days.iter().any(|d| *d > 6) {
+            return Err(FmError::Other("Schedule days are 0 (Monday) to 6 (Sunday)".into()));
+        }
+    }
+    bandwidth::set_schedule(schedule);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_bandwidth_limits() -> Result<BandwidthLimits, FmError> {
+    Ok(bandwidth::limits())
+}
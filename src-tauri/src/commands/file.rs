@@ -1,6 +1,8 @@
+use crate::bandwidth;
 use crate::models::{FmError, ProgressEvent, TransferCheckpoint};
 use std::collections::HashMap;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -53,6 +55,39 @@ fn total_bytes(path: &Path) -> u64 {
     total
 }
 
+/// Copy a single file. When a bandwidth limit applies it is copied in chunks
+/// paced by the limiter, checking for cancel between chunks; otherwise it is
+/// left to `fs::copy`.
+fn copy_file(src: &Path, dst: &Path, id: &str, flags: &OpFlags) -> Result<(), FmError> {
+    if !bandwidth::is_limited(id) {
+        fs::copy(src, dst)?;
+        return Ok(());
+    }
+    let mut reader = fs::File::open(src)?;
+    let mut writer = fs::File::create(dst)?;
+    let mut buf = vec![0u8; 256 * 1024];
+    let result = (|| {
+        loop {
+            if flags.cancel.load(Ordering::Relaxed) {
+                return Err(FmError::Other("Operation cancelled".into()));
+            }
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+            bandwidth::throttle_blocking(id, n as u64);
+        }
+        fs::set_permissions(dst, reader.metadata()?.permissions())?;
+        Ok(())
+    })();
+    if result.is_err() {
+        drop(writer);
+        let _ = fs::remove_file(dst);
+    }
+    result
+}
+
 /// Check result for copy_recursive: either success, pause, or error.
 enum CopyResult {
     Done,
@@ -107,7 +142,7 @@ fn copy_recursive(
         }
 
         let size = fs::metadata(src).map(|m| m.len()).unwrap_or(0);
-        fs::copy(src, dst)?;
+        copy_file(src, dst, id, flags)?;
         *bytes_done += size;
         *files_done += 1;
         completed_files.push(src.to_string_lossy().into_owned());
@@ -119,6 +154,7 @@ fn copy_recursive(
             current_file: src.to_string_lossy().into_owned(),
             files_done: *files_done,
             files_total,
+            retries: 0,
         });
     }
     Ok(CopyResult::Done)
@@ -129,7 +165,7 @@ fn copy_recursive(
 /// Copy one or more files/directories to `destination` with progress reporting.
 /// Returns None on success, Some(checkpoint) on pause.
 #[tauri::command]
-pub fn copy_files(
+pub async fn copy_files(
     id: String,
     sources: Vec<String>,
     destination: String,
@@ -156,49 +192,56 @@ pub fn copy_files(
         map.insert(id.clone(), flags.clone());
     }
 
-    let mut bytes_done: u64 = 0;
-    let mut files_done: u32 = 0;
-    let mut completed_files: Vec<String> = Vec::new();
-
-    let result = (|| {
-        for src in &sources {
-            let src_path = PathBuf::from(src);
-            let file_name = src_path
-                .file_name()
-                .ok_or_else(|| FmError::Other(format!("invalid source path: {src}")))?;
-            let dst_path = dest.join(file_name);
+    // Copying blocks (and sleeps under a bandwidth limit), so run it on a
+    // blocking thread; cancel/pause still reach it through `flags`.
+    let result = tauri::async_runtime::spawn_blocking({
+        let (id, flags) = (id.clone(), flags.clone());
+        move || {
+            let mut bytes_done: u64 = 0;
+            let mut files_done: u32 = 0;
+            let mut completed_files: Vec<String> = Vec::new();
+            for src in &sources {
+                let src_path = PathBuf::from(src);
+                let file_name = src_path
+                    .file_name()
+                    .ok_or_else(|| FmError::Other(format!("invalid source path: {src}")))?;
+                let dst_path = dest.join(file_name);
 
-            match copy_recursive(
-                &src_path,
-                &dst_path,
-                &id,
-                &mut bytes_done,
-                bytes_total,
-                &mut files_done,
-                files_total,
-                &channel,
-                &flags,
-                &mut completed_files,
-            )? {
-                CopyResult::Done => {}
-                CopyResult::Paused => {
-                    return Ok(Some(TransferCheckpoint {
-                        files_completed: completed_files,
-                        bytes_done,
-                        bytes_total,
-                        files_done,
-                        files_total,
-                    }));
+                match copy_recursive(
+                    &src_path,
+                    &dst_path,
+                    &id,
+                    &mut bytes_done,
+                    bytes_total,
+                    &mut files_done,
+                    files_total,
+                    &channel,
+                    &flags,
+                    &mut completed_files,
+                )? {
+                    CopyResult::Done => {}
+                    CopyResult::Paused => {
+                        return Ok(Some(TransferCheckpoint {
+                            files_completed: completed_files,
+                            bytes_done,
+                            bytes_total,
+                            files_done,
+                            files_total,
+                        }));
+                    }
                 }
             }
+            Ok(None)
         }
-        Ok(None)
-    })();
+    })
+    .await
+    .unwrap_or_else(|e| Err(FmError::Other(e.to_string())));
 
     // Clean up the flags from state.
     if let Ok(mut map) = state.0.lock() {
         map.remove(&id);
     }
+    bandwidth::release(&id);
 
     result
 }
@@ -209,7 +252,7 @@ pub fn copy_files(
 /// fails (e.g. cross-device move).
 /// Returns None on success, Some(checkpoint) on pause.
 #[tauri::command]
-pub fn move_files(
+pub async fn move_files(
     id: String,
     sources: Vec<String>,
     destination: String,
@@ -236,87 +279,94 @@ pub fn move_files(
         map.insert(id.clone(), flags.clone());
     }
 
-    let mut bytes_done: u64 = 0;
-    let mut files_done: u32 = 0;
-    let mut completed_files: Vec<String> = Vec::new();
-
-    let result = (|| {
-        for src in &sources {
-            if flags.cancel.load(Ordering::Relaxed) {
-                return Err(FmError::Other("Operation cancelled".into()));
-            }
-            if flags.pause.load(Ordering::Relaxed) {
-                return Ok(Some(TransferCheckpoint {
-                    files_completed: completed_files.clone(),
-                    bytes_done,
-                    bytes_total,
-                    files_done,
-                    files_total,
-                }));
-            }
+    // Runs on a blocking thread, like copy_files.
+    let result = tauri::async_runtime::spawn_blocking({
+        let (id, flags) = (id.clone(), flags.clone());
+        move || {
+            let mut bytes_done: u64 = 0;
+            let mut files_done: u32 = 0;
+            let mut completed_files: Vec<String> = Vec::new();
+            for src in &sources {
+                if flags.cancel.load(Ordering::Relaxed) {
+                    return Err(FmError::Other("Operation cancelled".into()));
+                }
+                if flags.pause.load(Ordering::Relaxed) {
+                    return Ok(Some(TransferCheckpoint {
+                        files_completed: completed_files.clone(),
+                        bytes_done,
+                        bytes_total,
+                        files_done,
+                        files_total,
+                    }));
+                }
 
-            let src_path = PathBuf::from(src);
-            let file_name = src_path
-                .file_name()
-                .ok_or_else(|| FmError::Other(format!("invalid source path: {src}")))?;
-            let dst_path = dest.join(file_name);
-
-            // Try fast rename first.
-            if fs::rename(&src_path, &dst_path).is_ok() {
-                let size = total_bytes(&dst_path);
-                let count = count_files(&dst_path);
-                bytes_done += size;
-                files_done += count;
-                completed_files.push(src.clone());
-
-                let _ = channel.send(ProgressEvent {
-                    id: id.clone(),
-                    bytes_done,
-                    bytes_total,
-                    current_file: src_path.to_string_lossy().into_owned(),
-                    files_done,
-                    files_total,
-                });
-            } else {
-                // Cross-device: copy then delete source.
-                match copy_recursive(
-                    &src_path,
-                    &dst_path,
-                    &id,
-                    &mut bytes_done,
-                    bytes_total,
-                    &mut files_done,
-                    files_total,
-                    &channel,
-                    &flags,
-                    &mut completed_files,
-                )? {
-                    CopyResult::Done => {
-                        if src_path.is_dir() {
-                            fs::remove_dir_all(&src_path)?;
-                        } else {
-                            fs::remove_file(&src_path)?;
+                let src_path = PathBuf::from(src);
+                let file_name = src_path
+                    .file_name()
+                    .ok_or_else(|| FmError::Other(format!("invalid source path: {src}")))?;
+                let dst_path = dest.join(file_name);
+
+                // Try fast rename first.
+                if fs::rename(&src_path, &dst_path).is_ok() {
+                    let size = total_bytes(&dst_path);
+                    let count = count_files(&dst_path);
+                    bytes_done += size;
+                    files_done += count;
+                    completed_files.push(src.clone());
+
+                    let _ = channel.send(ProgressEvent {
+                        id: id.clone(),
+                        bytes_done,
+                        bytes_total,
+                        current_file: src_path.to_string_lossy().into_owned(),
+                        files_done,
+                        files_total,
+                        retries: 0,
+                    });
+                } else {
+                    // Cross-device: copy then delete source.
+                    match copy_recursive(
+                        &src_path,
+                        &dst_path,
+                        &id,
+                        &mut bytes_done,
+                        bytes_total,
+                        &mut files_done,
+                        files_total,
+                        &channel,
+                        &flags,
+                        &mut completed_files,
+                    )? {
+                        CopyResult::Done => {
+                            if src_path.is_dir() {
+                                fs::remove_dir_all(&src_path)?;
+                            } else {
+                                fs::remove_file(&src_path)?;
+                            }
+                        }
+                        CopyResult::Paused => {
+                            return Ok(Some(TransferCheckpoint {
+                                files_completed: completed_files,
+                                bytes_done,
+                                bytes_total,
+                                files_done,
+                                files_total,
+                            }));
                         }
-                    }
-                    CopyResult::Paused => {
-                        return Ok(Some(TransferCheckpoint {
-                            files_completed: completed_files,
-                            bytes_done,
-                            bytes_total,
-                            files_done,
-                            files_total,
-                        }));
                     }
                 }
             }
+            Ok(None)
         }
-        Ok(None)
-    })();
+    })
+    .await
+    .unwrap_or_else(|e| Err(FmError::Other(e.to_string())));
 
     // Clean up the flags from state.
     if let Ok(mut map) = state.0.lock() {
         map.remove(&id);
     }
+    bandwidth::release(&id);
 
     result
 }
@@ -0,0 +1,226 @@
+use crate::commands::file::{FileOpState, OpFlags};
+use crate::ftp::client::{FtpConfig, FtpStream, FtpTls};
+use crate::ftp::helpers::strip_ftp_prefix;
+use crate::ftp::{ftperr, FtpConnection, FtpService, FtpState};
+use crate::models::{DirListing, FmError, ProgressEvent, TransferCheckpoint};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tauri::ipc::Channel;
+use tauri::State;
+
+// ── Helper ───────────────────────────────────────────────────────────────────
+
+/// Extract an owned FtpService from the state, dropping the MutexGuard
+/// before any async work (same pattern as S3's get_service).
+fn get_service(state: &State<'_, FtpState>, id: &str) -> Result<FtpService, FmError> {
+    let map = state.0.lock().map_err(|e| ftperr(e.to_string()))?;
+    let conn = map.get(id).ok_or_else(|| ftperr("FTP connection not found"))?;
+    Ok(FtpService::new(conn.control.clone(), conn.config.clone()))
+}
+
+fn register_op(file_op_state: &State<'_, FileOpState>, op_id: &str) -> Result<Arc<OpFlags>, FmError> {
+    let mut ops = file_op_state
+        .0
+        .lock()
+        .map_err(|e| ftperr(e.to_string()))?;
+    let flags = Arc::new(OpFlags {
+        cancel: AtomicBool::new(false),
+        pause: AtomicBool::new(false),
+    });
+    ops.insert(op_id.to_string(), flags.clone());
+    Ok(flags)
+}
+
+fn unregister_op(file_op_state: &State<'_, FileOpState>, op_id: &str) -> Result<(), FmError> {
+    file_op_state
+        .0
+        .lock()
+        .map_err(|e| ftperr(e.to_string()))?
+        .remove(op_id);
+    Ok(())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Connect and log in, returning the initial directory. `tls` is "none"
+/// (default), "explicit" (AUTH TLS) or "implicit"; the port defaults to 990
+/// for implicit TLS and 21 otherwise. Without a username the login is
+/// anonymous.
+#[tauri::command]
+pub async fn ftp_connect(
+    state: State<'_, FtpState>,
+    id: String,
+    host: String,
+    port: Option<u16>,
+    username: Option<String>,
+    password: Option<String>,
+    tls: Option<String>,
+    passive: Option<bool>,
+    accept_invalid_certs: Option<bool>,
+) -> Result<String, FmError> {
+    let tls = FtpTls::parse(tls.as_deref().unwrap_or("none"))?;
+    let default_port = if tls == FtpTls::Implicit { 990 } else { 21 };
+    let config = FtpConfig {
+        host,
+        port: port.unwrap_or(default_port),
+        username: username
+            .filter(|u| !u.is_empty())
+            .unwrap_or_else(|| "anonymous".to_string()),
+        password: password.unwrap_or_default(),
+        tls,
+        passive: passive.unwrap_or(true),
+        accept_invalid_certs: accept_invalid_certs.unwrap_or(false),
+    };
+
+    let mut stream = FtpStream::connect(&config).await?;
+    let home_dir = stream.pwd().await.unwrap_or_else(|_| "/".to_string());
+
+    let conn = FtpConnection {
+        control: Arc::new(tokio::sync::Mutex::new(stream)),
+        config: Arc::new(config),
+        home_dir: home_dir.clone(),
+    };
+    state
+        .0
+        .lock()
+        .map_err(|e| ftperr(e.to_string()))?
+        .insert(id, conn);
+
+    Ok(home_dir)
+}
+
+#[tauri::command]
+pub async fn ftp_disconnect(state: State<'_, FtpState>, id: String) -> Result<(), FmError> {
+    let conn = {
+        let mut map = state.0.lock().map_err(|e| ftperr(e.to_string()))?;
+        map.remove(&id)
+    };
+    if let Some(conn) = conn {
+        let _ = conn.control.lock().await.command("QUIT").await;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ftp_list_objects(
+    state: State<'_, FtpState>,
+    id: String,
+    path: String,
+) -> Result<DirListing, FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.list_objects(strip_ftp_prefix(&path)).await
+}
+
+#[tauri::command]
+pub async fn ftp_delete(
+    state: State<'_, FtpState>,
+    id: String,
+    paths: Vec<String>,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    let remote_paths: Vec<String> = paths.iter().map(|p| strip_ftp_prefix(p).to_string()).collect();
+    svc.delete(&remote_paths).await
+}
+
+#[tauri::command]
+pub async fn ftp_rename(
+    state: State<'_, FtpState>,
+    id: String,
+    path: String,
+    new_name: String,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.rename(strip_ftp_prefix(&path), &new_name).await
+}
+
+#[tauri::command]
+pub async fn ftp_create_folder(
+    state: State<'_, FtpState>,
+    id: String,
+    path: String,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.create_folder(strip_ftp_prefix(&path)).await
+}
+
+#[tauri::command]
+pub async fn ftp_download(
+    state: State<'_, FtpState>,
+    file_op_state: State<'_, FileOpState>,
+    id: String,
+    op_id: String,
+    keys: Vec<String>,
+    destination: String,
+    resume: Option<bool>,
+    channel: Channel<ProgressEvent>,
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let flags = register_op(&file_op_state, &op_id)?;
+    let svc = get_service(&state, &id)?;
+    let remote_paths: Vec<String> = keys.iter().map(|p| strip_ftp_prefix(p).to_string()).collect();
+
+    let result = svc
+        .download(
+            &remote_paths,
+            &destination,
+            &op_id,
+            &flags.cancel,
+            &flags.pause,
+            &|evt| { let _ = channel.send(evt); },
+            resume.unwrap_or(false),
+        )
+        .await;
+
+    unregister_op(&file_op_state, &op_id)?;
+    result
+}
+
+#[tauri::command]
+pub async fn ftp_upload(
+    state: State<'_, FtpState>,
+    file_op_state: State<'_, FileOpState>,
+    id: String,
+    op_id: String,
+    sources: Vec<String>,
+    remote_prefix: String,
+    resume: Option<bool>,
+    channel: Channel<ProgressEvent>,
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let flags = register_op(&file_op_state, &op_id)?;
+    let svc = get_service(&state, &id)?;
+
+    let result = svc
+        .upload(
+            &sources,
+            strip_ftp_prefix(&remote_prefix),
+            &op_id,
+            &flags.cancel,
+            &flags.pause,
+            &|evt| { let _ = channel.send(evt); },
+            resume.unwrap_or(false),
+        )
+        .await;
+
+    unregister_op(&file_op_state, &op_id)?;
+    result
+}
+
+#[tauri::command]
+pub async fn ftp_download_temp(
+    state: State<'_, FtpState>,
+    id: String,
+    path: String,
+) -> Result<String, FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.download_temp(strip_ftp_prefix(&path)).await
+}
+
+#[tauri::command]
+pub async fn ftp_put_text(
+    state: State<'_, FtpState>,
+    id: String,
+    path: String,
+    content: String,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.put_text(strip_ftp_prefix(&path), &content).await
+}
@@ -1,15 +1,20 @@
 pub mod archive;
+pub mod bandwidth;
 pub mod cloudfront;
 pub mod directory;
 pub mod file;
+pub mod ftp;
 pub mod git;
 pub mod keychain;
 pub mod metadata;
 pub mod oidc;
+pub mod queue;
 pub mod s3;
 pub mod sftp;
 pub mod search;
+pub mod storage;
 pub mod sync;
 pub mod terminal;
 pub mod volumes;
 pub mod watcher;
+pub mod webdav;
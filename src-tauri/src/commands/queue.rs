@@ -0,0 +1,405 @@
+use crate::bandwidth;
+use crate::commands::file::{FileOpState, OpFlags};
+use crate::commands::sftp::get_service as get_sftp_service;
+use crate::commands::storage::{native_path, resolve_backend, s3_service};
+use crate::models::{FmError, ProgressEvent, S3UploadCheckpoint, TransferJob};
+use crate::queue::{queue_path, TransferQueue, MAX_CONCURRENCY};
+use crate::s3::{retry, S3State};
+use crate::sftp::SftpState;
+use crate::storage;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+// ── Managed state ───────────────────────────────────────────────────────
+
+/// The persistent transfer queue. Every change is written back to disk and
+/// the full job list is emitted as a `"transfer-queue"` event; progress of
+/// running jobs is emitted as `"transfer-queue-progress"`.
+pub struct QueueState {
+    queue: Mutex<TransferQueue>,
+    path: PathBuf,
+}
+
+impl QueueState {
+    pub fn load() -> Self {
+        let path = queue_path();
+        let queue = TransferQueue::load(&path).unwrap_or_else(|e| {
+            log::warn!("starting with an empty transfer queue: {e}");
+            TransferQueue::default()
+        });
+        Self {
+            queue: Mutex::new(queue),
+            path,
+        }
+    }
+
+    /// Apply `f` to the queue, then save and announce the result.
+    fn update<R>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut TransferQueue) -> Result<R, FmError>,
+    ) -> Result<R, FmError> {
+        let mut queue = self.queue.lock().map_err(|e| FmError::Other(e.to_string()))?;
+        let result = f(&mut queue)?;
+        if let Err(e) = queue.save(&self.path) {
+            log::warn!("failed to save transfer queue: {e}");
+        }
+        let _ = app.emit("transfer-queue", &queue.jobs);
+        Ok(result)
+    }
+
+    fn jobs(&self) -> Result<Vec<TransferJob>, FmError> {
+        let queue = self.queue.lock().map_err(|e| FmError::Other(e.to_string()))?;
+        Ok(queue.jobs.clone())
+    }
+}
+
+// ── Runner ──────────────────────────────────────────────────────────────
+
+/// How often a running job's progress is written to disk.
+const PROGRESS_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start as many pending jobs as the concurrency limit allows. Each job's
+/// flags are registered before it is spawned, so a pause or removal that
+/// arrives before the job gets going still reaches it.
+fn pump(app: &AppHandle) {
+    let state = app.state::<QueueState>();
+    let file_op_state = app.state::<FileOpState>();
+    let started = state.update(app, |q| Ok(q.start_next())).unwrap_or_default();
+    for id in started {
+        let flags = Arc::new(OpFlags {
+            cancel: AtomicBool::new(false),
+            pause: AtomicBool::new(false),
+        });
+        if let Ok(mut map) = file_op_state.0.lock() {
+            map.insert(id.clone(), flags.clone());
+        }
+        tauri::async_runtime::spawn(run_job(app.clone(), id, flags));
+    }
+}
+
+/// Run one job to the end of this attempt, record the outcome and start
+/// whatever is next.
+async fn run_job(app: AppHandle, id: String, flags: Arc<OpFlags>) {
+    let state = app.state::<QueueState>();
+    let job = match state.queue.lock() {
+        Ok(queue) => queue.get(&id).cloned(),
+        Err(_) => None,
+    };
+    let file_op_state = app.state::<FileOpState>();
+    let Some(job) = job else {
+        if let Ok(mut map) = file_op_state.0.lock() {
+            map.remove(&id);
+        }
+        return;
+    };
+
+    let last_saved = Mutex::new(Instant::now());
+    let on_progress = |evt: ProgressEvent| {
+        let save = last_saved.lock().is_ok_and(|mut at| {
+            let due = at.elapsed() >= PROGRESS_SAVE_INTERVAL;
+            if due {
+                *at = Instant::now();
+            }
+            due
+        });
+        if save {
+            let _ = state.update(&app, |q| {
+                q.set_progress(&evt);
+                Ok(())
+            });
+        } else if let Ok(mut queue) = state.queue.lock() {
+            queue.set_progress(&evt);
+        }
+        let _ = app.emit("transfer-queue-progress", &evt);
+    };
+    // Saved right away, so an upload left open by a crash can still be
+    // resumed or aborted after a restart.
+    let on_checkpoint = |cp: S3UploadCheckpoint| {
+        let _ = state.update(&app, |q| {
+            q.set_checkpoint(&id, cp);
+            Ok(())
+        });
+    };
+    let (outcome, transfer_limit) = retry::counted(bandwidth::scoped(
+        &job.id,
+        &[&job.source_id, &job.dest_id],
+        async {
+            let outcome = execute(&app, &job, &flags, &on_progress, &on_checkpoint).await;
+            (outcome, bandwidth::limits().transfers.get(&job.id).copied())
+        },
+    ))
+    .await;
+    // The scope drops the job's transfer limit; a paused job resumes under
+    // the same id, so it gets its limit back.
+    if let (Ok(Some(_)), Some(rate)) = (&outcome, transfer_limit) {
+        bandwidth::set_transfer(&job.id, rate);
+    }
+
+    if let Ok(mut map) = file_op_state.0.lock() {
+        map.remove(&id);
+    }
+    let _ = state.update(&app, |q| {
+        q.finish(&id, outcome.map_err(|e| e.to_string()));
+        Ok(())
+    });
+    pump(&app);
+}
+
+/// Copy a job's files, continuing from its checkpoint. Uploads to S3 keep
+/// their open multipart upload in the checkpoint and continue it after the
+/// parts already uploaded; S3 → S3 copies skip the objects the checkpoint
+/// lists and any others that already match; S3 → SFTP continues partly
+/// written files.
+async fn execute(
+    app: &AppHandle,
+    job: &TransferJob,
+    flags: &OpFlags,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+    on_checkpoint: &(dyn Fn(S3UploadCheckpoint) + Send + Sync),
+) -> Result<Option<S3UploadCheckpoint>, FmError> {
+    let s3_state = app.state::<S3State>();
+    let sftp_state = app.state::<SftpState>();
+    let first = job.sources.first().map(String::as_str).unwrap_or("");
+    let sources: Vec<String> = job
+        .sources
+        .iter()
+        .map(|p| native_path(&job.source_backend, p))
+        .collect();
+    let dest_dir = native_path(&job.dest_backend, &job.destination);
+    let checkpoint = job.checkpoint.clone();
+
+    match (job.source_backend.as_str(), job.dest_backend.as_str()) {
+        ("s3", "s3") => {
+            let src = s3_service(&s3_state, &job.source_id, first)?;
+            let dest = s3_service(&s3_state, &job.dest_id, &job.destination)?;
+            let policy = if checkpoint.is_some() { "skip_identical" } else { "overwrite" };
+            let result = src
+                .resume_copy_objects(
+                    &src.client,
+                    &src.bucket,
+                    &sources,
+                    &dest.client,
+                    &dest.bucket,
+                    &dest_dir,
+                    policy,
+                    checkpoint.map(Into::into),
+                    &job.id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                )
+                .await?;
+            Ok(result.map(S3UploadCheckpoint::from))
+        }
+        ("s3", "sftp") => {
+            let s3 = s3_service(&s3_state, &job.source_id, first)?;
+            let sftp = get_sftp_service(&sftp_state, &job.dest_id).await?;
+            let result = storage::s3_to_sftp(
+                &s3,
+                &sources,
+                &sftp,
+                &dest_dir,
+                checkpoint.is_some(),
+                &job.id,
+                &flags.cancel,
+                &flags.pause,
+                on_progress,
+            )
+            .await?;
+            Ok(result.map(S3UploadCheckpoint::from))
+        }
+        (_, "s3") => {
+            let source = resolve_backend(&job.source_backend, &job.source_id, first, &s3_state, &sftp_state).await?;
+            let s3 = s3_service(&s3_state, &job.dest_id, &job.destination)?;
+            storage::upload_to_s3(
+                source.as_ref(),
+                &sources,
+                &s3,
+                &dest_dir,
+                checkpoint,
+                &job.id,
+                &flags.cancel,
+                &flags.pause,
+                on_progress,
+                on_checkpoint,
+            )
+            .await
+        }
+        _ => {
+            let source = resolve_backend(&job.source_backend, &job.source_id, first, &s3_state, &sftp_state).await?;
+            let dest = resolve_backend(&job.dest_backend, &job.dest_id, &job.destination, &s3_state, &sftp_state).await?;
+            let result = storage::transfer(
+                source.as_ref(),
+                &sources,
+                dest.as_ref(),
+                &dest_dir,
+                checkpoint.map(Into::into),
+                &job.id,
+                &flags.cancel,
+                &flags.pause,
+                on_progress,
+            )
+            .await?;
+            Ok(result.map(S3UploadCheckpoint::from))
+        }
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Add a transfer to the end of the queue and start it when a slot is free.
+/// Paths are panel paths as for `transfer`; connection ids are "" for local.
+#[tauri::command]
+pub fn queue_add(
+    app: AppHandle,
+    state: State<'_, QueueState>,
+    source_backend: String, // "local" | "s3" | "sftp"
+    source_id: String,
+    sources: Vec<String>,
+    dest_backend: String,
+    dest_id: String,
+    destination: String,
+) -> Result<TransferJob, FmError> {
+    for backend in [&source_backend, &dest_backend] {
+        if !matches!(backend.as_str(), "local" | "s3" | "sftp") {
+            return Err(FmError::Other(format!("Unknown backend: {}", backend)));
+        }
+    }
+    if sources.is_empty() {
+        return Err(FmError::Other("Nothing to transfer".into()));
+    }
+
+    let job = TransferJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        source_backend,
+        source_id,
+        sources,
+        dest_backend,
+        dest_id,
+        destination,
+        status: "pending".into(),
+        error: None,
+        bytes_done: 0,
+        bytes_total: 0,
+        files_done: 0,
+        files_total: 0,
+        checkpoint: None,
+        created: chrono::Utc::now().timestamp_millis(),
+    };
+    state.update(&app, |q| {
+        q.jobs.push(job.clone());
+        Ok(())
+    })?;
+    pump(&app);
+    Ok(job)
+}
+
+/// All jobs in run order.
+#[tauri::command]
+pub fn queue_list(state: State<'_, QueueState>) -> Result<Vec<TransferJob>, FmError> {
+    state.jobs()
+}
+
+/// Move a job to position `index` in the run order.
+#[tauri::command]
+pub fn queue_reorder(
+    app: AppHandle,
+    state: State<'_, QueueState>,
+    id: String,
+    index: usize,
+) -> Result<Vec<TransferJob>, FmError> {
+    state.update(&app, |q| q.move_to(&id, index))?;
+    pump(&app);
+    state.jobs()
+}
+
+/// Queue a failed job again; it continues from where it stopped.
+#[tauri::command]
+pub fn queue_retry(app: AppHandle, state: State<'_, QueueState>, id: String) -> Result<(), FmError> {
+    state.update(&app, |q| q.retry(&id))?;
+    pump(&app);
+    Ok(())
+}
+
+/// Pause a job. A running job finishes the parts in flight and keeps its
+/// checkpoint; a pending one is held back.
+#[tauri::command]
+pub fn queue_pause(
+    app: AppHandle,
+    state: State<'_, QueueState>,
+    file_op_state: State<'_, FileOpState>,
+    id: String,
+) -> Result<(), FmError> {
+    if state.update(&app, |q| q.pause(&id))? {
+        let map = file_op_state.0.lock().map_err(|e| FmError::Other(e.to_string()))?;
+        if let Some(flags) = map.get(&id) {
+            flags.pause.store(true, Ordering::Relaxed);
+        }
+    }
+    Ok(())
+}
+
+/// Queue a paused job again.
+#[tauri::command]
+pub fn queue_resume(app: AppHandle, state: State<'_, QueueState>, id: String) -> Result<(), FmError> {
+    state.update(&app, |q| q.resume(&id))?;
+    pump(&app);
+    Ok(())
+}
+
+/// Remove a job, cancelling it if it runs. A multipart upload it left open
+/// is aborted when its S3 connection is available.
+#[tauri::command]
+pub async fn queue_remove(
+    app: AppHandle,
+    state: State<'_, QueueState>,
+    file_op_state: State<'_, FileOpState>,
+    s3_state: State<'_, S3State>,
+    id: String,
+) -> Result<(), FmError> {
+    let job = state.update(&app, |q| Ok(q.remove(&id)))?;
+    {
+        let map = file_op_state.0.lock().map_err(|e| FmError::Other(e.to_string()))?;
+        if let Some(flags) = map.get(&id) {
+            flags.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+    let open_upload = job.as_ref().and_then(|job| {
+        let cp = job.checkpoint.as_ref()?;
+        Some((job, cp.current_file_key.clone()?, cp.current_file_upload_id.clone()?))
+    });
+    if let Some((job, key, upload_id)) = open_upload {
+        if let Ok(s3) = s3_service(&s3_state, &job.dest_id, &job.destination) {
+            let _ = s3.abort_multipart_upload(&key, &upload_id).await;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the jobs that finished.
+#[tauri::command]
+pub fn queue_clear_done(app: AppHandle, state: State<'_, QueueState>) -> Result<(), FmError> {
+    state.update(&app, |q| {
+        q.clear_done();
+        Ok(())
+    })
+}
+
+/// Set how many jobs run at once (1–5).
+#[tauri::command]
+pub fn queue_set_concurrency(
+    app: AppHandle,
+    state: State<'_, QueueState>,
+    max_concurrent: usize,
+) -> Result<(), FmError> {
+    state.update(&app, |q| {
+        q.max_concurrent = max_concurrent.clamp(1, MAX_CONCURRENCY);
+        Ok(())
+    })?;
+    pump(&app);
+    Ok(())
+}
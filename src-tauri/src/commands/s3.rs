@@ -5,10 +5,11 @@ use crate::models::{
     S3BucketLogging, S3BucketOwnership, S3BucketVersioning, S3BucketWebsite, S3CorsRule,
     S3InventoryConfiguration, S3LifecycleRule, S3MultipartUpload, S3NotificationConfiguration,
     S3ObjectLegalHold, S3ObjectLockConfig, S3ObjectMetadata, S3ObjectProperties, S3ObjectRetention,
-    S3ObjectVersion, S3PublicAccessBlock, S3ReplicationConfiguration, S3Tag, SearchEvent,
-    TransferCheckpoint,
+    S3ObjectVersion, S3PublicAccessBlock, S3ReplicationConfiguration, S3Tag, RetryPolicy,
+    SearchEvent, TransferCheckpoint,
 };
-use crate::s3::{self, build_s3_client, s3err, S3State, BANDWIDTH_LIMIT};
+use crate::bandwidth;
+use crate::s3::{self, build_s3_client, retry, s3err, S3State};
 use crate::s3::service::{S3Bucket, S3Service};
 use std::collections::HashMap;
 use std::sync::atomic::AtomicBool;
@@ -111,20 +112,24 @@ pub async fn s3_connect(
 
     // Validate bucket access — public buckets often deny HeadBucket, so use ListObjectsV2
     if anonymous.unwrap_or(false) {
-        client
-            .list_objects_v2()
-            .bucket(&bucket)
-            .max_keys(1)
-            .send()
-            .await
-            .map_err(|e| s3err(format!("Cannot access public bucket '{}': {}", bucket, e)))?;
+        retry::send(|| {
+            client
+                .list_objects_v2()
+                .bucket(&bucket)
+                .max_keys(1)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("Cannot access public bucket '{}': {}", bucket, e)))?;
     } else {
-        client
-            .head_bucket()
-            .bucket(&bucket)
-            .send()
-            .await
-            .map_err(|e| s3err(format!("Cannot access bucket '{}': {}", bucket, e)))?;
+        retry::send(|| {
+            client
+                .head_bucket()
+                .bucket(&bucket)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("Cannot access bucket '{}': {}", bucket, e)))?;
     }
 
     let conn = s3::S3Connection {
@@ -179,8 +184,10 @@ pub async fn s3_download(
         map.insert(op_id.clone(), flags.clone());
     }
 
-    let result = service
-        .download(
+    let result = retry::counted(bandwidth::scoped(
+        &op_id,
+        &[&id],
+        service.download(
             &keys,
             &destination,
             &op_id,
@@ -188,8 +195,9 @@ pub async fn s3_download(
             &flags.pause,
             &|evt| { let _ = channel.send(evt); },
             password.as_deref(),
-        )
-        .await;
+        ),
+    ))
+    .await;
 
     if let Ok(mut map) = file_op_state.0.lock() {
         map.remove(&op_id);
@@ -219,8 +227,10 @@ pub async fn s3_upload(
         map.insert(op_id.clone(), flags.clone());
     }
 
-    let result = service
-        .upload(
+    let result = retry::counted(bandwidth::scoped(
+        &op_id,
+        &[&id],
+        service.upload(
             &sources,
             &dest_prefix,
             &op_id,
@@ -228,8 +238,9 @@ pub async fn s3_upload(
             &flags.pause,
             &|evt| { let _ = channel.send(evt); },
             None,
-        )
-        .await;
+        ),
+    ))
+    .await;
 
     if let Ok(mut map) = file_op_state.0.lock() {
         map.remove(&op_id);
@@ -262,8 +273,10 @@ pub async fn s3_upload_encrypted(
         map.insert(op_id.clone(), flags.clone());
     }
 
-    let result = service
-        .upload_encrypted(
+    let result = retry::counted(bandwidth::scoped(
+        &op_id,
+        &[&id],
+        service.upload_encrypted(
             &sources,
             &dest_prefix,
             &password,
@@ -272,8 +285,9 @@ pub async fn s3_upload_encrypted(
             &flags.cancel,
             &flags.pause,
             &|evt| { let _ = channel.send(evt); },
-        )
-        .await;
+        ),
+    ))
+    .await;
 
     if let Ok(mut map) = file_op_state.0.lock() {
         map.remove(&op_id);
@@ -291,6 +305,7 @@ pub async fn s3_copy_objects(
     src_keys: Vec<String>,
     dest_id: String,
     dest_prefix: String,
+    conflict_policy: Option<String>, // "overwrite" (default) | "skip" | "rename" | "skip_identical"
     channel: Channel<ProgressEvent>,
 ) -> Result<Option<TransferCheckpoint>, FmError> {
     let (src_client, src_bucket, dest_client, dest_bucket) = {
@@ -317,20 +332,24 @@ pub async fn s3_copy_objects(
     // Use a service just for the method call structure; the actual src/dest are passed explicitly
     let service = S3Service::new(src_client.clone(), src_bucket.clone());
 
-    let result = service
-        .copy_objects(
+    let result = retry::counted(bandwidth::scoped(
+        &op_id,
+        &[&src_id, &dest_id],
+        service.copy_objects(
             &src_client,
             &src_bucket,
             &src_keys,
             &dest_client,
             &dest_bucket,
             &dest_prefix,
+            conflict_policy.as_deref().unwrap_or("overwrite"),
             &op_id,
             &flags.cancel,
             &flags.pause,
             &|evt| { let _ = channel.send(evt); },
-        )
-        .await;
+        ),
+    ))
+    .await;
 
     if let Ok(mut map) = file_op_state.0.lock() {
         map.remove(&op_id);
@@ -914,10 +933,29 @@ pub async fn s3_put_bucket_logging(
 
 #[tauri::command]
 pub async fn s3_set_bandwidth_limit(bytes_per_sec: u64) -> Result<(), FmError> {
-    BANDWIDTH_LIMIT.store(bytes_per_sec, std::sync::atomic::Ordering::Relaxed);
+    bandwidth::set_global(bytes_per_sec);
+    Ok(())
+}
+
+/// Set how failed S3 requests are retried; applies to requests made from
+/// now on, including those of running transfers.
+#[tauri::command]
+pub async fn s3_set_retry_policy(policy: RetryPolicy) -> Result<(), FmError> {
+    if !(1..=10).contains(&policy.max_attempts) {
+        return Err(s3err("Attempts must be between 1 and 10"));
+    }
+    if policy.base_delay_ms > policy.max_delay_ms || policy.throttle_delay_ms > policy.max_delay_ms {
+        return Err(s3err("Base delays cannot exceed the maximum delay"));
+    }
+    retry::set_policy(policy);
     Ok(())
 }
 
+#[tauri::command]
+pub async fn s3_get_retry_policy() -> Result<RetryPolicy, FmError> {
+    Ok(retry::policy())
+}
+
 #[tauri::command]
 pub async fn s3_list_kms_keys(
     state: State<'_, S3State>,
@@ -1282,7 +1320,11 @@ async fn get_account_id(state: &State<'_, S3State>, id: &str) -> Result<String,
 fn get_s3control_client(state: &State<'_, S3State>, id: &str) -> Result<aws_sdk_s3control::Client, FmError> {
     let map = state.0.lock().map_err(|e| s3err(e.to_string()))?;
     let conn = map.get(id).ok_or_else(|| s3err("S3 connection not found"))?;
-    Ok(aws_sdk_s3control::Client::new(&conn.sdk_config))
+    // Requests are retried by `retry::send`, as for the S3 client.
+    let config = aws_sdk_s3control::config::Builder::from(&conn.sdk_config)
+        .retry_config(aws_sdk_s3control::config::retry::RetryConfig::disabled())
+        .build();
+    Ok(aws_sdk_s3control::Client::from_conf(config))
 }
 
 /// Get the bucket name from the connection.
@@ -1,57 +1,294 @@
+use crate::bandwidth;
 use crate::commands::file::{FileOpState, OpFlags};
-use crate::models::{DirListing, FmError, ProgressEvent, TransferCheckpoint};
-use crate::sftp::{self, sftperr, SftpService, SftpState};
-use crate::sftp::helpers::strip_sftp_prefix;
-use std::sync::atomic::AtomicBool;
+use crate::models::{
+    DirListing, ExecEvent, FmError, ProgressEvent, SftpAuthPrompt, SftpHealthEvent,
+    SftpJumpHost, SshConfigHost, TransferCheckpoint,
+};
+use crate::sftp::{self, sftperr, ResumeMode, SftpConnection, SftpService, SftpState};
+use crate::sftp::client::{AuthPrompter, ConnectionHealth, SshAuth, SshTarget};
+use crate::sftp::helpers::{shell_quote, strip_sftp_prefix};
+use crate::sftp::ssh_config;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::ipc::Channel;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often the health monitor checks that a connection is still up.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest wait between the health monitor's reconnect attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
 
 // ── Helper ───────────────────────────────────────────────────────────────────
 
 /// Extract an owned SftpService from the state, dropping the MutexGuard
-/// before any async work (same pattern as S3's get_service).
-fn get_service(state: &State<'_, SftpState>, id: &str) -> Result<SftpService, FmError> {
-    let map = state.0.lock().map_err(|e| sftperr(e.to_string()))?;
-    let conn = map.get(id).ok_or_else(|| sftperr("SFTP connection not found"))?;
-    Ok(SftpService::new(
-        conn.session.clone(),
+/// before any async work (same pattern as S3's get_service). A connection
+/// whose SSH session has dropped is transparently re-established first.
+pub(crate) async fn get_service(
+    state: &State<'_, SftpState>,
+    id: &str,
+) -> Result<SftpService, FmError> {
+    let health = {
+        let map = state.0.lock().map_err(|e| sftperr(e.to_string()))?;
+        let conn = map.get(id).ok_or_else(|| sftperr("SFTP connection not found"))?;
+        if !conn.is_closed() {
+            return Ok(service_for(conn));
+        }
+        conn.health.clone()
+    };
+    reconnect(state, id, &health, 1).await
+}
+
+fn service_for(conn: &SftpConnection) -> SftpService {
+    SftpService::new(
+        conn.session(),
+        conn.ssh_handle.clone(),
         conn.host.clone(),
         conn.port,
-    ))
+    )
+}
+
+/// Replace the dropped connection `id` with a new one built from its stored
+/// settings. Callers arriving while a reconnect is running wait for it and
+/// reuse the result.
+async fn reconnect(
+    state: &SftpState,
+    id: &str,
+    health: &Arc<ConnectionHealth>,
+    attempt: u32,
+) -> Result<SftpService, FmError> {
+    let _guard = health.reconnecting.lock().await;
+    let (target, jump_hosts) = {
+        let map = state.0.lock().map_err(|e| sftperr(e.to_string()))?;
+        let conn = map.get(id).ok_or_else(|| sftperr("SFTP connection not found"))?;
+        if !conn.is_closed() {
+            return Ok(service_for(conn));
+        }
+        (conn.target.clone(), conn.jump_hosts.clone())
+    };
+
+    let event = |status: &str, message: Option<String>| SftpHealthEvent {
+        id: id.to_string(),
+        status: status.to_string(),
+        attempt,
+        message,
+    };
+    if needs_user_input(&target, &jump_hosts) {
+        // Prompts would pop up unasked and one-time codes cannot be replayed,
+        // so the user has to connect again.
+        let message = "Connection lost; connect again to answer the login prompts".to_string();
+        health.notify(event("disconnected", Some(message.clone())));
+        return Err(sftperr(message));
+    }
+    health.notify(event("reconnecting", None));
+    let mut conn = match sftp::client::build_sftp_client(&target, &jump_hosts).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            health.notify(event("disconnected", Some(e.to_string())));
+            return Err(e);
+        }
+    };
+    conn.health = health.clone();
+    let svc = service_for(&conn);
+
+    {
+        let mut map = state.0.lock().map_err(|e| sftperr(e.to_string()))?;
+        // The user may have disconnected while we were reconnecting.
+        let slot = map.get_mut(id).ok_or_else(|| sftperr("SFTP connection not found"))?;
+        *slot = conn;
+    }
+    health.notify(event("connected", None));
+    Ok(svc)
+}
+
+/// Whether logging in to `target` or a jump host needs answers from the user.
+fn needs_user_input(target: &SshTarget, jump_hosts: &[SshTarget]) -> bool {
+    std::iter::once(target)
+        .chain(jump_hosts)
+        .any(|t| t.auth.method == "keyboard-interactive")
+}
+
+/// Watch connection `id` in the background and reconnect it with backoff when
+/// its SSH session drops, so it is ready before the next command. Stops once
+/// the id is disconnected or replaced by a new `sftp_connect`, or after
+/// reporting the drop of a keyboard-interactive login.
+fn spawn_health_monitor(app_handle: AppHandle, id: String, health: Arc<ConnectionHealth>) {
+    tokio::spawn(async move {
+        let state = app_handle.state::<SftpState>();
+        let mut attempt = 0;
+        loop {
+            let delay = if attempt == 0 {
+                HEALTH_CHECK_INTERVAL
+            } else {
+                (HEALTH_CHECK_INTERVAL * 2u32.pow(attempt.min(8) - 1)).min(MAX_RECONNECT_DELAY)
+            };
+            tokio::time::sleep(delay).await;
+
+            let (closed, interactive) = {
+                let Ok(map) = state.0.lock() else { return };
+                match map.get(&id) {
+                    Some(conn) if Arc::ptr_eq(&conn.health, &health) => {
+                        (conn.is_closed(), needs_user_input(&conn.target, &conn.jump_hosts))
+                    }
+                    _ => return,
+                }
+            };
+            if !closed {
+                attempt = 0;
+                continue;
+            }
+            attempt += 1;
+            let reconnected = reconnect(&state, &id, &health, attempt).await.is_ok();
+            if interactive {
+                return;
+            }
+            if reconnected {
+                attempt = 0;
+            }
+        }
+    });
+}
+
+/// Map the optional `resume`/`verify` command flags to a ResumeMode.
+fn resume_mode(resume: Option<bool>, verify: Option<bool>) -> ResumeMode {
+    match (resume.unwrap_or(false), verify.unwrap_or(false)) {
+        (false, _) => ResumeMode::Restart,
+        (true, false) => ResumeMode::Append,
+        (true, true) => ResumeMode::Verify,
+    }
 }
 
 // ── Commands ────────────────────────────────────────────────────────────────
 
+/// Connect to `host`, which may be a `~/.ssh/config` alias. Port, user and
+/// auth settings not given explicitly are taken from the config: auth
+/// defaults to the first existing `IdentityFile`, otherwise the SSH agent.
+///
+/// `jump_hosts` chains the connection through bastions, outermost first. When
+/// omitted, the alias's `ProxyJump` is used; an empty list connects directly.
+///
+/// Keyboard-interactive challenges (password, OTP) are emitted as
+/// `"sftp-auth-prompt"` events; answer them with `sftp_auth_respond`.
+/// Key auth also offers the OpenSSH certificate at `cert_path`, or
+/// `{key_path}-cert.pub` / the config's `CertificateFile` if present.
+///
+/// The settings are kept in memory to reconnect automatically if the session
+/// drops; changes are reported as `"sftp-health"` events. Keyboard-interactive
+/// logins are not repeated unasked: they report "disconnected" instead.
 #[tauri::command]
 pub async fn sftp_connect(
+    app_handle: AppHandle,
     state: State<'_, SftpState>,
     id: String,
     host: String,
-    port: u16,
-    username: String,
-    auth_method: String,
+    port: Option<u16>,
+    username: Option<String>,
+    auth_method: Option<String>,
     password: Option<String>,
     key_path: Option<String>,
     key_passphrase: Option<String>,
+    cert_path: Option<String>,
+    jump_hosts: Option<Vec<SftpJumpHost>>,
 ) -> Result<String, FmError> {
-    let conn = sftp::client::build_sftp_client(
+    let (mut target, proxy_jump) = resolve_target(
         &host,
         port,
-        &username,
-        &auth_method,
-        password.as_deref(),
-        key_path.as_deref(),
-        key_passphrase.as_deref(),
-    )
-    .await?;
+        username,
+        auth_method,
+        password,
+        key_path,
+        key_passphrase,
+        cert_path,
+    );
+
+    let mut jumps: Vec<SshTarget> = match jump_hosts {
+        Some(hops) => hops
+            .into_iter()
+            .map(|j| {
+                resolve_target(
+                    &j.host,
+                    j.port,
+                    j.username,
+                    j.auth_method,
+                    j.password,
+                    j.key_path,
+                    j.key_passphrase,
+                    j.cert_path,
+                )
+                .0
+            })
+            .collect(),
+        None => proxy_jump
+            .as_deref()
+            .map(ssh_config::parse_proxy_jump)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|j| resolve_target(&j.host, j.port, j.user, None, None, None, None, None).0)
+            .collect(),
+    };
+
+    let emitter = app_handle.clone();
+    let prompter: AuthPrompter = Arc::new(move |prompt: SftpAuthPrompt| {
+        let _ = emitter.emit("sftp-auth-prompt", &prompt);
+    });
+    for hop in jumps.iter_mut().chain(std::iter::once(&mut target)) {
+        hop.auth.prompter = Some(prompter.clone());
+    }
+
+    let mut conn = sftp::client::build_sftp_client(&target, &jumps).await?;
+    let emitter = app_handle.clone();
+    let health = Arc::new(ConnectionHealth::new(move |evt| {
+        let _ = emitter.emit("sftp-health", &evt);
+    }));
+    conn.health = health.clone();
 
     let home_dir = conn.home_dir.clone();
-    let mut map = state.0.lock().map_err(|e| sftperr(e.to_string()))?;
-    map.insert(id, conn);
+    {
+        let mut map = state.0.lock().map_err(|e| sftperr(e.to_string()))?;
+        map.insert(id.clone(), conn);
+    }
+    spawn_health_monitor(app_handle, id, health);
     Ok(home_dir)
 }
 
+/// Fill in unset connection settings for `host` from `~/.ssh/config`.
+/// Also returns the host's `ProxyJump`, if any.
+fn resolve_target(
+    host: &str,
+    port: Option<u16>,
+    username: Option<String>,
+    auth_method: Option<String>,
+    password: Option<String>,
+    key_path: Option<String>,
+    key_passphrase: Option<String>,
+    cert_path: Option<String>,
+) -> (SshTarget, Option<String>) {
+    let cfg = ssh_config::resolve(host);
+    let cert_path = cert_path.or_else(|| cfg.existing_certificate());
+    let key_path = key_path.or_else(|| cfg.existing_identity());
+    let method = auth_method.unwrap_or_else(|| {
+        if key_path.is_some() { "key" } else { "agent" }.to_string()
+    });
+    let target = SshTarget {
+        host: cfg.effective_host(host),
+        port: port.or(cfg.port).unwrap_or(22),
+        username: username
+            .or(cfg.user)
+            .unwrap_or_else(ssh_config::local_user),
+        auth: SshAuth {
+            method,
+            password,
+            key_path,
+            key_passphrase,
+            cert_path,
+            identities_only: cfg.identities_only,
+            prompter: None,
+        },
+    };
+    (target, cfg.proxy_jump)
+}
+
 #[tauri::command]
 pub async fn sftp_disconnect(state: State<'_, SftpState>, id: String) -> Result<(), FmError> {
     let conn = {
@@ -59,7 +296,9 @@ pub async fn sftp_disconnect(state: State<'_, SftpState>, id: String) -> Result<
         map.remove(&id)
     };
     if let Some(conn) = conn {
-        let _ = conn.session.close().await;
+        for session in &conn.sessions {
+            let _ = session.close().await;
+        }
     }
     Ok(())
 }
@@ -70,7 +309,7 @@ pub async fn sftp_list_objects(
     id: String,
     path: String,
 ) -> Result<DirListing, FmError> {
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_path = strip_sftp_prefix(&path);
     svc.list_objects(remote_path).await
 }
@@ -81,7 +320,7 @@ pub async fn sftp_delete(
     id: String,
     paths: Vec<String>,
 ) -> Result<(), FmError> {
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_paths: Vec<String> = paths.iter().map(|p| strip_sftp_prefix(p).to_string()).collect();
     svc.delete(&remote_paths).await
 }
@@ -93,7 +332,7 @@ pub async fn sftp_rename(
     path: String,
     new_name: String,
 ) -> Result<(), FmError> {
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_path = strip_sftp_prefix(&path);
     svc.rename(remote_path, &new_name).await
 }
@@ -104,7 +343,7 @@ pub async fn sftp_create_folder(
     id: String,
     path: String,
 ) -> Result<(), FmError> {
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_path = strip_sftp_prefix(&path);
     svc.create_folder(remote_path).await
 }
@@ -117,6 +356,8 @@ pub async fn sftp_download(
     op_id: String,
     keys: Vec<String>,
     destination: String,
+    resume: Option<bool>,
+    verify: Option<bool>,
     channel: Channel<ProgressEvent>,
 ) -> Result<Option<TransferCheckpoint>, FmError> {
     let flags = {
@@ -132,18 +373,23 @@ pub async fn sftp_download(
         flags
     };
 
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_paths: Vec<String> = keys.iter().map(|p| strip_sftp_prefix(p).to_string()).collect();
 
-    let result = svc
-        .download(
+    let result = bandwidth::scoped(
+        &op_id,
+        &[&id],
+        svc.download(
             &remote_paths,
             &destination,
             &op_id,
             &flags.cancel,
+            &flags.pause,
             &|evt| { let _ = channel.send(evt); },
-        )
-        .await;
+            resume_mode(resume, verify),
+        ),
+    )
+    .await;
 
     // Clean up
     file_op_state
@@ -163,6 +409,8 @@ pub async fn sftp_upload(
     op_id: String,
     sources: Vec<String>,
     remote_prefix: String,
+    resume: Option<bool>,
+    verify: Option<bool>,
     channel: Channel<ProgressEvent>,
 ) -> Result<Option<TransferCheckpoint>, FmError> {
     let flags = {
@@ -178,20 +426,74 @@ pub async fn sftp_upload(
         flags
     };
 
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_dest = strip_sftp_prefix(&remote_prefix);
 
-    let result = svc
-        .upload(
+    let result = bandwidth::scoped(
+        &op_id,
+        &[&id],
+        svc.upload(
             &sources,
             remote_dest,
             &op_id,
             &flags.cancel,
+            &flags.pause,
             &|evt| { let _ = channel.send(evt); },
+            resume_mode(resume, verify),
+        ),
+    )
+    .await;
+
+    // Clean up
+    file_op_state
+        .0
+        .lock()
+        .map_err(|e| sftperr(e.to_string()))?
+        .remove(&op_id);
+
+    result
+}
+
+/// Copy files and directories to another directory on the same server.
+/// The data never leaves the server; see `SftpService::copy`.
+#[tauri::command]
+pub async fn sftp_copy(
+    state: State<'_, SftpState>,
+    file_op_state: State<'_, FileOpState>,
+    id: String,
+    op_id: String,
+    keys: Vec<String>,
+    destination: String,
+    channel: Channel<ProgressEvent>,
+) -> Result<(), FmError> {
+    let flags = {
+        let mut ops = file_op_state
+            .0
+            .lock()
+            .map_err(|e| sftperr(e.to_string()))?;
+        let flags = Arc::new(OpFlags {
+            cancel: AtomicBool::new(false),
+            pause: AtomicBool::new(false),
+        });
+        ops.insert(op_id.clone(), flags.clone());
+        flags
+    };
+
+    let svc = get_service(&state, &id).await?;
+    let paths: Vec<String> = keys.iter().map(|p| strip_sftp_prefix(p).to_string()).collect();
+
+    let result = svc
+        .copy(
+            &paths,
+            strip_sftp_prefix(&destination),
+            &op_id,
+            &flags.cancel,
+            &|evt| {
+                let _ = channel.send(evt);
+            },
         )
         .await;
 
-    // Clean up
     file_op_state
         .0
         .lock()
@@ -207,7 +509,7 @@ pub async fn sftp_download_temp(
     id: String,
     path: String,
 ) -> Result<String, FmError> {
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_path = strip_sftp_prefix(&path);
     svc.download_temp(remote_path).await
 }
@@ -219,7 +521,7 @@ pub async fn sftp_put_text(
     path: String,
     content: String,
 ) -> Result<(), FmError> {
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_path = strip_sftp_prefix(&path);
     svc.put_text(remote_path, &content).await
 }
@@ -230,30 +532,182 @@ pub async fn sftp_head(
     id: String,
     path: String,
 ) -> Result<crate::models::FileProperties, FmError> {
-    let svc = get_service(&state, &id)?;
+    let svc = get_service(&state, &id).await?;
     let remote_path = strip_sftp_prefix(&path);
-    let meta = svc.stat(remote_path).await?;
-    let name = remote_path.rsplit('/').next().unwrap_or(remote_path);
+    let sym_meta = svc.lstat(remote_path).await?;
+    let is_symlink = sym_meta.is_symlink();
+
+    // Like local properties: size/type describe the link target, while
+    // permissions and ownership describe the entry itself.
+    let (meta, symlink_target) = if is_symlink {
+        let target = svc.read_link(remote_path).await.ok();
+        let meta = svc.stat(remote_path).await.unwrap_or_else(|_| sym_meta.clone());
+        (meta, target)
+    } else {
+        (sym_meta.clone(), None)
+    };
+
+    let (owner, group) = svc.owner_names(sym_meta.uid, sym_meta.gid).await;
+    let name = remote_path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(remote_path);
     Ok(crate::models::FileProperties {
         name: name.to_string(),
         path: path.clone(),
         size: meta.size.unwrap_or(0),
         is_dir: meta.is_dir(),
-        is_symlink: meta.is_symlink(),
-        symlink_target: None,
+        is_symlink,
+        symlink_target,
+        // SFTP v3 has no birth time.
         created: 0,
         modified: meta.mtime.map(|t| t as i64 * 1000).unwrap_or(0),
         accessed: meta.atime.map(|t| t as i64 * 1000).unwrap_or(0),
-        permissions: meta.permissions.unwrap_or(0),
-        owner: meta.uid.map(|u| u.to_string()).unwrap_or_default(),
-        group: meta.gid.map(|g| g.to_string()).unwrap_or_default(),
-        kind: if meta.is_dir() {
-            "Directory"
-        } else if meta.is_symlink() {
+        permissions: sym_meta.permissions.unwrap_or(0),
+        owner,
+        group,
+        kind: if is_symlink {
             "Symlink"
+        } else if meta.is_dir() {
+            "Directory"
         } else {
             "File"
         }
         .to_string(),
     })
 }
+
+/// Set permission bits on a remote file (e.g. 0o755), like `set_permissions`
+/// for local files.
+#[tauri::command]
+pub async fn sftp_set_permissions(
+    state: State<'_, SftpState>,
+    id: String,
+    path: String,
+    mode: u32,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id).await?;
+    svc.set_permissions(strip_sftp_prefix(&path), mode).await
+}
+
+/// Change the numeric owner and/or group of a remote file. Usually needs the
+/// server login to be root.
+#[tauri::command]
+pub async fn sftp_chown(
+    state: State<'_, SftpState>,
+    id: String,
+    path: String,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id).await?;
+    svc.chown(strip_sftp_prefix(&path), uid, gid).await
+}
+
+/// Create a symlink at `path` pointing to `target`. The target is stored
+/// verbatim, so it may be relative to the link's directory.
+#[tauri::command]
+pub async fn sftp_symlink(
+    state: State<'_, SftpState>,
+    id: String,
+    path: String,
+    target: String,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id).await?;
+    svc.symlink(strip_sftp_prefix(&path), strip_sftp_prefix(&target))
+        .await
+}
+
+/// Read the target of a remote symlink.
+#[tauri::command]
+pub async fn sftp_readlink(
+    state: State<'_, SftpState>,
+    id: String,
+    path: String,
+) -> Result<String, FmError> {
+    let svc = get_service(&state, &id).await?;
+    svc.read_link(strip_sftp_prefix(&path)).await
+}
+
+/// Answer a keyboard-interactive challenge from an `"sftp-auth-prompt"`
+/// event, one response per prompt. Omit `responses` to cancel the login.
+#[tauri::command]
+pub fn sftp_auth_respond(request_id: String, responses: Option<Vec<String>>) -> Result<(), FmError> {
+    sftp::client::answer_prompt(&request_id, responses)
+}
+
+/// Accept a host key the user confirmed after an "Unknown host key" error and
+/// append it to `~/.ssh/known_hosts`. `fingerprint` must match the key the
+/// server presented (as shown in the error).
+#[tauri::command]
+pub fn sftp_trust_host_key(
+    host: String,
+    port: u16,
+    fingerprint: String,
+) -> Result<(), FmError> {
+    sftp::known_hosts::trust_pending(&host, port, &fingerprint)
+}
+
+/// Run `command` on the remote host over the connection's SSH session,
+/// streaming its output. `cwd` is entered first if given. Cancel via `op_id`.
+#[tauri::command]
+pub async fn sftp_exec(
+    state: State<'_, SftpState>,
+    file_op_state: State<'_, FileOpState>,
+    id: String,
+    op_id: String,
+    command: String,
+    cwd: Option<String>,
+    channel: Channel<ExecEvent>,
+) -> Result<(), FmError> {
+    let flags = {
+        let mut ops = file_op_state
+            .0
+            .lock()
+            .map_err(|e| sftperr(e.to_string()))?;
+        let flags = Arc::new(OpFlags {
+            cancel: AtomicBool::new(false),
+            pause: AtomicBool::new(false),
+        });
+        ops.insert(op_id.clone(), flags.clone());
+        flags
+    };
+
+    let svc = get_service(&state, &id).await?;
+    let command = match cwd {
+        Some(dir) => format!("cd {} && {}", shell_quote(strip_sftp_prefix(&dir)), command),
+        None => command,
+    };
+
+    let result = svc
+        .exec_stream(&command, &flags.cancel, &|evt| { let _ = channel.send(evt); })
+        .await;
+
+    // Clean up
+    file_op_state
+        .0
+        .lock()
+        .map_err(|e| sftperr(e.to_string()))?
+        .remove(&op_id);
+
+    result
+}
+
+/// Set how many files SFTP transfers move at once and how many read/write
+/// requests each file keeps in flight.
+#[tauri::command]
+pub async fn sftp_set_transfer_concurrency(
+    files: usize,
+    requests_per_file: usize,
+) -> Result<(), FmError> {
+    sftp::service::CONCURRENT_FILES.store(files.max(1), Ordering::Relaxed);
+    sftp::service::REQUESTS_PER_FILE.store(requests_per_file.max(1), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Concrete host aliases from `~/.ssh/config` for the connection manager.
+#[tauri::command]
+pub fn sftp_list_config_hosts() -> Result<Vec<SshConfigHost>, FmError> {
+    Ok(ssh_config::list_hosts())
+}
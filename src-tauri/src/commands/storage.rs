@@ -0,0 +1,235 @@
+use crate::bandwidth;
+use crate::commands::file::{FileOpState, OpFlags};
+use crate::commands::sftp::get_service as get_sftp_service;
+use crate::models::{FmError, ProgressEvent, S3UploadCheckpoint, TransferCheckpoint};
+use crate::s3::{retry, s3err, S3Service, S3State};
+use crate::sftp::helpers::strip_sftp_prefix;
+use crate::sftp::SftpState;
+use crate::storage::{self, LocalStorage, StorageBackend};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tauri::ipc::Channel;
+use tauri::State;
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Storage backend for a panel path on `backend` ("local" | "s3" | "sftp").
+/// An `s3://bucket/...` path selects that bucket on the connection.
+pub(crate) async fn resolve_backend(
+    backend: &str,
+    conn_id: &str,
+    path: &str,
+    s3_state: &State<'_, S3State>,
+    sftp_state: &State<'_, SftpState>,
+) -> Result<Box<dyn StorageBackend>, FmError> {
+    match backend {
+        "local" => Ok(Box::new(LocalStorage)),
+        "s3" => Ok(Box::new(s3_service(s3_state, conn_id, path)?)),
+        "sftp" => Ok(Box::new(get_sftp_service(sftp_state, conn_id).await?)),
+        _ => Err(FmError::Other(format!("Unknown backend: {}", backend))),
+    }
+}
+
+/// S3 service for a connection. An `s3://bucket/...` path selects that bucket
+/// instead of the connection's own.
+pub(crate) fn s3_service(s3_state: &State<'_, S3State>, conn_id: &str, path: &str) -> Result<S3Service, FmError> {
+    let map = s3_state.0.lock().map_err(|e| s3err(e.to_string()))?;
+    let conn = map.get(conn_id).ok_or_else(|| s3err("S3 connection not found"))?;
+    let bucket = path
+        .strip_prefix("s3://")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|b| !b.is_empty())
+        .unwrap_or(&conn.bucket)
+        .to_string();
+    Ok(S3Service::new(conn.client.clone(), bucket))
+}
+
+/// Backend-native form of a panel path: the key for S3, the remote path for
+/// SFTP, unchanged for local.
+pub(crate) fn native_path(backend: &str, path: &str) -> String {
+    match backend {
+        "s3" => match path.strip_prefix("s3://") {
+            Some(rest) => rest.split_once('/').map(|(_, key)| key).unwrap_or("").to_string(),
+            None => path.to_string(),
+        },
+        "sftp" => strip_sftp_prefix(path).to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Panel path of the directory containing `path`.
+fn parent_dir(path: &str) -> &str {
+    let clean = path.trim_end_matches('/');
+    clean.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+fn register_op(file_op_state: &State<'_, FileOpState>, op_id: &str) -> Result<Arc<OpFlags>, FmError> {
+    let mut ops = file_op_state
+        .0
+        .lock()
+        .map_err(|e| FmError::Other(e.to_string()))?;
+    let flags = Arc::new(OpFlags {
+        cancel: AtomicBool::new(false),
+        pause: AtomicBool::new(false),
+    });
+    ops.insert(op_id.to_string(), flags.clone());
+    Ok(flags)
+}
+
+fn unregister_op(file_op_state: &State<'_, FileOpState>, op_id: &str) -> Result<(), FmError> {
+    file_op_state
+        .0
+        .lock()
+        .map_err(|e| FmError::Other(e.to_string()))?
+        .remove(op_id);
+    Ok(())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Copy files and directories into `destination` between any two backends,
+/// e.g. S3 → SFTP. Data is streamed from source to destination without a
+/// local temp copy. Paths are panel paths (local, `s3://bucket/key` or
+/// `sftp://host:port/path`); connection ids are "" for local. A checkpoint
+/// returned on pause skips the files already copied.
+#[tauri::command]
+pub async fn transfer(
+    s3_state: State<'_, S3State>,
+    sftp_state: State<'_, SftpState>,
+    file_op_state: State<'_, FileOpState>,
+    op_id: String,
+    source_backend: String, // "local" | "s3" | "sftp"
+    source_id: String,
+    sources: Vec<String>,
+    dest_backend: String,
+    dest_id: String,
+    destination: String,
+    checkpoint: Option<TransferCheckpoint>,
+    channel: Channel<ProgressEvent>,
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    if source_backend == dest_backend
+        && source_id == dest_id
+        && sources
+            .iter()
+            .any(|p| parent_dir(p) == destination.trim_end_matches('/'))
+    {
+        return Err(FmError::Other(
+            "Source and destination directory are the same".into(),
+        ));
+    }
+
+    let first = sources.first().map(String::as_str).unwrap_or("");
+    let source = resolve_backend(&source_backend, &source_id, first, &s3_state, &sftp_state).await?;
+    let dest = resolve_backend(&dest_backend, &dest_id, &destination, &s3_state, &sftp_state).await?;
+    let source_paths: Vec<String> = sources
+        .iter()
+        .map(|p| native_path(&source_backend, p))
+        .collect();
+    let dest_dir = native_path(&dest_backend, &destination);
+
+    let flags = register_op(&file_op_state, &op_id)?;
+    let result = retry::counted(bandwidth::scoped(
+        &op_id,
+        &[&source_id, &dest_id],
+        storage::transfer(
+            source.as_ref(),
+            &source_paths,
+            dest.as_ref(),
+            &dest_dir,
+            checkpoint,
+            &op_id,
+            &flags.cancel,
+            &flags.pause,
+            &|evt| { let _ = channel.send(evt); },
+        ),
+    ))
+    .await;
+
+    unregister_op(&file_op_state, &op_id)?;
+    result
+}
+
+/// Stream S3 objects straight into an SFTP directory. With `resume`, files
+/// already partly on the server are continued with ranged GETs.
+#[tauri::command]
+pub async fn s3_to_sftp(
+    s3_state: State<'_, S3State>,
+    sftp_state: State<'_, SftpState>,
+    file_op_state: State<'_, FileOpState>,
+    op_id: String,
+    s3_id: String,
+    keys: Vec<String>,
+    sftp_id: String,
+    destination: String,
+    resume: Option<bool>,
+    channel: Channel<ProgressEvent>,
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let first = keys.first().map(String::as_str).unwrap_or("");
+    let s3 = s3_service(&s3_state, &s3_id, first)?;
+    let sftp = get_sftp_service(&sftp_state, &sftp_id).await?;
+    let keys: Vec<String> = keys.iter().map(|k| native_path("s3", k)).collect();
+    let dest_dir = native_path("sftp", &destination);
+
+    let flags = register_op(&file_op_state, &op_id)?;
+    let result = retry::counted(bandwidth::scoped(
+        &op_id,
+        &[&s3_id, &sftp_id],
+        storage::s3_to_sftp(
+            &s3,
+            &keys,
+            &sftp,
+            &dest_dir,
+            resume.unwrap_or(false),
+            &op_id,
+            &flags.cancel,
+            &flags.pause,
+            &|evt| { let _ = channel.send(evt); },
+        ),
+    ))
+    .await;
+
+    unregister_op(&file_op_state, &op_id)?;
+    result
+}
+
+/// Stream SFTP files into an S3 prefix, using multipart uploads for large
+/// files. A checkpoint returned on pause continues the open upload.
+#[tauri::command]
+pub async fn sftp_to_s3(
+    s3_state: State<'_, S3State>,
+    sftp_state: State<'_, SftpState>,
+    file_op_state: State<'_, FileOpState>,
+    op_id: String,
+    sftp_id: String,
+    paths: Vec<String>,
+    s3_id: String,
+    destination: String,
+    checkpoint: Option<S3UploadCheckpoint>,
+    channel: Channel<ProgressEvent>,
+) -> Result<Option<S3UploadCheckpoint>, FmError> {
+    let sftp = get_sftp_service(&sftp_state, &sftp_id).await?;
+    let s3 = s3_service(&s3_state, &s3_id, &destination)?;
+    let paths: Vec<String> = paths.iter().map(|p| native_path("sftp", p)).collect();
+    let dest_prefix = native_path("s3", &destination);
+
+    let flags = register_op(&file_op_state, &op_id)?;
+    let result = retry::counted(bandwidth::scoped(
+        &op_id,
+        &[&sftp_id, &s3_id],
+        storage::sftp_to_s3(
+            &sftp,
+            &paths,
+            &s3,
+            &dest_prefix,
+            checkpoint,
+            &op_id,
+            &flags.cancel,
+            &flags.pause,
+            &|evt| { let _ = channel.send(evt); },
+        ),
+    ))
+    .await;
+
+    unregister_op(&file_op_state, &op_id)?;
+    result
+}
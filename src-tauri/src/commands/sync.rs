@@ -1,10 +1,22 @@
-use crate::s3::S3State;
-use crate::models::{FmError, SyncEntry, SyncEvent};
+use crate::bandwidth;
+use crate::commands::file::{FileOpState, OpFlags};
+use crate::commands::sftp::get_service as get_sftp_service;
+use crate::commands::webdav::get_service as get_webdav_service;
+use crate::s3::{copy_single_or_multipart, retry, S3Service, S3State};
+use crate::sftp::helpers::strip_sftp_prefix;
+use crate::sftp::{ResumeMode, SftpService, SftpState};
+use crate::webdav::helpers::strip_dav_prefix;
+use crate::webdav::{WebDavService, WebDavState};
+use crate::storage::{self, batch::parent_path, StorageBackend};
+use crate::sync::{classify_two_way, FileState, SnapshotEntry, SyncSnapshot};
+use crate::models::{
+    FmError, ProgressEvent, SyncAction, SyncApplyResult, SyncEntry, SyncEvent, TransferCheckpoint,
+};
 use glob_match::glob_match;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::UNIX_EPOCH;
@@ -18,21 +30,32 @@ pub struct SyncState(pub Mutex<HashMap<String, Arc<AtomicBool>>>);
 /// File info: (size, modified_ms, optional etag/md5)
 type FileInfo = (u64, i64, Option<String>);
 
+/// One side of a sync, resolved from (backend, path, connection id).
+enum SyncEndpoint {
+    Local(PathBuf),
+    S3 { service: S3Service, prefix: String },
+    Sftp { service: SftpService, root: String },
+    WebDav { service: WebDavService, root: String },
+}
+
 // ── Commands ────────────────────────────────────────────────────────────
 
 #[tauri::command]
 pub async fn sync_diff(
     id: String,
-    source_backend: String,  // "local" | "s3"
-    source_path: String,     // local dir path or s3://bucket/prefix
-    source_s3_id: String,    // "" for local
+    source_backend: String,  // "local" | "s3" | "sftp" | "webdav"
+    source_path: String,     // local dir path, s3://bucket/prefix, sftp://host:port/path or webdav://host/path
+    source_s3_id: String,    // S3, SFTP or WebDAV connection id, "" for local
     dest_backend: String,
     dest_path: String,
     dest_s3_id: String,
     exclude_patterns: Vec<String>,
     compare_mode: String,    // "size_mtime" | "checksum"
+    mode: Option<String>,    // "mirror" (default) | "two_way"
     channel: Channel<SyncEvent>,
     s3_state: State<'_, S3State>,
+    sftp_state: State<'_, SftpState>,
+    webdav_state: State<'_, WebDavState>,
     sync_state: State<'_, SyncState>,
 ) -> Result<(), FmError> {
     let cancel_flag = Arc::new(AtomicBool::new(false));
@@ -45,6 +68,9 @@ pub async fn sync_diff(
     }
 
     let use_checksum = compare_mode == "checksum";
+    let on_warning = |message: String| {
+        let _ = channel.send(SyncEvent::Warning { message });
+    };
 
     // Collect source files
     let mut source_files = collect_files(
@@ -53,6 +79,9 @@ pub async fn sync_diff(
         &source_s3_id,
         use_checksum,
         &s3_state,
+        &sftp_state,
+        &webdav_state,
+        &on_warning,
     )
     .await?;
 
@@ -68,6 +97,9 @@ pub async fn sync_diff(
         &dest_s3_id,
         use_checksum,
         &s3_state,
+        &sftp_state,
+        &webdav_state,
+        &on_warning,
     )
     .await?;
 
@@ -83,67 +115,40 @@ pub async fn sync_diff(
     }
 
     // Compare and stream entries
+    let entries = if mode.as_deref() == Some("two_way") {
+        let snapshot = SyncSnapshot::load(
+            &pair_side(&source_backend, &source_s3_id, &source_path),
+            &pair_side(&dest_backend, &dest_s3_id, &dest_path),
+        )?;
+        diff_two_way(&source_files, &dest_files, &snapshot)
+    } else {
+        diff_files(&source_files, &dest_files, use_checksum)
+    };
+
     let mut scanned: u32 = 0;
     let mut new_count: u32 = 0;
     let mut modified: u32 = 0;
     let mut deleted: u32 = 0;
+    let mut conflicts: u32 = 0;
     let mut same_count: u32 = 0;
 
-    // Keys in source
-    for (rel_path, (src_size, src_modified, ref src_etag)) in &source_files {
+    for entry in entries {
         if cancel_flag.load(Ordering::Relaxed) {
             cleanup(&sync_state, &id);
             return Ok(());
         }
 
-        let entry = if let Some((dst_size, dst_modified, ref dst_etag)) = dest_files.get(rel_path) {
-            let is_modified = if use_checksum {
-                files_differ_checksum(
-                    *src_size, src_etag.as_deref(),
-                    *dst_size, dst_etag.as_deref(),
-                )
-            } else {
-                src_size != dst_size || *src_modified > *dst_modified
-            };
-
-            if is_modified {
-                modified += 1;
-                SyncEntry {
-                    relative_path: rel_path.clone(),
-                    status: "modified".to_string(),
-                    source_size: *src_size,
-                    dest_size: *dst_size,
-                    source_modified: *src_modified,
-                    dest_modified: *dst_modified,
-                    source_etag: src_etag.clone().unwrap_or_default(),
-                    dest_etag: dst_etag.clone().unwrap_or_default(),
-                }
-            } else {
-                same_count += 1;
-                SyncEntry {
-                    relative_path: rel_path.clone(),
-                    status: "same".to_string(),
-                    source_size: *src_size,
-                    dest_size: *dst_size,
-                    source_modified: *src_modified,
-                    dest_modified: *dst_modified,
-                    source_etag: src_etag.clone().unwrap_or_default(),
-                    dest_etag: dst_etag.clone().unwrap_or_default(),
-                }
-            }
-        } else {
-            new_count += 1;
-            SyncEntry {
-                relative_path: rel_path.clone(),
-                status: "new".to_string(),
-                source_size: *src_size,
-                dest_size: 0,
-                source_modified: *src_modified,
-                dest_modified: 0,
-                source_etag: src_etag.clone().unwrap_or_default(),
-                dest_etag: String::new(),
-            }
-        };
+        let rel = &entry.relative_path;
+        match entry.status.as_str() {
+            "new" => new_count += 1,
+            "modified" => modified += 1,
+            "deleted" | "left_deleted" | "right_deleted" => deleted += 1,
+            "left_changed" if !dest_files.contains_key(rel) => new_count += 1,
+            "right_changed" if !source_files.contains_key(rel) => new_count += 1,
+            "left_changed" | "right_changed" => modified += 1,
+            "conflict" => conflicts += 1,
+            _ => same_count += 1,
+        }
 
         let _ = channel.send(SyncEvent::Entry(entry));
         scanned += 1;
@@ -153,46 +158,168 @@ pub async fn sync_diff(
         }
     }
 
-    // Keys only in dest (deleted from source perspective)
-    for (rel_path, (dst_size, dst_modified, ref dst_etag)) in &dest_files {
-        if cancel_flag.load(Ordering::Relaxed) {
-            cleanup(&sync_state, &id);
-            return Ok(());
-        }
-
-        if !source_files.contains_key(rel_path) {
-            deleted += 1;
-            let entry = SyncEntry {
-                relative_path: rel_path.clone(),
-                status: "deleted".to_string(),
-                source_size: 0,
-                dest_size: *dst_size,
-                source_modified: 0,
-                dest_modified: *dst_modified,
-                source_etag: String::new(),
-                dest_etag: dst_etag.clone().unwrap_or_default(),
-            };
-            let _ = channel.send(SyncEvent::Entry(entry));
-            scanned += 1;
-
-            if scanned % 100 == 0 {
-                let _ = channel.send(SyncEvent::Progress { scanned });
-            }
-        }
-    }
-
-    let total = new_count + modified + deleted + same_count;
+    let total = new_count + modified + deleted + conflicts + same_count;
     let _ = channel.send(SyncEvent::Done {
         total,
         new_count,
         modified,
         deleted,
+        conflicts,
     });
 
     cleanup(&sync_state, &id);
     Ok(())
 }
 
+/// Apply a sync plan: copy new/modified files from source to dest and, when
+/// `delete_extraneous` is set, remove dest files that no longer exist in the
+/// source. Uses `entries` from a previous `sync_diff` when given, otherwise the
+/// diff is recomputed. With `dry_run` the planned actions are returned without
+/// touching either side.
+///
+/// In "two_way" mode changes flow in both directions, conflicts are left
+/// untouched and reported, and the pair's snapshot is updated after a
+/// complete run.
+/// Cancel/pause through `cancel_file_operation` / `pause_file_operation` with `id`.
+#[tauri::command]
+pub async fn sync_apply(
+    id: String,
+    source_backend: String,  // "local" | "s3" | "sftp" | "webdav"
+    source_path: String,     // local dir path, s3://bucket/prefix, sftp://host:port/path or webdav://host/path
+    source_s3_id: String,    // S3, SFTP or WebDAV connection id, "" for local
+    dest_backend: String,
+    dest_path: String,
+    dest_s3_id: String,
+    entries: Option<Vec<SyncEntry>>,
+    exclude_patterns: Vec<String>,
+    compare_mode: String,    // "size_mtime" | "checksum"
+    mode: Option<String>,    // "mirror" (default) | "two_way"
+    delete_extraneous: bool, // also propagates deletions in two-way mode
+    dry_run: bool,
+    channel: Channel<ProgressEvent>,
+    s3_state: State<'_, S3State>,
+    sftp_state: State<'_, SftpState>,
+    webdav_state: State<'_, WebDavState>,
+    file_op_state: State<'_, FileOpState>,
+) -> Result<SyncApplyResult, FmError> {
+    let use_checksum = compare_mode == "checksum";
+    let two_way = mode.as_deref() == Some("two_way");
+    let source_side = (source_backend.as_str(), source_path.as_str(), source_s3_id.as_str());
+    let dest_side = (dest_backend.as_str(), dest_path.as_str(), dest_s3_id.as_str());
+
+    let entries = match entries {
+        Some(entries) => entries,
+        None => {
+            let (source_files, dest_files) = collect_pair(
+                source_side,
+                dest_side,
+                &exclude_patterns,
+                use_checksum,
+                &s3_state,
+                &sftp_state,
+                &webdav_state,
+            )
+            .await?;
+            if two_way {
+                let snapshot = SyncSnapshot::load(
+                    &pair_side(&source_backend, &source_s3_id, &source_path),
+                    &pair_side(&dest_backend, &dest_s3_id, &dest_path),
+                )?;
+                diff_two_way(&source_files, &dest_files, &snapshot)
+            } else {
+                diff_files(&source_files, &dest_files, use_checksum)
+            }
+        }
+    };
+    let conflicts: Vec<String> = entries
+        .iter()
+        .filter(|e| e.status == "conflict")
+        .map(|e| e.relative_path.clone())
+        .collect();
+
+    let source = resolve_endpoint(
+        &source_backend,
+        &source_path,
+        &source_s3_id,
+        &s3_state,
+        &sftp_state,
+        &webdav_state,
+    )
+    .await?;
+    let dest = resolve_endpoint(
+        &dest_backend,
+        &dest_path,
+        &dest_s3_id,
+        &s3_state,
+        &sftp_state,
+        &webdav_state,
+    )
+    .await?;
+
+    let actions = plan_actions(&entries, &source, &dest, delete_extraneous);
+    if dry_run {
+        return Ok(SyncApplyResult {
+            actions,
+            conflicts,
+            dry_run: true,
+            checkpoint: None,
+        });
+    }
+
+    let flags = Arc::new(OpFlags {
+        cancel: AtomicBool::new(false),
+        pause: AtomicBool::new(false),
+    });
+    {
+        let mut map = file_op_state
+            .0
+            .lock()
+            .map_err(|e| FmError::Other(e.to_string()))?;
+        map.insert(id.clone(), flags.clone());
+    }
+
+    let result = retry::counted(bandwidth::scoped(
+        &id,
+        &[&source_s3_id, &dest_s3_id],
+        run_actions(&id, &actions, &source, &dest, &flags, &channel),
+    ))
+    .await;
+
+    if let Ok(mut map) = file_op_state.0.lock() {
+        map.remove(&id);
+    }
+    let checkpoint = result?;
+
+    // Record the post-sync state only after a complete run, so paused or
+    // failed transfers are detected again next time.
+    if two_way && checkpoint.is_none() {
+        let (source_files, dest_files) = collect_pair(
+            source_side,
+            dest_side,
+            &exclude_patterns,
+            use_checksum,
+            &s3_state,
+            &sftp_state,
+            &webdav_state,
+        )
+        .await?;
+        let mut snapshot = SyncSnapshot::load(
+            &pair_side(&source_backend, &source_s3_id, &source_path),
+            &pair_side(&dest_backend, &dest_s3_id, &dest_path),
+        )?;
+        let skip: HashSet<&str> = conflicts.iter().map(|c| c.as_str()).collect();
+        update_snapshot(&mut snapshot, &source_files, &dest_files, &skip);
+        snapshot.save()?;
+    }
+
+    Ok(SyncApplyResult {
+        actions,
+        conflicts,
+        dry_run: false,
+        checkpoint,
+    })
+}
+
 #[tauri::command]
 pub fn cancel_sync(id: String, state: State<'_, SyncState>) -> Result<(), FmError> {
     let map = state
@@ -213,6 +340,182 @@ fn cleanup(sync_state: &State<'_, SyncState>, id: &str) {
     }
 }
 
+/// Collect and filter both sides of a sync pair. Each side is
+/// (backend, path, connection id).
+async fn collect_pair(
+    source: (&str, &str, &str),
+    dest: (&str, &str, &str),
+    exclude_patterns: &[String],
+    use_checksum: bool,
+    s3_state: &State<'_, S3State>,
+    sftp_state: &State<'_, SftpState>,
+    webdav_state: &State<'_, WebDavState>,
+) -> Result<(HashMap<String, FileInfo>, HashMap<String, FileInfo>), FmError> {
+    let on_warning = |message: String| log::warn!("{}", message);
+    let (backend, path, conn_id) = source;
+    let mut source_files = collect_files(
+        backend, path, conn_id, use_checksum, s3_state, sftp_state, webdav_state, &on_warning,
+    )
+    .await?;
+    let (backend, path, conn_id) = dest;
+    let mut dest_files = collect_files(
+        backend, path, conn_id, use_checksum, s3_state, sftp_state, webdav_state, &on_warning,
+    )
+    .await?;
+    if !exclude_patterns.is_empty() {
+        apply_excludes(&mut source_files, exclude_patterns);
+        apply_excludes(&mut dest_files, exclude_patterns);
+    }
+    Ok((source_files, dest_files))
+}
+
+/// Build the SyncEntry for one relative path. `src` is None for files that
+/// only exist in the destination.
+fn compare_entry(
+    rel_path: &str,
+    src: Option<&FileInfo>,
+    dst: Option<&FileInfo>,
+    use_checksum: bool,
+) -> SyncEntry {
+    let (src_size, src_modified, src_etag) = src.cloned().unwrap_or((0, 0, None));
+    let (dst_size, dst_modified, dst_etag) = dst.cloned().unwrap_or((0, 0, None));
+
+    let status = match (src, dst) {
+        (Some(_), None) => "new",
+        (None, _) => "deleted",
+        (Some(_), Some(_)) => {
+            let is_modified = if use_checksum {
+                files_differ_checksum(
+                    src_size, src_etag.as_deref(),
+                    dst_size, dst_etag.as_deref(),
+                )
+            } else {
+                src_size != dst_size || src_modified > dst_modified
+            };
+            if is_modified {
+                "modified"
+            } else {
+                "same"
+            }
+        }
+    };
+
+    build_entry(rel_path, src, dst, status)
+}
+
+fn build_entry(
+    rel_path: &str,
+    src: Option<&FileInfo>,
+    dst: Option<&FileInfo>,
+    status: &str,
+) -> SyncEntry {
+    let (src_size, src_modified, src_etag) = src.cloned().unwrap_or((0, 0, None));
+    let (dst_size, dst_modified, dst_etag) = dst.cloned().unwrap_or((0, 0, None));
+
+    SyncEntry {
+        relative_path: rel_path.to_string(),
+        status: status.to_string(),
+        source_size: src_size,
+        dest_size: dst_size,
+        source_modified: src_modified,
+        dest_modified: dst_modified,
+        source_etag: src_etag.unwrap_or_default(),
+        dest_etag: dst_etag.unwrap_or_default(),
+    }
+}
+
+/// Compare two file maps without streaming, for callers that need the whole diff.
+fn diff_files(
+    source_files: &HashMap<String, FileInfo>,
+    dest_files: &HashMap<String, FileInfo>,
+    use_checksum: bool,
+) -> Vec<SyncEntry> {
+    let mut entries: Vec<SyncEntry> = source_files
+        .iter()
+        .map(|(rel, src)| compare_entry(rel, Some(src), dest_files.get(rel), use_checksum))
+        .collect();
+    entries.extend(
+        dest_files
+            .iter()
+            .filter(|(rel, _)| !source_files.contains_key(*rel))
+            .map(|(rel, dst)| compare_entry(rel, None, Some(dst), use_checksum)),
+    );
+    entries
+}
+
+/// Two-way diff against the pair's last-synced snapshot.
+fn diff_two_way(
+    source_files: &HashMap<String, FileInfo>,
+    dest_files: &HashMap<String, FileInfo>,
+    snapshot: &SyncSnapshot,
+) -> Vec<SyncEntry> {
+    let mut paths: Vec<&String> = source_files.keys().chain(dest_files.keys()).collect();
+    paths.extend(snapshot.files.keys());
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter_map(|rel| {
+            let src = source_files.get(rel);
+            let dst = dest_files.get(rel);
+            let status = classify_two_way(
+                snapshot.files.get(rel),
+                src.map(file_state).as_ref(),
+                dst.map(file_state).as_ref(),
+            )?;
+            Some(build_entry(rel, src, dst, status))
+        })
+        .collect()
+}
+
+/// Replace snapshot entries with the current state of files present on both
+/// sides. Paths in `skip` (unresolved conflicts) and one-sided files keep
+/// their previous snapshot so they are classified the same way next time.
+fn update_snapshot(
+    snapshot: &mut SyncSnapshot,
+    source_files: &HashMap<String, FileInfo>,
+    dest_files: &HashMap<String, FileInfo>,
+    skip: &HashSet<&str>,
+) {
+    let mut files = HashMap::new();
+    for (rel, src) in source_files {
+        if skip.contains(rel.as_str()) {
+            continue;
+        }
+        if let Some(dst) = dest_files.get(rel) {
+            files.insert(
+                rel.clone(),
+                SnapshotEntry {
+                    source: file_state(src),
+                    dest: file_state(dst),
+                },
+            );
+        }
+    }
+    for (rel, old) in &snapshot.files {
+        let present = source_files.contains_key(rel) || dest_files.contains_key(rel);
+        if present && !files.contains_key(rel) {
+            files.insert(rel.clone(), old.clone());
+        }
+    }
+    snapshot.files = files;
+}
+
+fn file_state(info: &FileInfo) -> FileState {
+    FileState {
+        size: info.0,
+        modified: info.1,
+        etag: info.2.clone(),
+    }
+}
+
+/// Snapshot identity of one side: backend, connection id and normalized
+/// path, so the same path on two connections is never taken for one side.
+fn pair_side(backend: &str, connection_id: &str, path: &str) -> String {
+    format!("{}:{}:{}", backend, connection_id, path.trim_end_matches('/'))
+}
+
 /// Remove entries whose relative paths match any exclude pattern.
 fn apply_excludes(files: &mut HashMap<String, FileInfo>, patterns: &[String]) {
     files.retain(|rel_path, _| {
@@ -266,13 +569,18 @@ fn files_differ_checksum(
 async fn collect_files(
     backend: &str,
     path: &str,
-    s3_id: &str,
+    conn_id: &str,
     use_checksum: bool,
     s3_state: &State<'_, S3State>,
+    sftp_state: &State<'_, SftpState>,
+    webdav_state: &State<'_, WebDavState>,
+    on_warning: &(dyn Fn(String) + Send + Sync),
 ) -> Result<HashMap<String, FileInfo>, FmError> {
     match backend {
         "local" => collect_local_files_recursive(Path::new(path), use_checksum),
-        "s3" => collect_s3_files(s3_id, path, s3_state).await,
+        "s3" => collect_s3_files(conn_id, path, s3_state).await,
+        "sftp" => collect_sftp_files(conn_id, path, use_checksum, sftp_state, on_warning).await,
+        "webdav" => collect_webdav_files(conn_id, path, webdav_state).await,
         _ => Err(FmError::Other(format!("Unknown backend: {}", backend))),
     }
 }
@@ -425,6 +733,80 @@ async fn collect_s3_files(
     Ok(result)
 }
 
+/// Collect remote files under an SFTP directory, returning relative paths with
+/// size, mtime, and (in checksum mode) an MD5 computed on the server.
+async fn collect_sftp_files(
+    conn_id: &str,
+    path: &str,
+    use_checksum: bool,
+    sftp_state: &State<'_, SftpState>,
+    on_warning: &(dyn Fn(String) + Send + Sync),
+) -> Result<HashMap<String, FileInfo>, FmError> {
+    let service = get_sftp_service(sftp_state, conn_id).await?;
+    let root = sftp_root(path);
+    let files = service.list_files_recursive(&root).await?;
+
+    // Without a remote shell the digests are unavailable; files_differ_checksum
+    // then falls back to comparing sizes, which the caller is told about.
+    let checksums = if use_checksum {
+        match service.remote_md5sums(&root).await {
+            Ok(checksums) => checksums,
+            Err(e) => {
+                on_warning(format!(
+                    "Checksums unavailable on {root} ({e}); comparing SFTP files by size"
+                ));
+                HashMap::new()
+            }
+        }
+    } else {
+        HashMap::new()
+    };
+
+    Ok(files
+        .into_iter()
+        .map(|(rel, size, modified)| {
+            let md5 = checksums.get(&rel).cloned();
+            (rel, (size, modified, md5))
+        })
+        .collect())
+}
+
+/// Remote directory for an sftp://host:port/path (or bare) path, without trailing slash.
+fn sftp_root(path: &str) -> String {
+    let remote = strip_sftp_prefix(path).trim_end_matches('/');
+    if remote.is_empty() {
+        "/".to_string()
+    } else {
+        remote.to_string()
+    }
+}
+
+/// Collect files under a WebDAV collection, returning relative paths with
+/// size and mtime. ETags are opaque rather than content hashes, so they are
+/// left out and checksum mode falls back to comparing sizes.
+async fn collect_webdav_files(
+    conn_id: &str,
+    path: &str,
+    webdav_state: &State<'_, WebDavState>,
+) -> Result<HashMap<String, FileInfo>, FmError> {
+    let service = get_webdav_service(webdav_state, conn_id)?;
+    let files = service.list_files_recursive(&webdav_root(path)).await?;
+    Ok(files
+        .into_iter()
+        .map(|(rel, size, modified, _)| (rel, (size, modified, None)))
+        .collect())
+}
+
+/// Remote directory for a webdav://host/path (or bare) path, without trailing slash.
+fn webdav_root(path: &str) -> String {
+    let remote = strip_dav_prefix(path).trim_end_matches('/');
+    if remote.is_empty() {
+        "/".to_string()
+    } else {
+        remote.to_string()
+    }
+}
+
 /// Parse an s3://bucket/prefix path into (bucket, prefix).
 fn parse_s3_path(path: &str) -> Result<(String, String), FmError> {
     if let Some(rest) = path.strip_prefix("s3://") {
@@ -462,8 +844,7 @@ async fn list_all_objects(
             req = req.continuation_token(token);
         }
 
-        let resp = req
-            .send()
+        let resp = retry::send(|| req.clone().send())
             .await
             .map_err(|e| FmError::S3(e.to_string()))?;
 
@@ -487,3 +868,474 @@ async fn list_all_objects(
 
     Ok(results)
 }
+
+// ── Apply ───────────────────────────────────────────────────────────────
+
+async fn resolve_endpoint(
+    backend: &str,
+    path: &str,
+    conn_id: &str,
+    s3_state: &State<'_, S3State>,
+    sftp_state: &State<'_, SftpState>,
+    webdav_state: &State<'_, WebDavState>,
+) -> Result<SyncEndpoint, FmError> {
+    match backend {
+        "local" => Ok(SyncEndpoint::Local(PathBuf::from(path))),
+        "s3" => {
+            let (bucket, prefix) = parse_s3_path(path)?;
+            let map = s3_state
+                .0
+                .lock()
+                .map_err(|e| FmError::Other(e.to_string()))?;
+            let conn = map
+                .get(conn_id)
+                .ok_or_else(|| FmError::S3("S3 connection not found".to_string()))?;
+            let bucket = if bucket.is_empty() {
+                conn.bucket.clone()
+            } else {
+                bucket
+            };
+            Ok(SyncEndpoint::S3 {
+                service: S3Service::new(conn.client.clone(), bucket),
+                prefix,
+            })
+        }
+        "sftp" => Ok(SyncEndpoint::Sftp {
+            service: get_sftp_service(sftp_state, conn_id).await?,
+            root: sftp_root(path),
+        }),
+        "webdav" => Ok(SyncEndpoint::WebDav {
+            service: get_webdav_service(webdav_state, conn_id)?,
+            root: webdav_root(path),
+        }),
+        _ => Err(FmError::Other(format!("Unknown backend: {}", backend))),
+    }
+}
+
+/// Turn diff entries into the list of actions `sync_apply` will perform.
+/// Conflicts and unchanged files produce no action.
+fn plan_actions(
+    entries: &[SyncEntry],
+    source: &SyncEndpoint,
+    dest: &SyncEndpoint,
+    delete_extraneous: bool,
+) -> Vec<SyncAction> {
+    entries
+        .iter()
+        .filter_map(|e| {
+            let (action, target, size) = match e.status.as_str() {
+                "new" | "modified" | "left_changed" => {
+                    (transfer_kind(source, dest), "dest", e.source_size)
+                }
+                "right_changed" => (transfer_kind(dest, source), "source", e.dest_size),
+                "deleted" | "left_deleted" if delete_extraneous => ("delete", "dest", e.dest_size),
+                "right_deleted" if delete_extraneous => ("delete", "source", e.source_size),
+                _ => return None,
+            };
+            Some(SyncAction {
+                relative_path: e.relative_path.clone(),
+                action: action.to_string(),
+                target: target.to_string(),
+                size,
+            })
+        })
+        .collect()
+}
+
+fn transfer_kind(from: &SyncEndpoint, to: &SyncEndpoint) -> &'static str {
+    match (from, to) {
+        (SyncEndpoint::Local(_), SyncEndpoint::Local(_)) => "copy",
+        (SyncEndpoint::Local(_), _) => "upload",
+        (_, SyncEndpoint::Local(_)) => "download",
+        _ => "copy",
+    }
+}
+
+/// S3 keys awaiting a batched delete, per side.
+#[derive(Default)]
+struct PendingDeletes {
+    source: Vec<String>,
+    dest: Vec<String>,
+}
+
+impl PendingDeletes {
+    async fn flush(&mut self, source: &SyncEndpoint, dest: &SyncEndpoint) -> Result<(), FmError> {
+        flush_deletes(source, &mut self.source).await?;
+        flush_deletes(dest, &mut self.dest).await
+    }
+}
+
+/// Execute planned actions in order. Returns None on success, Some(checkpoint)
+/// on pause; `files_completed` holds relative paths.
+async fn run_actions(
+    op_id: &str,
+    actions: &[SyncAction],
+    source: &SyncEndpoint,
+    dest: &SyncEndpoint,
+    flags: &OpFlags,
+    channel: &Channel<ProgressEvent>,
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let files_total = actions.len() as u32;
+    let bytes_total: u64 = actions
+        .iter()
+        .filter(|a| a.action != "delete")
+        .map(|a| a.size)
+        .sum();
+    let mut bytes_done: u64 = 0;
+    let mut completed_files: Vec<String> = Vec::new();
+    let mut pending = PendingDeletes::default();
+
+    for (i, action) in actions.iter().enumerate() {
+        let files_done = i as u32;
+        if flags.cancel.load(Ordering::Relaxed) {
+            pending.flush(source, dest).await?;
+            return Err(FmError::Other("Operation cancelled".into()));
+        }
+        if flags.pause.load(Ordering::Relaxed) {
+            pending.flush(source, dest).await?;
+            return Ok(Some(TransferCheckpoint {
+                files_completed: completed_files,
+                bytes_done,
+                bytes_total,
+                files_done,
+                files_total,
+            }));
+        }
+
+        let rel = &action.relative_path;
+        let to_source = action.target == "source";
+        let (from, to) = if to_source { (dest, source) } else { (source, dest) };
+
+        if action.action == "delete" {
+            match to {
+                SyncEndpoint::Local(root) => {
+                    let path = root.join(rel);
+                    if path.exists() {
+                        fs::remove_file(&path)?;
+                    }
+                }
+                SyncEndpoint::S3 { prefix, .. } => {
+                    let keys = if to_source { &mut pending.source } else { &mut pending.dest };
+                    keys.push(join_key(prefix, rel));
+                }
+                SyncEndpoint::Sftp { service, root } => {
+                    let path = remote_join(root, rel);
+                    if service.session.try_exists(&path).await.unwrap_or(false) {
+                        service.delete(&[path]).await?;
+                    }
+                }
+                SyncEndpoint::WebDav { service, root } => {
+                    match service.delete(&[remote_join(root, rel)]).await {
+                        Ok(()) | Err(FmError::NotFound(_)) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        } else {
+            let base = bytes_done;
+            let on_progress = |evt: ProgressEvent| {
+                let _ = channel.send(ProgressEvent {
+                    id: op_id.to_string(),
+                    bytes_done: base + evt.bytes_done.min(action.size),
+                    bytes_total,
+                    current_file: rel.clone(),
+                    files_done,
+                    files_total,
+                    retries: retry::retries(),
+                });
+            };
+            let paused =
+                transfer_file(rel, action.size, from, to, op_id, flags, &on_progress).await?;
+            if paused {
+                pending.flush(source, dest).await?;
+                return Ok(Some(TransferCheckpoint {
+                    files_completed: completed_files,
+                    bytes_done,
+                    bytes_total,
+                    files_done,
+                    files_total,
+                }));
+            }
+            bytes_done += action.size;
+        }
+
+        completed_files.push(rel.clone());
+
+        let _ = channel.send(ProgressEvent {
+            id: op_id.to_string(),
+            bytes_done,
+            bytes_total,
+            current_file: rel.clone(),
+            files_done: files_done + 1,
+            files_total,
+            retries: retry::retries(),
+        });
+    }
+
+    pending.flush(source, dest).await?;
+    Ok(None)
+}
+
+/// Copy one file between endpoints. Returns true if the underlying transfer
+/// observed the pause flag before it started.
+async fn transfer_file(
+    rel: &str,
+    size: u64,
+    source: &SyncEndpoint,
+    dest: &SyncEndpoint,
+    op_id: &str,
+    flags: &OpFlags,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+) -> Result<bool, FmError> {
+    match (source, dest) {
+        (
+            SyncEndpoint::S3 { service: src, prefix: src_prefix },
+            SyncEndpoint::S3 { service: dst, prefix: dst_prefix },
+        ) => {
+            copy_single_or_multipart(
+                &src.client,
+                &src.bucket,
+                &join_key(src_prefix, rel),
+                &dst.client,
+                &dst.bucket,
+                &join_key(dst_prefix, rel),
+                size,
+            )
+            .await?;
+            Ok(false)
+        }
+        (SyncEndpoint::Local(src_root), _) => {
+            store_from_local(dest, src_root, rel, op_id, flags, on_progress).await
+        }
+        (_, SyncEndpoint::Local(dst_root)) => {
+            fetch_to_local(source, rel, dst_root, op_id, flags, on_progress).await
+        }
+        _ => {
+            // S3 ↔ SFTP and SFTP ↔ SFTP: stream from one to the other.
+            if let (Some((from, path, _)), Some((to, _, dir))) =
+                (storage_file(source, rel), storage_file(dest, rel))
+            {
+                if let SyncEndpoint::Sftp { service, .. } = dest {
+                    service.ensure_remote_dir(&dir).await?;
+                }
+                let checkpoint = storage::transfer(
+                    from,
+                    &[path],
+                    to,
+                    &dir,
+                    None,
+                    op_id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                )
+                .await?;
+                return Ok(checkpoint.is_some());
+            }
+
+            // To or from WebDAV: stage through a temp file, each leg
+            // reporting half of the file's progress.
+            let leg = |offset: u64| {
+                move |evt: ProgressEvent| {
+                    on_progress(ProgressEvent {
+                        bytes_done: offset + evt.bytes_done.min(size) / 2,
+                        ..evt
+                    })
+                }
+            };
+            let staging = std::env::temp_dir().join("furman-sync").join(op_id);
+            let result = async {
+                if fetch_to_local(source, rel, &staging, op_id, flags, &leg(0)).await? {
+                    return Ok(true);
+                }
+                store_from_local(dest, &staging, rel, op_id, flags, &leg(size / 2)).await
+            }
+            .await;
+            let _ = tokio::fs::remove_dir_all(&staging).await;
+            result
+        }
+    }
+}
+
+/// `rel` on an endpoint served by a `StorageBackend`, as (backend, path,
+/// parent directory); None for local and WebDAV endpoints.
+fn storage_file<'a>(endpoint: &'a SyncEndpoint, rel: &str) -> Option<(&'a dyn StorageBackend, String, String)> {
+    match endpoint {
+        SyncEndpoint::S3 { service, prefix } => {
+            let key = join_key(prefix, rel);
+            let dir = key_dir(&key);
+            Some((service, key, dir))
+        }
+        SyncEndpoint::Sftp { service, root } => {
+            let path = remote_join(root, rel);
+            let dir = parent_path(&path);
+            Some((service, path, dir))
+        }
+        SyncEndpoint::Local(_) | SyncEndpoint::WebDav { .. } => None,
+    }
+}
+
+/// Write `local_root/rel` to `rel` under the destination endpoint.
+async fn store_from_local(
+    dest: &SyncEndpoint,
+    local_root: &Path,
+    rel: &str,
+    op_id: &str,
+    flags: &OpFlags,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+) -> Result<bool, FmError> {
+    let from = local_root.join(rel);
+    match dest {
+        SyncEndpoint::Local(dst_root) => {
+            copy_local(&from, &dst_root.join(rel)).await?;
+            Ok(false)
+        }
+        SyncEndpoint::S3 { service, prefix } => {
+            let key = join_key(prefix, rel);
+            let checkpoint = service
+                .upload(
+                    &[from.to_string_lossy().into_owned()],
+                    &key_dir(&key),
+                    op_id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                    None,
+                )
+                .await?;
+            Ok(checkpoint.is_some())
+        }
+        SyncEndpoint::Sftp { service, root } => {
+            let remote = remote_join(root, rel);
+            let remote_dir = remote.rsplit_once('/').map(|(p, _)| p).unwrap_or("");
+            let checkpoint = service
+                .upload(
+                    &[from.to_string_lossy().into_owned()],
+                    remote_dir,
+                    op_id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                    ResumeMode::Restart,
+                )
+                .await?;
+            Ok(checkpoint.is_some())
+        }
+        SyncEndpoint::WebDav { service, root } => {
+            let remote = remote_join(root, rel);
+            let remote_dir = remote.rsplit_once('/').map(|(p, _)| p).unwrap_or("");
+            let checkpoint = service
+                .upload(
+                    &[from.to_string_lossy().into_owned()],
+                    remote_dir,
+                    op_id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                )
+                .await?;
+            Ok(checkpoint.is_some())
+        }
+    }
+}
+
+/// Read `rel` from the source endpoint into `local_root/rel`.
+async fn fetch_to_local(
+    source: &SyncEndpoint,
+    rel: &str,
+    local_root: &Path,
+    op_id: &str,
+    flags: &OpFlags,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+) -> Result<bool, FmError> {
+    let to = local_root.join(rel);
+    let dir = to.parent().unwrap_or(local_root).to_string_lossy().into_owned();
+    match source {
+        SyncEndpoint::Local(src_root) => {
+            copy_local(&src_root.join(rel), &to).await?;
+            Ok(false)
+        }
+        SyncEndpoint::S3 { service, prefix } => {
+            let checkpoint = service
+                .download(
+                    &[join_key(prefix, rel)],
+                    &dir,
+                    op_id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                    None,
+                )
+                .await?;
+            Ok(checkpoint.is_some())
+        }
+        SyncEndpoint::Sftp { service, root } => {
+            let checkpoint = service
+                .download(
+                    &[remote_join(root, rel)],
+                    &dir,
+                    op_id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                    ResumeMode::Restart,
+                )
+                .await?;
+            Ok(checkpoint.is_some())
+        }
+        SyncEndpoint::WebDav { service, root } => {
+            let checkpoint = service
+                .download(
+                    &[remote_join(root, rel)],
+                    &dir,
+                    op_id,
+                    &flags.cancel,
+                    &flags.pause,
+                    on_progress,
+                    false,
+                )
+                .await?;
+            Ok(checkpoint.is_some())
+        }
+    }
+}
+
+async fn copy_local(from: &Path, to: &Path) -> Result<(), FmError> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::copy(from, to).await?;
+    Ok(())
+}
+
+async fn flush_deletes(dest: &SyncEndpoint, keys: &mut Vec<String>) -> Result<(), FmError> {
+    if let SyncEndpoint::S3 { service, .. } = dest {
+        if !keys.is_empty() {
+            service.delete_objects(keys).await?;
+            keys.clear();
+        }
+    }
+    Ok(())
+}
+
+/// Join a prefix (without trailing slash) and a relative path into an S3 key.
+fn join_key(prefix: &str, rel: &str) -> String {
+    if prefix.is_empty() {
+        rel.to_string()
+    } else {
+        format!("{}/{}", prefix, rel)
+    }
+}
+
+/// The "directory" part of a key including the trailing slash ("" at the root).
+fn key_dir(key: &str) -> String {
+    match key.rfind('/') {
+        Some(pos) => key[..pos + 1].to_string(),
+        None => String::new(),
+    }
+}
+
+/// Join a remote directory and a relative path.
+fn remote_join(root: &str, rel: &str) -> String {
+    format!("{}/{}", root.trim_end_matches('/'), rel)
+}
@@ -1,19 +1,41 @@
+use crate::commands::sftp::get_service as get_sftp_service;
 use crate::models::FmError;
+use crate::sftp::helpers::{shell_quote, strip_sftp_prefix};
+use crate::sftp::{sftperr, SftpState};
+use futures::future::{select, Either};
 use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use russh::ChannelMsg;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::path::PathBuf;
+use std::pin::pin;
 use std::sync::Mutex;
 use tauri::{AppHandle, Emitter, State};
+use tokio::sync::mpsc;
 
 /// Managed state holding one PTY session per terminal id.
 pub struct TerminalState(pub Mutex<HashMap<String, TerminalSession>>);
 
-pub struct TerminalSession {
-    _master: Box<dyn MasterPty + Send>,
-    writer: Box<dyn Write + Send>,
-    child: Box<dyn portable_pty::Child + Send + Sync>,
+pub enum TerminalSession {
+    /// Shell running in a local PTY.
+    Local {
+        master: Box<dyn MasterPty + Send>,
+        writer: Box<dyn Write + Send>,
+        child: Box<dyn portable_pty::Child + Send + Sync>,
+    },
+    /// Shell on an SFTP host. Input is forwarded to the task that owns the
+    /// SSH channel.
+    Remote {
+        input: mpsc::UnboundedSender<RemoteInput>,
+    },
+}
+
+/// Requests for a remote terminal's channel task.
+pub enum RemoteInput {
+    Data(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+    Close,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -156,8 +178,8 @@ pub fn terminal_spawn(
     });
 
     // Store the session
-    let session = TerminalSession {
-        _master: pair.master,
+    let session = TerminalSession::Local {
+        master: pair.master,
         writer,
         child,
     };
@@ -171,6 +193,98 @@ pub fn terminal_spawn(
     Ok(())
 }
 
+/// Open an interactive shell on the host of SFTP connection `sftp_id`, over its
+/// existing SSH session, starting in remote directory `cwd`. Output and exit are
+/// reported with the same events as local terminals.
+#[tauri::command]
+pub async fn terminal_spawn_remote(
+    id: String,
+    sftp_id: String,
+    cwd: String,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    app_handle: AppHandle,
+    sftp_state: State<'_, SftpState>,
+    state: State<'_, TerminalState>,
+) -> Result<(), FmError> {
+    let svc = get_sftp_service(&sftp_state, &sftp_id).await?;
+    let (cols, rows) = (cols.unwrap_or(80), rows.unwrap_or(24));
+
+    let mut channel = svc
+        .ssh_handle
+        .channel_open_session()
+        .await
+        .map_err(|e| sftperr(format!("Channel open failed: {e}")))?;
+    channel
+        .request_pty(true, "xterm-256color", cols as u32, rows as u32, 0, 0, &[])
+        .await
+        .map_err(|e| sftperr(format!("PTY request failed: {e}")))?;
+    // Start in the pane's directory, then replace the wrapper with a login shell.
+    let command = format!(
+        "cd {} 2>/dev/null; exec \"${{SHELL:-/bin/sh}}\" -l",
+        shell_quote(strip_sftp_prefix(&cwd))
+    );
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| sftperr(format!("Shell start failed: {e}")))?;
+
+    let (input, mut input_rx) = mpsc::unbounded_channel();
+    {
+        let mut map = state
+            .0
+            .lock()
+            .map_err(|e| FmError::Other(format!("lock poisoned: {e}")))?;
+        map.insert(id.clone(), TerminalSession::Remote { input });
+    }
+
+    // Pump channel output to "terminal-output" events and forward input,
+    // until the shell exits or the terminal is closed.
+    tokio::spawn(async move {
+        let mut code = None;
+        loop {
+            let next = {
+                let output = pin!(channel.wait());
+                let request = pin!(input_rx.recv());
+                match select(output, request).await {
+                    Either::Left((msg, _)) => Either::Left(msg),
+                    Either::Right((req, _)) => Either::Right(req),
+                }
+            };
+            match next {
+                Either::Left(Some(ChannelMsg::Data { data }))
+                | Either::Left(Some(ChannelMsg::ExtendedData { data, .. })) => {
+                    let payload = TerminalOutput {
+                        id: id.clone(),
+                        data: String::from_utf8_lossy(&data).into_owned(),
+                    };
+                    let _ = app_handle.emit("terminal-output", &payload);
+                }
+                Either::Left(Some(ChannelMsg::ExitStatus { exit_status })) => {
+                    code = Some(exit_status);
+                }
+                Either::Left(Some(_)) => {}
+                Either::Left(None) => break,
+                Either::Right(Some(RemoteInput::Data(bytes))) => {
+                    if channel.data(&bytes[..]).await.is_err() {
+                        break;
+                    }
+                }
+                Either::Right(Some(RemoteInput::Resize { cols, rows })) => {
+                    let _ = channel.window_change(cols as u32, rows as u32, 0, 0).await;
+                }
+                Either::Right(Some(RemoteInput::Close)) | Either::Right(None) => {
+                    let _ = channel.close().await;
+                    break;
+                }
+            }
+        }
+        let _ = app_handle.emit("terminal-exit", &TerminalExit { id, code });
+    });
+
+    Ok(())
+}
+
 /// Write data (keystrokes) to a PTY session.
 #[tauri::command]
 pub fn terminal_write(
@@ -187,15 +301,22 @@ pub fn terminal_write(
         .get_mut(&id)
         .ok_or_else(|| FmError::NotFound(format!("no terminal with id: {id}")))?;
 
-    session
-        .writer
-        .write_all(data.as_bytes())
-        .map_err(|e| FmError::Other(format!("write: {e}")))?;
+    match session {
+        TerminalSession::Local { writer, .. } => {
+            writer
+                .write_all(data.as_bytes())
+                .map_err(|e| FmError::Other(format!("write: {e}")))?;
 
-    session
-        .writer
-        .flush()
-        .map_err(|e| FmError::Other(format!("flush: {e}")))?;
+            writer
+                .flush()
+                .map_err(|e| FmError::Other(format!("flush: {e}")))?;
+        }
+        TerminalSession::Remote { input } => {
+            input
+                .send(RemoteInput::Data(data.into_bytes()))
+                .map_err(|_| FmError::Other("remote terminal closed".into()))?;
+        }
+    }
 
     Ok(())
 }
@@ -217,15 +338,23 @@ pub fn terminal_resize(
         .get(&id)
         .ok_or_else(|| FmError::NotFound(format!("no terminal with id: {id}")))?;
 
-    session
-        ._master
-        .resize(PtySize {
-            rows,
-            cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })
-        .map_err(|e| FmError::Other(format!("resize: {e}")))?;
+    match session {
+        TerminalSession::Local { master, .. } => {
+            master
+                .resize(PtySize {
+                    rows,
+                    cols,
+                    pixel_width: 0,
+                    pixel_height: 0,
+                })
+                .map_err(|e| FmError::Other(format!("resize: {e}")))?;
+        }
+        TerminalSession::Remote { input } => {
+            input
+                .send(RemoteInput::Resize { cols, rows })
+                .map_err(|_| FmError::Other("remote terminal closed".into()))?;
+        }
+    }
 
     Ok(())
 }
@@ -241,8 +370,14 @@ pub fn terminal_close(
         .lock()
         .map_err(|e| FmError::Other(format!("lock poisoned: {e}")))?;
 
-    if let Some(mut session) = map.remove(&id) {
-        let _ = session.child.kill();
+    match map.remove(&id) {
+        Some(TerminalSession::Local { mut child, .. }) => {
+            let _ = child.kill();
+        }
+        Some(TerminalSession::Remote { input }) => {
+            let _ = input.send(RemoteInput::Close);
+        }
+        None => {}
     }
 
     Ok(())
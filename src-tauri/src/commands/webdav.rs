@@ -0,0 +1,252 @@
+use crate::commands::file::{FileOpState, OpFlags};
+use crate::models::{DirListing, FmError, ProgressEvent, TransferCheckpoint};
+use crate::webdav::client::{build_client, DavAuth};
+use crate::webdav::helpers::strip_dav_prefix;
+use crate::webdav::{daverr, WebDavConnection, WebDavService, WebDavState};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tauri::ipc::Channel;
+use tauri::State;
+
+// ── Helper ───────────────────────────────────────────────────────────────────
+
+/// Extract an owned WebDavService from the state, dropping the MutexGuard
+/// before any async work (same pattern as S3's get_service).
+pub(crate) fn get_service(
+    state: &State<'_, WebDavState>,
+    id: &str,
+) -> Result<WebDavService, FmError> {
+    let map = state.0.lock().map_err(|e| daverr(e.to_string()))?;
+    let conn = map.get(id).ok_or_else(|| daverr("WebDAV connection not found"))?;
+    Ok(WebDavService::new(conn))
+}
+
+fn register_op(file_op_state: &State<'_, FileOpState>, op_id: &str) -> Result<Arc<OpFlags>, FmError> {
+    let mut ops = file_op_state
+        .0
+        .lock()
+        .map_err(|e| daverr(e.to_string()))?;
+    let flags = Arc::new(OpFlags {
+        cancel: AtomicBool::new(false),
+        pause: AtomicBool::new(false),
+    });
+    ops.insert(op_id.to_string(), flags.clone());
+    Ok(flags)
+}
+
+fn unregister_op(file_op_state: &State<'_, FileOpState>, op_id: &str) -> Result<(), FmError> {
+    file_op_state
+        .0
+        .lock()
+        .map_err(|e| daverr(e.to_string()))?
+        .remove(op_id);
+    Ok(())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Connect to the collection at `url` (e.g.
+/// `https://cloud.example.com/remote.php/dav/files/alice`), returning the
+/// initial path. A `token` selects bearer auth, otherwise `username` and
+/// `password` are sent as basic auth; with neither the server is accessed
+/// anonymously.
+#[tauri::command]
+pub async fn webdav_connect(
+    state: State<'_, WebDavState>,
+    id: String,
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    token: Option<String>,
+    accept_invalid_certs: Option<bool>,
+) -> Result<String, FmError> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(daverr(format!("Not an http(s) URL: {}", url)));
+    }
+    let auth = match (token.filter(|t| !t.is_empty()), username.filter(|u| !u.is_empty())) {
+        (Some(token), _) => DavAuth::Bearer(token),
+        (None, Some(username)) => DavAuth::Basic {
+            username,
+            password: password.unwrap_or_default(),
+        },
+        (None, None) => DavAuth::None,
+    };
+    let conn = WebDavConnection {
+        client: build_client(accept_invalid_certs.unwrap_or(false))?,
+        base_url: url.trim_end_matches('/').to_string(),
+        auth,
+    };
+    WebDavService::new(&conn).check().await?;
+
+    state
+        .0
+        .lock()
+        .map_err(|e| daverr(e.to_string()))?
+        .insert(id, conn);
+
+    Ok("/".to_string())
+}
+
+#[tauri::command]
+pub fn webdav_disconnect(state: State<'_, WebDavState>, id: String) -> Result<(), FmError> {
+    state
+        .0
+        .lock()
+        .map_err(|e| daverr(e.to_string()))?
+        .remove(&id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn webdav_list_objects(
+    state: State<'_, WebDavState>,
+    id: String,
+    path: String,
+) -> Result<DirListing, FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.list_objects(strip_dav_prefix(&path)).await
+}
+
+#[tauri::command]
+pub async fn webdav_delete(
+    state: State<'_, WebDavState>,
+    id: String,
+    paths: Vec<String>,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    let remote_paths: Vec<String> = paths.iter().map(|p| strip_dav_prefix(p).to_string()).collect();
+    svc.delete(&remote_paths).await
+}
+
+#[tauri::command]
+pub async fn webdav_rename(
+    state: State<'_, WebDavState>,
+    id: String,
+    path: String,
+    new_name: String,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.rename(strip_dav_prefix(&path), &new_name).await
+}
+
+#[tauri::command]
+pub async fn webdav_create_folder(
+    state: State<'_, WebDavState>,
+    id: String,
+    path: String,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.create_folder(strip_dav_prefix(&path)).await
+}
+
+/// Copy files and directories to another directory on the same server
+/// (WebDAV COPY); the data never leaves the server.
+#[tauri::command]
+pub async fn webdav_copy(
+    state: State<'_, WebDavState>,
+    id: String,
+    keys: Vec<String>,
+    destination: String,
+    overwrite: Option<bool>,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    let paths: Vec<String> = keys.iter().map(|p| strip_dav_prefix(p).to_string()).collect();
+    svc.copy(&paths, strip_dav_prefix(&destination), overwrite.unwrap_or(false))
+        .await
+}
+
+/// Move files and directories to another directory on the same server
+/// (WebDAV MOVE).
+#[tauri::command]
+pub async fn webdav_move(
+    state: State<'_, WebDavState>,
+    id: String,
+    keys: Vec<String>,
+    destination: String,
+    overwrite: Option<bool>,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    let paths: Vec<String> = keys.iter().map(|p| strip_dav_prefix(p).to_string()).collect();
+    svc.move_to(&paths, strip_dav_prefix(&destination), overwrite.unwrap_or(false))
+        .await
+}
+
+#[tauri::command]
+pub async fn webdav_download(
+    state: State<'_, WebDavState>,
+    file_op_state: State<'_, FileOpState>,
+    id: String,
+    op_id: String,
+    keys: Vec<String>,
+    destination: String,
+    resume: Option<bool>,
+    channel: Channel<ProgressEvent>,
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let flags = register_op(&file_op_state, &op_id)?;
+    let svc = get_service(&state, &id)?;
+    let remote_paths: Vec<String> = keys.iter().map(|p| strip_dav_prefix(p).to_string()).collect();
+
+    let result = svc
+        .download(
+            &remote_paths,
+            &destination,
+            &op_id,
+            &flags.cancel,
+            &flags.pause,
+            &|evt| { let _ = channel.send(evt); },
+            resume.unwrap_or(false),
+        )
+        .await;
+
+    unregister_op(&file_op_state, &op_id)?;
+    result
+}
+
+#[tauri::command]
+pub async fn webdav_upload(
+    state: State<'_, WebDavState>,
+    file_op_state: State<'_, FileOpState>,
+    id: String,
+    op_id: String,
+    sources: Vec<String>,
+    remote_prefix: String,
+    channel: Channel<ProgressEvent>,
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let flags = register_op(&file_op_state, &op_id)?;
+    let svc = get_service(&state, &id)?;
+
+    let result = svc
+        .upload(
+            &sources,
+            strip_dav_prefix(&remote_prefix),
+            &op_id,
+            &flags.cancel,
+            &flags.pause,
+            &|evt| { let _ = channel.send(evt); },
+        )
+        .await;
+
+    unregister_op(&file_op_state, &op_id)?;
+    result
+}
+
+#[tauri::command]
+pub async fn webdav_download_temp(
+    state: State<'_, WebDavState>,
+    id: String,
+    path: String,
+) -> Result<String, FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.download_temp(strip_dav_prefix(&path)).await
+}
+
+#[tauri::command]
+pub async fn webdav_put_text(
+    state: State<'_, WebDavState>,
+    id: String,
+    path: String,
+    content: String,
+) -> Result<(), FmError> {
+    let svc = get_service(&state, &id)?;
+    svc.put_text(strip_dav_prefix(&path), &content).await
+}
@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{self, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use tokio_rustls::TlsConnector;
+
+use crate::models::FmError;
+use super::helpers::{ftperr, parse_epsv, parse_list_line, parse_mlsd_line, parse_pasv, parse_pwd, FtpEntry};
+
+/// Timeout for connecting and for each control-connection reply.
+const FTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Idle time after which the control connection is probed with NOOP before
+/// use; servers typically drop idle sessions after a few minutes.
+const IDLE_CHECK: Duration = Duration::from_secs(60);
+
+// ── State ────────────────────────────────────────────────────────────────────
+
+pub struct FtpState(pub Mutex<HashMap<String, FtpConnection>>);
+
+impl Default for FtpState {
+    fn default() -> Self {
+        FtpState(Mutex::new(HashMap::new()))
+    }
+}
+
+pub struct FtpConnection {
+    /// Control connection used for browsing; transfers open their own.
+    pub control: Arc<tokio::sync::Mutex<FtpStream>>,
+    pub config: Arc<FtpConfig>,
+    pub home_dir: String,
+}
+
+// ── Settings ─────────────────────────────────────────────────────────────────
+
+/// How TLS is negotiated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FtpTls {
+    /// Plain FTP.
+    None,
+    /// FTPS via `AUTH TLS` on the normal port (FTPES).
+    Explicit,
+    /// TLS from the first byte, usually on port 990.
+    Implicit,
+}
+
+impl FtpTls {
+    pub fn parse(value: &str) -> Result<Self, FmError> {
+        match value {
+            "none" | "" => Ok(FtpTls::None),
+            "explicit" => Ok(FtpTls::Explicit),
+            "implicit" => Ok(FtpTls::Implicit),
+            other => Err(ftperr(format!("Unknown TLS mode: {other}"))),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub tls: FtpTls,
+    /// Passive (PASV/EPSV) data connections; active mode (PORT/EPRT) otherwise.
+    pub passive: bool,
+    /// Skip certificate verification, for servers with self-signed certificates.
+    pub accept_invalid_certs: bool,
+}
+
+// ── Control connection ──────────────────────────────────────────────────────
+
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+/// A data connection, TLS-wrapped when the session uses `PROT P`.
+pub struct DataStream(Box<dyn Io>);
+
+impl DataStream {
+    /// Read until the server closes the connection. A missing TLS
+    /// close_notify is tolerated, as many servers just close the socket.
+    pub async fn read_chunk(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.read(buf).await {
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(0),
+            other => other,
+        }
+    }
+
+    pub async fn write_all(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.0.write_all(data).await
+    }
+
+    /// Flush and close, sending TLS close_notify. For uploads the server
+    /// treats the end of the stream as the end of the file; after a download
+    /// some TLS servers wait for it before sending the completion reply.
+    pub async fn finish(mut self) -> std::io::Result<()> {
+        self.0.shutdown().await
+    }
+}
+
+/// One logged-in control connection. FTP runs one command at a time, so
+/// callers hold it exclusively (see `FtpConnection::control`).
+pub struct FtpStream {
+    reader: BufReader<Box<dyn Io>>,
+    /// Set once `PROT P` is active: data connections are TLS too.
+    data_tls: Option<(TlsConnector, ServerName<'static>)>,
+    local_ip: IpAddr,
+    peer_ip: IpAddr,
+    passive: bool,
+    epsv: bool,
+    mlsd: bool,
+    last_used: Instant,
+}
+
+impl FtpStream {
+    /// Connect, negotiate TLS as configured, log in and switch to binary mode.
+    pub async fn connect(config: &FtpConfig) -> Result<Self, FmError> {
+        let (host, port) = (config.host.as_str(), config.port);
+        let tcp = tokio::time::timeout(FTP_TIMEOUT, TcpStream::connect((host, port)))
+            .await
+            .map_err(|_| ftperr(format!("Connection to {host}:{port} timed out")))?
+            .map_err(|e| ftperr(format!("Connection to {host}:{port} failed: {e}")))?;
+        let local_ip = tcp.local_addr()?.ip();
+        let peer_ip = tcp.peer_addr()?.ip();
+
+        let tls = match config.tls {
+            FtpTls::None => None,
+            _ => Some((tls_connector(config.accept_invalid_certs)?, server_name(host)?)),
+        };
+        let io: Box<dyn Io> = match (&tls, config.tls) {
+            (Some((connector, name)), FtpTls::Implicit) => Box::new(
+                connector
+                    .connect(name.clone(), tcp)
+                    .await
+                    .map_err(|e| ftperr(format!("TLS handshake failed: {e}")))?,
+            ),
+            _ => Box::new(tcp),
+        };
+
+        let mut stream = FtpStream {
+            reader: BufReader::new(io),
+            data_tls: None,
+            local_ip,
+            peer_ip,
+            passive: config.passive,
+            epsv: true,
+            mlsd: false,
+            last_used: Instant::now(),
+        };
+        stream.read_expect("connect", &[220]).await?;
+
+        if config.tls == FtpTls::Explicit {
+            stream.expect("AUTH TLS", &[234]).await?;
+            let (connector, name) = tls.as_ref().ok_or_else(|| ftperr("TLS not configured"))?;
+            let plain = std::mem::replace(&mut stream.reader, BufReader::new(Box::new(tokio::io::empty())));
+            let tls_io = connector
+                .connect(name.clone(), plain.into_inner())
+                .await
+                .map_err(|e| ftperr(format!("TLS handshake failed: {e}")))?;
+            stream.reader = BufReader::new(Box::new(tls_io));
+        }
+
+        let (code, text) = stream.command(&format!("USER {}", config.username)).await?;
+        match code {
+            230 => {}
+            331 | 332 => {
+                stream.send(&format!("PASS {}", config.password)).await?;
+                let (code, text) = stream.read_reply().await?;
+                if code != 230 && code != 202 {
+                    return Err(ftperr(format!("Login failed: {code} {text}")));
+                }
+            }
+            _ => return Err(ftperr(format!("Login failed: {code} {text}"))),
+        }
+
+        if tls.is_some() {
+            stream.expect("PBSZ 0", &[200]).await?;
+            stream.expect("PROT P", &[200]).await?;
+            stream.data_tls = tls;
+        }
+
+        // FEAT is optional; servers without it get LIST and no UTF-8 switch.
+        if let Ok((211, features)) = stream.command("FEAT").await {
+            let has = |f: &str| features.lines().any(|l| l.trim().to_ascii_uppercase().starts_with(f));
+            stream.mlsd = has("MLST");
+            if has("UTF8") {
+                let _ = stream.command("OPTS UTF8 ON").await;
+            }
+        }
+        stream.expect("TYPE I", &[200]).await?;
+        Ok(stream)
+    }
+
+    /// Probe a connection that has been idle for a while.
+    pub async fn is_alive(&mut self) -> bool {
+        self.last_used.elapsed() < IDLE_CHECK
+            || matches!(self.command("NOOP").await, Ok((200, _)))
+    }
+
+    pub async fn pwd(&mut self) -> Result<String, FmError> {
+        let text = self.expect("PWD", &[257]).await?;
+        parse_pwd(&text).ok_or_else(|| ftperr(format!("Unexpected PWD reply: {text}")))
+    }
+
+    /// True if `path` is a directory (CWD into it succeeds).
+    pub async fn is_dir(&mut self, path: &str) -> Result<bool, FmError> {
+        let (code, _) = self.command(&format!("CWD {path}")).await?;
+        Ok(code == 250)
+    }
+
+    /// File size via SIZE, None if the server does not report it.
+    pub async fn size(&mut self, path: &str) -> Result<Option<u64>, FmError> {
+        let (code, text) = self.command(&format!("SIZE {path}")).await?;
+        Ok(if code == 213 { text.trim().parse().ok() } else { None })
+    }
+
+    /// List a directory, preferring machine-readable MLSD over LIST.
+    pub async fn list(&mut self, path: &str) -> Result<Vec<FtpEntry>, FmError> {
+        let (cmd, parse): (String, fn(&str) -> Option<FtpEntry>) = if self.mlsd {
+            (format!("MLSD {path}"), parse_mlsd_line)
+        } else {
+            (format!("LIST {path}"), parse_list_line)
+        };
+        let mut data = self.open_data(&cmd).await?;
+        let mut raw = Vec::new();
+        let mut buf = vec![0u8; 16 * 1024];
+        loop {
+            let n = data.read_chunk(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            raw.extend_from_slice(&buf[..n]);
+        }
+        let _ = data.finish().await;
+        self.finish_data(&cmd).await?;
+
+        let text = String::from_utf8_lossy(&raw);
+        Ok(text
+            .lines()
+            .filter_map(|line| parse(line.trim_end_matches('\r')))
+            .collect())
+    }
+
+    /// Open a data connection and start `cmd` (LIST, RETR, STOR, …) on it.
+    /// After the transfer, call `finish_data` to read the completion reply.
+    pub async fn open_data(&mut self, cmd: &str) -> Result<DataStream, FmError> {
+        let tcp = if self.passive {
+            let addr = self.passive_addr().await?;
+            let tcp = tokio::time::timeout(FTP_TIMEOUT, TcpStream::connect(addr))
+                .await
+                .map_err(|_| ftperr("Data connection timed out"))?
+                .map_err(|e| ftperr(format!("Data connection failed: {e}")))?;
+            self.expect(cmd, &[125, 150]).await?;
+            tcp
+        } else {
+            let listener = TcpListener::bind((self.local_ip, 0)).await?;
+            let local = listener.local_addr()?;
+            let port_cmd = match local.ip() {
+                IpAddr::V4(ip) => {
+                    let o = ip.octets();
+                    let p = local.port();
+                    format!("PORT {},{},{},{},{},{}", o[0], o[1], o[2], o[3], p >> 8, p & 0xff)
+                }
+                IpAddr::V6(ip) => format!("EPRT |2|{}|{}|", ip, local.port()),
+            };
+            self.expect(&port_cmd, &[200]).await?;
+            self.expect(cmd, &[125, 150]).await?;
+            let (tcp, _) = tokio::time::timeout(FTP_TIMEOUT, listener.accept())
+                .await
+                .map_err(|_| ftperr("Server did not open the data connection"))??;
+            tcp
+        };
+
+        let io: Box<dyn Io> = match &self.data_tls {
+            Some((connector, name)) => Box::new(
+                connector
+                    .connect(name.clone(), tcp)
+                    .await
+                    .map_err(|e| ftperr(format!("Data TLS handshake failed: {e}")))?,
+            ),
+            None => Box::new(tcp),
+        };
+        Ok(DataStream(io))
+    }
+
+    /// Read the reply that ends a data transfer.
+    pub async fn finish_data(&mut self, cmd: &str) -> Result<(), FmError> {
+        self.read_expect(cmd, &[226, 250]).await.map(|_| ())
+    }
+
+    /// Address for a passive data connection. The host part of a PASV reply is
+    /// ignored in favour of the control connection's peer, which also works
+    /// behind NAT.
+    async fn passive_addr(&mut self) -> Result<SocketAddr, FmError> {
+        if self.epsv {
+            let (code, text) = self.command("EPSV").await?;
+            if code == 229 {
+                let port = parse_epsv(&text)
+                    .ok_or_else(|| ftperr(format!("Unexpected EPSV reply: {text}")))?;
+                return Ok(SocketAddr::new(self.peer_ip, port));
+            }
+            self.epsv = false;
+        }
+        let text = self.expect("PASV", &[227]).await?;
+        let (_, port) =
+            parse_pasv(&text).ok_or_else(|| ftperr(format!("Unexpected PASV reply: {text}")))?;
+        Ok(SocketAddr::new(self.peer_ip, port))
+    }
+
+    /// Send a command and return the reply code and text.
+    pub async fn command(&mut self, cmd: &str) -> Result<(u32, String), FmError> {
+        self.send(cmd).await?;
+        self.read_reply().await
+    }
+
+    /// Send a command and fail unless the reply code is one of `codes`.
+    pub async fn expect(&mut self, cmd: &str, codes: &[u32]) -> Result<String, FmError> {
+        self.send(cmd).await?;
+        self.read_expect(cmd, codes).await
+    }
+
+    async fn read_expect(&mut self, cmd: &str, codes: &[u32]) -> Result<String, FmError> {
+        let (code, text) = self.read_reply().await?;
+        if codes.contains(&code) {
+            Ok(text)
+        } else {
+            // Only the verb, so PASS never ends up in an error message.
+            let verb = cmd.split(' ').next().unwrap_or(cmd);
+            Err(ftperr(format!("{verb}: {code} {}", text.trim())))
+        }
+    }
+
+    async fn send(&mut self, cmd: &str) -> Result<(), FmError> {
+        self.last_used = Instant::now();
+        let io = self.reader.get_mut();
+        io.write_all(format!("{cmd}\r\n").as_bytes()).await?;
+        io.flush().await?;
+        Ok(())
+    }
+
+    /// Read a possibly multi-line reply (`123-…` continued until `123 …`).
+    async fn read_reply(&mut self) -> Result<(u32, String), FmError> {
+        let first = self.read_line().await?;
+        let code: u32 = first
+            .get(..3)
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| ftperr(format!("Malformed reply: {first}")))?;
+        let mut text = first[3..].trim_start_matches([' ', '-']).to_string();
+        if first.as_bytes().get(3) == Some(&b'-') {
+            let end = format!("{code} ");
+            loop {
+                let line = self.read_line().await?;
+                if line.starts_with(&end) || line == code.to_string() {
+                    break;
+                }
+                text.push('\n');
+                text.push_str(&line);
+            }
+        }
+        Ok((code, text))
+    }
+
+    async fn read_line(&mut self) -> Result<String, FmError> {
+        let mut line = Vec::new();
+        let n = tokio::time::timeout(FTP_TIMEOUT, self.reader.read_until(b'\n', &mut line))
+            .await
+            .map_err(|_| ftperr("Timed out waiting for server reply"))??;
+        if n == 0 {
+            return Err(ftperr("Connection closed by server"));
+        }
+        let line = String::from_utf8_lossy(&line);
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+// ── TLS ──────────────────────────────────────────────────────────────────────
+
+/// One connector per session so data connections can resume the control
+/// connection's TLS session, which servers like vsftpd require.
+fn tls_connector(accept_invalid_certs: bool) -> Result<TlsConnector, FmError> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| ftperr(format!("TLS setup failed: {e}")))?;
+    let config = if accept_invalid_certs {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)))
+            .with_no_client_auth()
+    } else {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+fn server_name(host: &str) -> Result<ServerName<'static>, FmError> {
+    ServerName::try_from(host.to_string())
+        .map_err(|e| ftperr(format!("Invalid TLS server name {host}: {e}")))
+}
+
+/// Accepts any certificate but still checks handshake signatures.
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
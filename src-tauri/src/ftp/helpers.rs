@@ -0,0 +1,363 @@
+use std::net::Ipv4Addr;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
+
+use crate::models::FmError;
+
+/// Convenience constructor for FTP errors.
+pub fn ftperr(msg: impl Into<String>) -> FmError {
+    FmError::Ftp(msg.into())
+}
+
+/// Build an `ftp://host:port/path` URI.
+pub fn ftp_path(host: &str, port: u16, path: &str) -> String {
+    format!("ftp://{}:{}{}", host, port, path)
+}
+
+/// Strip the `ftp://host:port` prefix, returning just the remote path.
+pub fn strip_ftp_prefix(path: &str) -> &str {
+    if let Some(rest) = path.strip_prefix("ftp://") {
+        if let Some(idx) = rest.find('/') {
+            return &rest[idx..];
+        }
+        return "/";
+    }
+    path
+}
+
+// ── Replies ─────────────────────────────────────────────────────────────────
+
+/// Address from a `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)` reply.
+pub fn parse_pasv(text: &str) -> Option<(Ipv4Addr, u16)> {
+    let start = text.find('(')?;
+    let end = text[start..].find(')')? + start;
+    let nums: Vec<u8> = text[start + 1..end]
+        .split(',')
+        .map(|n| n.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    if nums.len() != 6 {
+        return None;
+    }
+    let ip = Ipv4Addr::new(nums[0], nums[1], nums[2], nums[3]);
+    Some((ip, u16::from(nums[4]) << 8 | u16::from(nums[5])))
+}
+
+/// Port from a `229 Entering Extended Passive Mode (|||port|)` reply.
+pub fn parse_epsv(text: &str) -> Option<u16> {
+    let start = text.find('(')?;
+    let end = text[start..].find(')')? + start;
+    let inner = &text[start + 1..end];
+    let delim = inner.chars().next()?;
+    inner.split(delim).nth(3)?.parse().ok()
+}
+
+/// Path from a `257 "/path" is current directory` reply (`""` escapes `"`).
+pub fn parse_pwd(text: &str) -> Option<String> {
+    let start = text.find('"')? + 1;
+    let mut out = String::new();
+    let mut chars = text[start..].chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                out.push('"');
+            } else {
+                return Some(out);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    None
+}
+
+/// Epoch milliseconds from an MLSD/MDTM timestamp (`YYYYMMDDHHMMSS[.sss]`, UTC).
+pub fn parse_ftp_time(value: &str) -> Option<i64> {
+    let (secs, frac) = value.split_once('.').unwrap_or((value, ""));
+    let dt = NaiveDateTime::parse_from_str(secs, "%Y%m%d%H%M%S").ok()?;
+    let digits: String = frac.chars().take_while(char::is_ascii_digit).take(3).collect();
+    let millis = format!("{:0<3}", digits).parse::<i64>().unwrap_or(0);
+    Some(dt.and_utc().timestamp_millis() + millis)
+}
+
+// ── Listings ────────────────────────────────────────────────────────────────
+
+/// One entry of a directory listing, parsed from MLSD or LIST output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FtpEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    pub size: u64,
+    /// Epoch milliseconds, 0 if unknown.
+    pub modified: i64,
+    /// Unix mode including file type bits, 0 if unknown.
+    pub permissions: u32,
+    pub owner: String,
+    pub group: String,
+}
+
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Parse one MLSD line (`fact=value;fact=value; name`). Returns None for the
+/// `.`/`..` entries (`type=cdir`/`pdir`) and malformed lines.
+pub fn parse_mlsd_line(line: &str) -> Option<FtpEntry> {
+    let (facts, name) = line.split_once(' ')?;
+    let mut entry = FtpEntry {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    let mut mode = None;
+    for fact in facts.split(';').filter(|f| !f.is_empty()) {
+        let (key, value) = fact.split_once('=')?;
+        match key.to_ascii_lowercase().as_str() {
+            "type" => match value.to_ascii_lowercase().as_str() {
+                "cdir" | "pdir" => return None,
+                "dir" => entry.is_dir = true,
+                "os.unix=symlink" | "os.unix=slink" => entry.is_symlink = true,
+                t if t.starts_with("os.unix=slink:") => {
+                    entry.is_symlink = true;
+                    entry.symlink_target = Some(value["OS.unix=slink:".len()..].to_string());
+                }
+                _ => {}
+            },
+            "size" | "sizd" => entry.size = value.parse().unwrap_or(0),
+            "modify" => entry.modified = parse_ftp_time(value).unwrap_or(0),
+            "unix.mode" => mode = u32::from_str_radix(value, 8).ok(),
+            "unix.owner" | "unix.ownername" => entry.owner = value.to_string(),
+            "unix.uid" if entry.owner.is_empty() => entry.owner = value.to_string(),
+            "unix.group" | "unix.groupname" => entry.group = value.to_string(),
+            "unix.gid" if entry.group.is_empty() => entry.group = value.to_string(),
+            _ => {}
+        }
+    }
+    if entry.is_dir {
+        entry.size = 0;
+    }
+    if let Some(mode) = mode {
+        entry.permissions = mode & 0o7777 | file_type_bits(&entry);
+    }
+    Some(entry)
+}
+
+/// Parse one LIST line in Unix (`ls -l`) or DOS/IIS format. Returns None for
+/// `total` lines, `.`/`..` and anything unrecognised.
+pub fn parse_list_line(line: &str) -> Option<FtpEntry> {
+    let entry = parse_unix_list_line(line).or_else(|| parse_dos_list_line(line))?;
+    if entry.name == "." || entry.name == ".." {
+        return None;
+    }
+    Some(entry)
+}
+
+fn parse_unix_list_line(line: &str) -> Option<FtpEntry> {
+    // perms, links, owner, group, size, month, day, time/year, name
+    let (fields, name) = split_fields(line, 8)?;
+    let perms = fields[0];
+    let kind = perms.chars().next()?;
+    if perms.len() < 10 || !perms.is_ascii() || !"-dlcbps".contains(kind) {
+        return None;
+    }
+
+    let mut entry = FtpEntry {
+        name: name.to_string(),
+        is_dir: kind == 'd',
+        is_symlink: kind == 'l',
+        size: fields[4].parse().ok()?,
+        owner: fields[2].to_string(),
+        group: fields[3].to_string(),
+        modified: parse_list_time(fields[5], fields[6], fields[7]).unwrap_or(0),
+        ..Default::default()
+    };
+    if entry.is_symlink {
+        if let Some((link, target)) = name.split_once(" -> ") {
+            entry.name = link.to_string();
+            entry.symlink_target = Some(target.to_string());
+        }
+    }
+    entry.permissions = mode_from_rwx(&perms[1..10]) | file_type_bits(&entry);
+    if entry.is_dir {
+        entry.size = 0;
+    }
+    Some(entry)
+}
+
+/// `01-02-24  03:04PM  <DIR>  name` or `01-02-24  03:04PM  1234 name`.
+fn parse_dos_list_line(line: &str) -> Option<FtpEntry> {
+    let (fields, name) = split_fields(line, 3)?;
+    let stamp = format!("{} {}", fields[0], fields[1]);
+    let dt = NaiveDateTime::parse_from_str(&stamp, "%m-%d-%y %I:%M%p")
+        .or_else(|_| NaiveDateTime::parse_from_str(&stamp, "%m-%d-%Y %I:%M%p"))
+        .ok()?;
+    let is_dir = fields[2].eq_ignore_ascii_case("<DIR>");
+    let size = if is_dir { 0 } else { fields[2].parse().ok()? };
+    Some(FtpEntry {
+        name: name.to_string(),
+        is_dir,
+        size,
+        modified: dt.and_utc().timestamp_millis(),
+        ..Default::default()
+    })
+}
+
+/// Split off `n` whitespace-separated fields; the rest of the line (which may
+/// contain spaces) is returned as the final field.
+fn split_fields(line: &str, n: usize) -> Option<(Vec<&str>, &str)> {
+    let mut fields = Vec::with_capacity(n);
+    let mut rest = line.trim_start();
+    while fields.len() < n {
+        let end = rest.find(char::is_whitespace)?;
+        fields.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    if rest.is_empty() {
+        return None;
+    }
+    Some((fields, rest))
+}
+
+/// `Jan  2 03:04` (within the last year) or `Jan  2  2023`.
+fn parse_list_time(month: &str, day: &str, time_or_year: &str) -> Option<i64> {
+    let month = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ]
+    .iter()
+    .position(|m| month.eq_ignore_ascii_case(m))? as u32
+        + 1;
+    let day: u32 = day.parse().ok()?;
+
+    let (year, hour, minute) = match time_or_year.split_once(':') {
+        Some((h, m)) => {
+            let now = Utc::now().naive_utc();
+            // No year means within the past six months; a date ahead of now
+            // therefore belongs to last year.
+            let this_year = NaiveDate::from_ymd_opt(now.year(), month, day)?;
+            let year = if this_year > now.date() + chrono::Duration::days(1) {
+                now.year() - 1
+            } else {
+                now.year()
+            };
+            (year, h.parse().ok()?, m.parse().ok()?)
+        }
+        None => (time_or_year.parse().ok()?, 0, 0),
+    };
+    let dt = NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, 0)?;
+    Some(dt.and_utc().timestamp_millis())
+}
+
+/// Mode bits from the 9-character `rwxr-xr-x` part of a listing.
+fn mode_from_rwx(rwx: &str) -> u32 {
+    let b = rwx.as_bytes();
+    let mut mode = 0;
+    for (i, &c) in b.iter().enumerate().take(9) {
+        let bit = 1 << (8 - i);
+        if c != b'-' && c != b'S' && c != b'T' {
+            mode |= bit;
+        }
+    }
+    // setuid / setgid / sticky
+    if matches!(b[2], b's' | b'S') {
+        mode |= 0o4000;
+    }
+    if matches!(b[5], b's' | b'S') {
+        mode |= 0o2000;
+    }
+    if matches!(b[8], b't' | b'T') {
+        mode |= 0o1000;
+    }
+    mode
+}
+
+fn file_type_bits(entry: &FtpEntry) -> u32 {
+    if entry.is_symlink {
+        S_IFLNK
+    } else if entry.is_dir {
+        S_IFDIR
+    } else {
+        S_IFREG
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passive_replies() {
+        assert_eq!(
+            parse_pasv("Entering Passive Mode (192,168,1,20,19,137)."),
+            Some((Ipv4Addr::new(192, 168, 1, 20), 5001))
+        );
+        assert_eq!(parse_pasv("Entering Passive Mode (1,2,3)"), None);
+        assert_eq!(parse_epsv("Entering Extended Passive Mode (|||6446|)"), Some(6446));
+        assert_eq!(parse_pwd(r#""/home/a ""b""" is the current directory"#).as_deref(), Some(r#"/home/a "b""#));
+    }
+
+    #[test]
+    fn test_mlsd_line() {
+        let e = parse_mlsd_line("type=file;size=1024;modify=20240102030405.5;UNIX.mode=0644;UNIX.owner=alice; report 1.txt").unwrap();
+        assert_eq!(e.name, "report 1.txt");
+        assert_eq!(e.size, 1024);
+        assert_eq!(e.modified, 1_704_164_645_500);
+        assert_eq!(e.permissions, 0o100644);
+        assert_eq!(e.owner, "alice");
+
+        let d = parse_mlsd_line("type=dir;modify=20240102030405; docs").unwrap();
+        assert!(d.is_dir);
+        assert!(parse_mlsd_line("type=cdir; .").is_none());
+    }
+
+    #[test]
+    fn test_unix_list_line() {
+        let e = parse_list_line("-rw-r--r--   1 alice  staff    2048 Mar  4  2021 my file.txt").unwrap();
+        assert_eq!(e.name, "my file.txt");
+        assert_eq!(e.size, 2048);
+        assert_eq!(e.owner, "alice");
+        assert_eq!(e.permissions, 0o100644);
+        assert_eq!(e.modified, 1_614_816_000_000);
+
+        let l = parse_list_line("lrwxrwxrwx 1 root root 7 Jan 1 10:00 current -> v1.2").unwrap();
+        assert!(l.is_symlink);
+        assert_eq!(l.name, "current");
+        assert_eq!(l.symlink_target.as_deref(), Some("v1.2"));
+
+        let d = parse_list_line("drwxr-sr-t 2 root root 4096 Jan 1 10:00 shared").unwrap();
+        assert!(d.is_dir);
+        assert_eq!(d.permissions, 0o043755);
+
+        assert!(parse_list_line("total 12").is_none());
+        assert!(parse_list_line("-rwxr-xr-é 1 a b 0 Jan 1 2024 x").is_none());
+        assert!(parse_list_line("drwxr-xr-x 2 root root 4096 Jan 1 10:00 ..").is_none());
+    }
+
+    #[test]
+    fn test_dos_list_line() {
+        let d = parse_list_line("01-02-24  03:04PM       <DIR>          Reports").unwrap();
+        assert!(d.is_dir);
+        assert_eq!(d.name, "Reports");
+        let f = parse_list_line("12-31-2023  11:59AM             1234 a b.csv").unwrap();
+        assert_eq!(f.size, 1234);
+        assert_eq!(f.name, "a b.csv");
+    }
+
+    #[test]
+    fn test_ftp_time() {
+        assert_eq!(parse_ftp_time("20240115103000"), Some(1705314600000));
+        assert_eq!(parse_ftp_time("20240115103000.5"), Some(1705314600500));
+        assert_eq!(parse_ftp_time("20240115103000.123456"), Some(1705314600123));
+        assert_eq!(parse_ftp_time("20240115103000.é"), Some(1705314600000));
+        assert_eq!(parse_ftp_time("2024011510300é"), None);
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        assert_eq!(strip_ftp_prefix("ftp://host:21/pub/file"), "/pub/file");
+        assert_eq!(strip_ftp_prefix("ftp://host:21"), "/");
+        assert_eq!(strip_ftp_prefix("/plain"), "/plain");
+    }
+}
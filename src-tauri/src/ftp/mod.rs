@@ -0,0 +1,7 @@
+pub mod client;
+pub mod helpers;
+pub mod service;
+
+pub use client::{FtpConnection, FtpState};
+pub use helpers::ftperr;
+pub use service::FtpService;
@@ -0,0 +1,465 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::MutexGuard;
+
+use crate::models::{DirListing, FileEntry, FmError, ProgressEvent, TransferCheckpoint};
+use crate::storage::batch::{
+    check_stop, collect_local_files_recursive, join, parent_path, FileOutcome, TransferProgress,
+};
+
+use super::client::{FtpConfig, FtpStream};
+use super::helpers::{ftp_path, FtpEntry};
+
+/// Bytes per read from a data connection or local file.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// ── FtpService ───────────────────────────────────────────────────────────────
+
+pub struct FtpService {
+    pub control: Arc<tokio::sync::Mutex<FtpStream>>,
+    pub config: Arc<FtpConfig>,
+}
+
+impl FtpService {
+    pub fn new(control: Arc<tokio::sync::Mutex<FtpStream>>, config: Arc<FtpConfig>) -> Self {
+        Self { control, config }
+    }
+
+    /// Lock the shared control connection, logging in again first if the
+    /// server dropped it while idle.
+    async fn control(&self) -> Result<MutexGuard<'_, FtpStream>, FmError> {
+        let mut ftp = self.control.lock().await;
+        if !ftp.is_alive().await {
+            *ftp = FtpStream::connect(&self.config).await?;
+        }
+        Ok(ftp)
+    }
+
+    /// List directory contents, returning a DirListing with `..` entry.
+    pub async fn list_objects(&self, path: &str) -> Result<DirListing, FmError> {
+        let listing = self.control().await?.list(path).await?;
+        let (host, port) = (&self.config.host, self.config.port);
+
+        let mut entries = Vec::new();
+
+        // Add parent directory entry
+        let parent = parent_path(path);
+        entries.push(FileEntry {
+            name: "..".to_string(),
+            path: ftp_path(host, port, &parent),
+            size: 0,
+            is_dir: true,
+            is_symlink: false,
+            symlink_target: None,
+            modified: 0,
+            permissions: 0,
+            owner: String::new(),
+            group: String::new(),
+            extension: None,
+            git_status: None,
+            storage_class: None,
+        });
+
+        for entry in listing.into_iter().filter(|e| e.name != "." && e.name != "..") {
+            let full_path = join(path, &entry.name);
+            let entry_path = if entry.is_dir {
+                format!("{}/", full_path)
+            } else {
+                full_path
+            };
+
+            let extension = if !entry.is_dir {
+                let dot = entry.name.rfind('.');
+                dot.map(|i| entry.name[i + 1..].to_string())
+            } else {
+                None
+            };
+
+            entries.push(FileEntry {
+                name: entry.name,
+                path: ftp_path(host, port, &entry_path),
+                size: entry.size,
+                is_dir: entry.is_dir,
+                is_symlink: entry.is_symlink,
+                symlink_target: entry.symlink_target,
+                modified: entry.modified,
+                permissions: entry.permissions,
+                owner: entry.owner,
+                group: entry.group,
+                extension,
+                git_status: None,
+                storage_class: None,
+            });
+        }
+
+        Ok(DirListing {
+            path: ftp_path(host, port, path),
+            entries,
+            total_size: 0,
+            free_space: 0,
+        })
+    }
+
+    /// Delete files and directories (recursive for directories).
+    pub async fn delete(&self, paths: &[String]) -> Result<(), FmError> {
+        let mut ftp = self.control().await?;
+        for path in paths {
+            let clean = path.trim_end_matches('/');
+            if ftp.is_dir(clean).await? {
+                Box::pin(delete_dir_recursive(&mut ftp, clean)).await?;
+            } else {
+                ftp.expect(&format!("DELE {}", clean), &[250]).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename a file or directory.
+    pub async fn rename(&self, old_path: &str, new_name: &str) -> Result<(), FmError> {
+        let clean = old_path.trim_end_matches('/');
+        let parent = parent_path(clean);
+        let new_path = join(&parent, new_name);
+        let mut ftp = self.control().await?;
+        ftp.expect(&format!("RNFR {}", clean), &[350]).await?;
+        ftp.expect(&format!("RNTO {}", new_path), &[250]).await?;
+        Ok(())
+    }
+
+    /// Create a directory.
+    pub async fn create_folder(&self, path: &str) -> Result<(), FmError> {
+        let clean = path.trim_end_matches('/');
+        self.control()
+            .await?
+            .expect(&format!("MKD {}", clean), &[257])
+            .await?;
+        Ok(())
+    }
+
+    /// Download remote files to a local destination directory. Transfers run
+    /// one file at a time over their own connection so browsing stays
+    /// responsive. Returns None on success, Some(checkpoint) on pause.
+    pub async fn download(
+        &self,
+        remote_paths: &[String],
+        local_dest: &str,
+        op_id: &str,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+        resume: bool,
+    ) -> Result<Option<TransferCheckpoint>, FmError> {
+        let mut ftp = FtpStream::connect(&self.config).await?;
+
+        // First pass: collect all files and calculate total size
+        let mut file_list: Vec<(String, String, u64)> = Vec::new(); // (remote_path, local_path, size)
+        for remote_path in remote_paths {
+            let clean = remote_path.trim_end_matches('/');
+            let name = clean.rsplit('/').next().unwrap_or(clean);
+            let local_target = format!("{}/{}", local_dest.trim_end_matches('/'), name);
+
+            if ftp.is_dir(clean).await? {
+                Box::pin(collect_remote_files(&mut ftp, clean, &local_target, &mut file_list))
+                    .await?;
+            } else {
+                let size = ftp.size(clean).await?.unwrap_or(0);
+                file_list.push((clean.to_string(), local_target, size));
+            }
+        }
+
+        // Ensure parent directories exist
+        for (_, local, _) in &file_list {
+            if let Some(parent) = Path::new(local).parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(FmError::Io)?;
+            }
+        }
+
+        let progress = TransferProgress::new(op_id, &file_list, |(_, _, s)| *s, on_progress);
+        let mut results = Vec::new();
+        for (remote, local, size) in &file_list {
+            let name = remote.rsplit('/').next().unwrap_or(remote);
+            let result = download_file(&mut ftp, remote, local, *size, resume, cancel, pause, &mut |n| {
+                progress.add_bytes(n, name)
+            })
+            .await;
+            progress.finish_file(&result, name);
+            let done = matches!(result, Ok(FileOutcome::Done));
+            results.push((remote.clone(), result));
+            if !done {
+                break;
+            }
+        }
+        progress.outcome(results)
+    }
+
+    /// Upload local files to a remote directory, one file at a time over a
+    /// dedicated connection. Returns None on success, Some(checkpoint) on pause.
+    pub async fn upload(
+        &self,
+        local_paths: &[String],
+        remote_dest: &str,
+        op_id: &str,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+        resume: bool,
+    ) -> Result<Option<TransferCheckpoint>, FmError> {
+        // Collect all local files
+        let mut file_list: Vec<(PathBuf, String, u64)> = Vec::new();
+        for local_path in local_paths {
+            let path = Path::new(local_path);
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let remote_target = join(remote_dest, &name);
+
+            if path.is_dir() {
+                collect_local_files_recursive(path, &remote_target, &mut file_list)?;
+            } else {
+                let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+                file_list.push((path.to_path_buf(), remote_target, size));
+            }
+        }
+
+        let mut ftp = FtpStream::connect(&self.config).await?;
+
+        // Ensure remote parent directories exist
+        let mut parents: Vec<&str> = file_list
+            .iter()
+            .filter_map(|(_, remote, _)| remote.rsplit_once('/').map(|(p, _)| p))
+            .collect();
+        parents.sort_unstable();
+        parents.dedup();
+        for parent in parents {
+            Box::pin(ensure_remote_dir(&mut ftp, parent)).await?;
+        }
+
+        let progress = TransferProgress::new(op_id, &file_list, |(_, _, s)| *s, on_progress);
+        let mut results = Vec::new();
+        for (local, remote, size) in &file_list {
+            let name = local
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let result = upload_file(&mut ftp, local, remote, *size, resume, cancel, pause, &mut |n| {
+                progress.add_bytes(n, &name)
+            })
+            .await;
+            progress.finish_file(&result, &name);
+            let done = matches!(result, Ok(FileOutcome::Done));
+            results.push((local.to_string_lossy().to_string(), result));
+            if !done {
+                break;
+            }
+        }
+        progress.outcome(results)
+    }
+
+    /// Download a remote file to a temp location, returning the local path.
+    pub async fn download_temp(&self, remote_path: &str) -> Result<String, FmError> {
+        let name = remote_path.rsplit('/').next().unwrap_or("file");
+        let tmp_dir = std::env::temp_dir().join("furman-ftp");
+        std::fs::create_dir_all(&tmp_dir).map_err(FmError::Io)?;
+        let local_path = tmp_dir.join(name);
+
+        let mut ftp = self.control().await?;
+        let cmd = format!("RETR {}", remote_path);
+        let mut data = ftp.open_data(&cmd).await?;
+        let mut content = Vec::new();
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let n = data.read_chunk(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            content.extend_from_slice(&buf[..n]);
+        }
+        let _ = data.finish().await;
+        ftp.finish_data(&cmd).await?;
+
+        tokio::fs::write(&local_path, &content)
+            .await
+            .map_err(FmError::Io)?;
+
+        Ok(local_path.to_string_lossy().to_string())
+    }
+
+    /// Write text content to a remote file.
+    pub async fn put_text(&self, remote_path: &str, content: &str) -> Result<(), FmError> {
+        let mut ftp = self.control().await?;
+        let cmd = format!("STOR {}", remote_path);
+        let mut data = ftp.open_data(&cmd).await?;
+        data.write_all(content.as_bytes()).await?;
+        data.finish().await?;
+        ftp.finish_data(&cmd).await
+    }
+}
+
+// ── Transfers ────────────────────────────────────────────────────────────────
+
+/// Copy one remote file to `local`. With `resume`, a shorter existing local
+/// file is continued via REST and one of the same size is skipped.
+async fn download_file(
+    ftp: &mut FtpStream,
+    remote: &str,
+    local: &str,
+    size: u64,
+    resume: bool,
+    cancel: &AtomicBool,
+    pause: &AtomicBool,
+    on_bytes: &mut (dyn FnMut(u64) + Send),
+) -> Result<FileOutcome, FmError> {
+    if check_stop(cancel, pause)? {
+        return Ok(FileOutcome::Paused);
+    }
+
+    let existing = match tokio::fs::metadata(local).await {
+        Ok(meta) if resume => meta.len(),
+        _ => 0,
+    };
+    if existing > 0 && existing == size {
+        on_bytes(size);
+        return Ok(FileOutcome::Done);
+    }
+    let offset = if existing < size { existing } else { 0 };
+    if offset > 0 {
+        ftp.expect(&format!("REST {}", offset), &[350]).await?;
+    }
+
+    let cmd = format!("RETR {}", remote);
+    let mut data = ftp.open_data(&cmd).await?;
+    let mut file = if offset > 0 {
+        tokio::fs::OpenOptions::new().append(true).open(local).await?
+    } else {
+        tokio::fs::File::create(local).await?
+    };
+    on_bytes(offset);
+
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        if check_stop(cancel, pause)? {
+            // The partial file is kept for a later resume; the connection
+            // is discarded with the aborted transfer.
+            file.flush().await?;
+            return Ok(FileOutcome::Paused);
+        }
+        let n = data.read_chunk(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n]).await?;
+        on_bytes(n as u64);
+    }
+    file.flush().await?;
+    let _ = data.finish().await;
+    ftp.finish_data(&cmd).await?;
+    Ok(FileOutcome::Done)
+}
+
+/// Copy one local file to `remote`. With `resume`, a shorter existing remote
+/// file is continued via APPE and one of the same size is skipped.
+async fn upload_file(
+    ftp: &mut FtpStream,
+    local: &Path,
+    remote: &str,
+    size: u64,
+    resume: bool,
+    cancel: &AtomicBool,
+    pause: &AtomicBool,
+    on_bytes: &mut (dyn FnMut(u64) + Send),
+) -> Result<FileOutcome, FmError> {
+    if check_stop(cancel, pause)? {
+        return Ok(FileOutcome::Paused);
+    }
+
+    let existing = if resume { ftp.size(remote).await?.unwrap_or(0) } else { 0 };
+    if existing > 0 && existing == size {
+        on_bytes(size);
+        return Ok(FileOutcome::Done);
+    }
+    let offset = if existing < size { existing } else { 0 };
+
+    let mut file = tokio::fs::File::open(local).await?;
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset)).await?;
+    }
+    let cmd = if offset > 0 {
+        format!("APPE {}", remote)
+    } else {
+        format!("STOR {}", remote)
+    };
+    let mut data = ftp.open_data(&cmd).await?;
+    on_bytes(offset);
+
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        if check_stop(cancel, pause)? {
+            // Closing the data connection ends the upload early; the server
+            // keeps what it received, which a resumed upload appends to.
+            data.finish().await?;
+            ftp.finish_data(&cmd).await?;
+            return Ok(FileOutcome::Paused);
+        }
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        data.write_all(&buf[..n]).await?;
+        on_bytes(n as u64);
+    }
+    data.finish().await?;
+    ftp.finish_data(&cmd).await?;
+    Ok(FileOutcome::Done)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn delete_dir_recursive(ftp: &mut FtpStream, path: &str) -> Result<(), FmError> {
+    let entries: Vec<FtpEntry> = ftp.list(path).await?;
+    for entry in entries.iter().filter(|e| e.name != "." && e.name != "..") {
+        let child = join(path, &entry.name);
+        if entry.is_dir {
+            Box::pin(delete_dir_recursive(ftp, &child)).await?;
+        } else {
+            ftp.expect(&format!("DELE {}", child), &[250]).await?;
+        }
+    }
+    ftp.expect(&format!("RMD {}", path), &[250]).await?;
+    Ok(())
+}
+
+/// Recursively collect files for download.
+async fn collect_remote_files(
+    ftp: &mut FtpStream,
+    remote_dir: &str,
+    local_dir: &str,
+    out: &mut Vec<(String, String, u64)>,
+) -> Result<(), FmError> {
+    let entries = ftp.list(remote_dir).await?;
+    for entry in entries.iter().filter(|e| e.name != "." && e.name != "..") {
+        let remote_child = join(remote_dir, &entry.name);
+        let local_child = format!("{}/{}", local_dir, entry.name);
+        if entry.is_dir {
+            Box::pin(collect_remote_files(ftp, &remote_child, &local_child, out)).await?;
+        } else {
+            out.push((remote_child, local_child, entry.size));
+        }
+    }
+    Ok(())
+}
+
+/// Create `path` and any missing parents.
+async fn ensure_remote_dir(ftp: &mut FtpStream, path: &str) -> Result<(), FmError> {
+    if path.is_empty() || ftp.is_dir(path).await? {
+        return Ok(());
+    }
+    Box::pin(ensure_remote_dir(ftp, &parent_path(path))).await?;
+    ftp.expect(&format!("MKD {}", path), &[257]).await?;
+    Ok(())
+}
@@ -1,13 +1,22 @@
 mod commands;
+pub mod bandwidth;
 pub mod cloudfront;
+pub mod ftp;
 pub mod models;
 pub mod oidc;
+pub mod queue;
 pub mod s3;
 pub mod sftp;
+pub mod storage;
+pub mod sync;
+pub mod webdav;
 
 use commands::file::FileOpState;
+use commands::queue::QueueState;
+use ftp::FtpState;
 use s3::S3State;
 use sftp::SftpState;
+use webdav::WebDavState;
 use commands::search::SearchState;
 use commands::sync::SyncState;
 use commands::terminal::TerminalState;
@@ -53,9 +62,12 @@ pub fn run() {
         .manage(TerminalState(Mutex::new(HashMap::new())))
         .manage(S3State(Mutex::new(HashMap::new())))
         .manage(SftpState::default())
+        .manage(FtpState::default())
+        .manage(WebDavState::default())
         .manage(SearchState(Mutex::new(HashMap::new())))
         .manage(FileOpState(Mutex::new(HashMap::new())))
         .manage(SyncState(Mutex::new(HashMap::new())))
+        .manage(QueueState::load())
         .plugin(tauri_plugin_drag::init())
         .setup(|app| {
             let mut targets = vec![
@@ -122,6 +134,7 @@ pub fn run() {
             commands::watcher::unwatch_directory,
             // terminal commands
             commands::terminal::terminal_spawn,
+            commands::terminal::terminal_spawn_remote,
             commands::terminal::terminal_write,
             commands::terminal::terminal_resize,
             commands::terminal::terminal_close,
@@ -182,6 +195,8 @@ pub fn run() {
             commands::s3::s3_get_bucket_logging,
             commands::s3::s3_put_bucket_logging,
             commands::s3::s3_set_bandwidth_limit,
+            commands::s3::s3_set_retry_policy,
+            commands::s3::s3_get_retry_policy,
             commands::s3::s3_list_kms_keys,
             commands::s3::s3_upload_encrypted,
             commands::s3::s3_is_object_encrypted,
@@ -222,9 +237,43 @@ pub fn run() {
             commands::sftp::sftp_create_folder,
             commands::sftp::sftp_download,
             commands::sftp::sftp_upload,
+            commands::sftp::sftp_copy,
             commands::sftp::sftp_download_temp,
             commands::sftp::sftp_put_text,
             commands::sftp::sftp_head,
+            commands::sftp::sftp_set_permissions,
+            commands::sftp::sftp_chown,
+            commands::sftp::sftp_symlink,
+            commands::sftp::sftp_readlink,
+            commands::sftp::sftp_trust_host_key,
+            commands::sftp::sftp_auth_respond,
+            commands::sftp::sftp_list_config_hosts,
+            commands::sftp::sftp_set_transfer_concurrency,
+            commands::sftp::sftp_exec,
+            // ftp commands
+            commands::ftp::ftp_connect,
+            commands::ftp::ftp_disconnect,
+            commands::ftp::ftp_list_objects,
+            commands::ftp::ftp_delete,
+            commands::ftp::ftp_rename,
+            commands::ftp::ftp_create_folder,
+            commands::ftp::ftp_download,
+            commands::ftp::ftp_upload,
+            commands::ftp::ftp_download_temp,
+            commands::ftp::ftp_put_text,
+            // webdav commands
+            commands::webdav::webdav_connect,
+            commands::webdav::webdav_disconnect,
+            commands::webdav::webdav_list_objects,
+            commands::webdav::webdav_delete,
+            commands::webdav::webdav_rename,
+            commands::webdav::webdav_create_folder,
+            commands::webdav::webdav_copy,
+            commands::webdav::webdav_move,
+            commands::webdav::webdav_download,
+            commands::webdav::webdav_upload,
+            commands::webdav::webdav_download_temp,
+            commands::webdav::webdav_put_text,
             // cloudfront commands
             commands::cloudfront::cf_list_distributions,
             commands::cloudfront::cf_get_distribution,
@@ -242,8 +291,27 @@ pub fn run() {
             // search commands
             commands::search::search_files,
             commands::search::cancel_search,
+            // bandwidth commands
+            commands::bandwidth::set_bandwidth_limit,
+            commands::bandwidth::set_bandwidth_schedule,
+            commands::bandwidth::get_bandwidth_limits,
+            // storage commands
+            commands::storage::transfer,
+            commands::storage::s3_to_sftp,
+            commands::storage::sftp_to_s3,
+            // transfer queue commands
+            commands::queue::queue_add,
+            commands::queue::queue_list,
+            commands::queue::queue_reorder,
+            commands::queue::queue_retry,
+            commands::queue::queue_pause,
+            commands::queue::queue_resume,
+            commands::queue::queue_remove,
+            commands::queue::queue_clear_done,
+            commands::queue::queue_set_concurrency,
             // sync commands
             commands::sync::sync_diff,
+            commands::sync::sync_apply,
             commands::sync::cancel_sync,
             // keychain commands
             commands::keychain::keychain_set,
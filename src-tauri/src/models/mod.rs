@@ -54,6 +54,9 @@ pub struct ProgressEvent {
     pub current_file: String,
     pub files_done: u32,
     pub files_total: u32,
+    /// S3 requests retried so far in this operation.
+    #[serde(default)]
+    pub retries: u32,
 }
 
 // ── FmError ──────────────────────────────────────────────────────────────────
@@ -77,6 +80,30 @@ pub enum FmError {
 
     #[error("SFTP: {0}")]
     Sftp(String),
+
+    #[error("FTP: {0}")]
+    Ftp(String),
+
+    #[error("WebDAV: {0}")]
+    WebDav(String),
+
+    /// Server presented a key not in known_hosts; the UI offers to trust it.
+    #[error("Unknown host key for {host}:{port}: {key_type} {fingerprint}")]
+    UnknownHostKey {
+        host: String,
+        port: u16,
+        key_type: String,
+        fingerprint: String,
+    },
+
+    /// Server key differs from known_hosts or is revoked.
+    #[error("Host key rejected for {host}:{port} ({reason}): {fingerprint}")]
+    HostKeyRejected {
+        host: String,
+        port: u16,
+        reason: String,
+        fingerprint: String,
+    },
 }
 
 // Tauri v2 requires command return errors to implement `Into<InvokeError>`.
@@ -203,7 +230,10 @@ pub enum SearchEvent {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncEntry {
     pub relative_path: String,
-    pub status: String, // "new" | "modified" | "deleted" | "same"
+    // Mirror: "new" | "modified" | "deleted" | "same"
+    // Two-way: "left_changed" | "right_changed" | "left_deleted" | "right_deleted"
+    //          | "conflict" | "same" (left = source)
+    pub status: String,
     pub source_size: u64,
     pub dest_size: u64,
     pub source_modified: i64, // epoch ms, 0 if missing
@@ -217,14 +247,34 @@ pub struct SyncEntry {
 pub enum SyncEvent {
     Entry(SyncEntry),
     Progress { scanned: u32 },
+    /// Something the comparison had to work around, e.g. checksums that
+    /// could not be computed.
+    Warning { message: String },
     Done {
         total: u32,
         new_count: u32,
         modified: u32,
         deleted: u32,
+        conflicts: u32,
     },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncAction {
+    pub relative_path: String,
+    pub action: String, // "upload" | "download" | "copy" | "delete"
+    pub target: String, // side that is written: "dest" | "source"
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncApplyResult {
+    pub actions: Vec<SyncAction>,
+    pub conflicts: Vec<String>, // relative paths left untouched (two-way)
+    pub dry_run: bool,
+    pub checkpoint: Option<TransferCheckpoint>, // Some when paused
+}
+
 // ── S3BucketVersioning ─────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -306,14 +356,12 @@ pub struct TransferCheckpoint {
     pub files_total: u32,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct S3CompletedPart {
     pub part_number: i32,
     pub etag: String,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct S3UploadCheckpoint {
     pub files_completed: Vec<String>,
@@ -326,6 +374,106 @@ pub struct S3UploadCheckpoint {
     pub files_total: u32,
 }
 
+impl From<TransferCheckpoint> for S3UploadCheckpoint {
+    fn from(cp: TransferCheckpoint) -> Self {
+        S3UploadCheckpoint {
+            files_completed: cp.files_completed,
+            current_file_upload_id: None,
+            current_file_key: None,
+            completed_parts: Vec::new(),
+            bytes_done: cp.bytes_done,
+            bytes_total: cp.bytes_total,
+            files_done: cp.files_done,
+            files_total: cp.files_total,
+        }
+    }
+}
+
+impl From<S3UploadCheckpoint> for TransferCheckpoint {
+    fn from(cp: S3UploadCheckpoint) -> Self {
+        TransferCheckpoint {
+            files_completed: cp.files_completed,
+            bytes_done: cp.bytes_done,
+            bytes_total: cp.bytes_total,
+            files_done: cp.files_done,
+            files_total: cp.files_total,
+        }
+    }
+}
+
+// ── Transfer Queue ──────────────────────────────────────────────────────────
+
+/// A transfer kept in the persistent queue. Paths are panel paths as in the
+/// `transfer` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferJob {
+    pub id: String,
+    pub source_backend: String, // "local" | "s3" | "sftp"
+    pub source_id: String,      // connection id, "" for local
+    pub sources: Vec<String>,
+    pub dest_backend: String,
+    pub dest_id: String,
+    pub destination: String,
+    pub status: String, // "pending" | "running" | "paused" | "failed" | "done"
+    pub error: Option<String>,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u32,
+    pub files_total: u32,
+    /// Where a paused job continues: files already copied and, for uploads
+    /// to S3, the open multipart upload.
+    pub checkpoint: Option<S3UploadCheckpoint>,
+    pub created: i64, // epoch ms
+}
+
+// ── Bandwidth Limits ────────────────────────────────────────────────────────
+
+/// A bandwidth limit for a weekly time window in local time, e.g. slower
+/// transfers during business hours.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BandwidthSchedule {
+    pub days: Vec<u8>,                 // 0 = Monday … 6 = Sunday; empty = every day
+    pub start_minute: u16,             // minutes after midnight
+    pub end_minute: u16,               // exclusive; before start_minute = spans midnight
+    pub bytes_per_sec: u64,            // 0 = unlimited
+    pub connection_id: Option<String>, // None = the global limit
+}
+
+/// All bandwidth limits in bytes per second; 0 or a missing entry means
+/// unlimited. A matching schedule entry replaces the global or connection
+/// limit while it is active.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BandwidthLimits {
+    pub global: u64,
+    pub connections: HashMap<String, u64>,
+    pub transfers: HashMap<String, u64>,
+    pub schedule: Vec<BandwidthSchedule>,
+}
+
+// ── Retry Policy ────────────────────────────────────────────────────────────
+
+/// How failed S3 requests are retried. The delay before retry n is about
+/// `base * 2^(n-1)`, capped at `max_delay_ms` and jittered; throttling
+/// starts from the longer `throttle_delay_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_attempts: u32, // including the first; 1 = no retries
+    pub base_delay_ms: u64,
+    pub throttle_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay_ms: 200,
+            throttle_delay_ms: 1000,
+            max_delay_ms: 20_000,
+        }
+    }
+}
+
 // ── S3CorsRule ──────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -554,6 +702,83 @@ pub struct CfInvalidation {
     pub paths: Vec<String>,
 }
 
+// ── SshConfigHost ───────────────────────────────────────────────────────────
+
+/// A concrete `Host` alias from `~/.ssh/config` with its resolved settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshConfigHost {
+    pub alias: String,
+    pub host_name: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+/// A bastion hop for `sftp_connect`. `host` may be an ssh_config alias;
+/// unset fields are resolved the same way as for the target host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SftpJumpHost {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub auth_method: Option<String>,
+    pub password: Option<String>,
+    pub key_path: Option<String>,
+    pub key_passphrase: Option<String>,
+    pub cert_path: Option<String>,
+}
+
+// ── SftpAuthPrompt ──────────────────────────────────────────────────────────
+
+/// A keyboard-interactive challenge (password, OTP, …) from an SSH server,
+/// emitted as `"sftp-auth-prompt"`. The UI answers it with
+/// `sftp_auth_respond`, one response per prompt.
+#[derive(Debug, Clone, Serialize)]
+pub struct SftpAuthPrompt {
+    pub request_id: String,
+    pub host: String,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<SftpAuthPromptField>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SftpAuthPromptField {
+    pub prompt: String,
+    /// False for secrets that should not be shown while typed.
+    pub echo: bool,
+}
+
+// ── ExecEvent ───────────────────────────────────────────────────────────────
+
+/// Output of a remote command run by `sftp_exec`, streamed as it arrives.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ExecEvent {
+    Stdout { data: String },
+    Stderr { data: String },
+    /// Sent last. `code` is None if the command was killed by `signal`.
+    Exit {
+        code: Option<u32>,
+        signal: Option<String>,
+    },
+}
+
+// ── SftpHealthEvent ─────────────────────────────────────────────────────────
+
+/// Connection state change of an SFTP connection, emitted as `"sftp-health"`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SftpHealthEvent {
+    pub id: String,
+    /// "reconnecting" | "connected" | "disconnected"
+    pub status: String,
+    /// Reconnect attempt this event belongs to, starting at 1.
+    pub attempt: u32,
+    /// Why the last attempt failed, for "disconnected".
+    pub message: Option<String>,
+}
+
 // ── Display impls ───────────────────────────────────────────────────────────
 
 impl fmt::Display for ProgressEvent {
@@ -567,6 +792,10 @@ impl fmt::Display for ProgressEvent {
             self.files_done,
             self.files_total,
             self.current_file,
-        )
+        )?;
+        if self.retries > 0 {
+            write!(f, " ({} retries)", self.retries)?;
+        }
+        Ok(())
     }
 }
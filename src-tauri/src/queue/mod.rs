@@ -0,0 +1,3 @@
+pub mod store;
+
+pub use store::{queue_path, TransferQueue, MAX_CONCURRENCY};
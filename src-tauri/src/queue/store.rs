@@ -0,0 +1,355 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{FmError, ProgressEvent, S3UploadCheckpoint, TransferJob};
+
+// ── Queue ───────────────────────────────────────────────────────────────────
+
+/// Jobs started at once unless the user changes it.
+const DEFAULT_CONCURRENCY: usize = 2;
+/// Upper bound for `max_concurrent`, as for the frontend transfer queue.
+pub const MAX_CONCURRENCY: usize = 5;
+
+fn default_concurrency() -> usize {
+    DEFAULT_CONCURRENCY
+}
+
+/// The transfer queue, persisted as JSON under the app data directory so
+/// pending, paused and failed jobs survive a restart. Jobs run in list order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferQueue {
+    #[serde(default = "default_concurrency")]
+    pub max_concurrent: usize,
+    pub jobs: Vec<TransferJob>,
+}
+
+impl Default for TransferQueue {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_CONCURRENCY,
+            jobs: Vec::new(),
+        }
+    }
+}
+
+impl TransferQueue {
+    /// Load the queue at `path`, or an empty one if there is none. Jobs that
+    /// were pending or running when the app quit come back paused, as their
+    /// connections have to be opened again before they can continue. They
+    /// keep the last checkpoint saved while running, so resuming continues
+    /// an open multipart upload and removing the job aborts it.
+    pub fn load(path: &Path) -> Result<Self, FmError> {
+        let mut queue: Self = match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                FmError::Other(format!("corrupt transfer queue {}: {e}", path.display()))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(FmError::Io(e)),
+        };
+        for job in &mut queue.jobs {
+            if job.status == "running" || job.status == "pending" {
+                job.status = "paused".into();
+            }
+        }
+        Ok(queue)
+    }
+
+    /// Write the queue atomically (temp file + rename).
+    pub fn save(&self, path: &Path) -> Result<(), FmError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_vec(self).map_err(|e| FmError::Other(e.to_string()))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TransferJob> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Result<&mut TransferJob, FmError> {
+        self.jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| FmError::NotFound(format!("transfer job {id}")))
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<TransferJob> {
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        Some(self.jobs.remove(index))
+    }
+
+    /// Move a job to `index` in the run order (clamped to the end).
+    pub fn move_to(&mut self, id: &str, index: usize) -> Result<(), FmError> {
+        let from = self
+            .jobs
+            .iter()
+            .position(|job| job.id == id)
+            .ok_or_else(|| FmError::NotFound(format!("transfer job {id}")))?;
+        let job = self.jobs.remove(from);
+        let index = index.min(self.jobs.len());
+        self.jobs.insert(index, job);
+        Ok(())
+    }
+
+    /// Queue a failed job again; it continues from its checkpoint.
+    pub fn retry(&mut self, id: &str) -> Result<(), FmError> {
+        let job = self.get_mut(id)?;
+        if job.status != "failed" {
+            return Err(FmError::Other(format!("Transfer job is {}, not failed", job.status)));
+        }
+        job.status = "pending".into();
+        job.error = None;
+        Ok(())
+    }
+
+    /// Hold a pending job back. Returns true if the job is running; the
+    /// caller then asks it to pause and it turns "paused" when it stops.
+    pub fn pause(&mut self, id: &str) -> Result<bool, FmError> {
+        let job = self.get_mut(id)?;
+        match job.status.as_str() {
+            "running" => Ok(true),
+            "pending" => {
+                job.status = "paused".into();
+                Ok(false)
+            }
+            other => Err(FmError::Other(format!("Transfer job is {other}, cannot pause"))),
+        }
+    }
+
+    /// Queue a paused job again.
+    pub fn resume(&mut self, id: &str) -> Result<(), FmError> {
+        let job = self.get_mut(id)?;
+        if job.status != "paused" {
+            return Err(FmError::Other(format!("Transfer job is {}, not paused", job.status)));
+        }
+        job.status = "pending".into();
+        Ok(())
+    }
+
+    /// Mark the first pending jobs "running", as many as `max_concurrent`
+    /// allows next to those already running, and return their ids.
+    pub fn start_next(&mut self) -> Vec<String> {
+        let running = self.jobs.iter().filter(|job| job.status == "running").count();
+        let free = self.max_concurrent.saturating_sub(running);
+        self.jobs
+            .iter_mut()
+            .filter(|job| job.status == "pending")
+            .take(free)
+            .map(|job| {
+                job.status = "running".into();
+                job.id.clone()
+            })
+            .collect()
+    }
+
+    pub fn set_progress(&mut self, evt: &ProgressEvent) {
+        if let Ok(job) = self.get_mut(&evt.id) {
+            job.bytes_done = evt.bytes_done;
+            job.bytes_total = evt.bytes_total;
+            job.files_done = evt.files_done;
+            job.files_total = evt.files_total;
+        }
+    }
+
+    /// Record where a running job could resume from, before the run ends.
+    pub fn set_checkpoint(&mut self, id: &str, cp: S3UploadCheckpoint) {
+        if let Ok(job) = self.get_mut(id) {
+            job.checkpoint = Some(cp);
+        }
+    }
+
+    /// Record how a run ended: done, paused at a checkpoint, or failed. A
+    /// failed job keeps its last checkpoint for `retry`.
+    pub fn finish(&mut self, id: &str, outcome: Result<Option<S3UploadCheckpoint>, String>) {
+        let Ok(job) = self.get_mut(id) else {
+            return; // removed while running
+        };
+        match outcome {
+            Ok(None) => {
+                job.status = "done".into();
+                job.checkpoint = None;
+                job.bytes_done = job.bytes_total;
+                job.files_done = job.files_total;
+            }
+            Ok(Some(cp)) => {
+                job.status = "paused".into();
+                job.bytes_done = cp.bytes_done;
+                job.files_done = cp.files_done;
+                job.checkpoint = Some(cp);
+            }
+            Err(e) => {
+                job.status = "failed".into();
+                job.error = Some(e);
+            }
+        }
+    }
+
+    /// Drop the jobs that finished.
+    pub fn clear_done(&mut self) {
+        self.jobs.retain(|job| job.status != "done");
+    }
+}
+
+/// `<data dir>/com.furman.filemanager/transfer-queue.json`
+pub fn queue_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join(".local/share"))
+        .join("com.furman.filemanager/transfer-queue.json")
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, status: &str) -> TransferJob {
+        TransferJob {
+            id: id.into(),
+            source_backend: "local".into(),
+            source_id: String::new(),
+            sources: vec![format!("/tmp/{id}")],
+            dest_backend: "s3".into(),
+            dest_id: "conn".into(),
+            destination: "s3://bucket/dest/".into(),
+            status: status.into(),
+            error: None,
+            bytes_done: 0,
+            bytes_total: 100,
+            files_done: 0,
+            files_total: 1,
+            checkpoint: None,
+            created: 0,
+        }
+    }
+
+    fn queue(jobs: &[(&str, &str)]) -> TransferQueue {
+        TransferQueue {
+            max_concurrent: 2,
+            jobs: jobs.iter().map(|(id, status)| job(id, status)).collect(),
+        }
+    }
+
+    fn ids(q: &TransferQueue) -> Vec<&str> {
+        q.jobs.iter().map(|j| j.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_start_next_respects_order_and_limit() {
+        let mut q = queue(&[("a", "done"), ("b", "pending"), ("c", "running"), ("d", "pending"), ("e", "pending")]);
+        assert_eq!(q.start_next(), vec!["b"]);
+        assert!(q.start_next().is_empty(), "two already running");
+        q.finish("c", Ok(None));
+        assert_eq!(q.start_next(), vec!["d"]);
+        assert_eq!(q.get("e").unwrap().status, "pending");
+    }
+
+    #[test]
+    fn test_move_to() {
+        let mut q = queue(&[("a", "pending"), ("b", "pending"), ("c", "pending")]);
+        q.move_to("c", 0).unwrap();
+        assert_eq!(ids(&q), vec!["c", "a", "b"]);
+        q.move_to("c", 99).unwrap();
+        assert_eq!(ids(&q), vec!["a", "b", "c"]);
+        assert!(q.move_to("x", 0).is_err());
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        let mut q = queue(&[("a", "pending"), ("b", "running"), ("c", "failed"), ("d", "done")]);
+        assert!(!q.pause("a").unwrap());
+        assert_eq!(q.get("a").unwrap().status, "paused");
+        assert!(q.pause("b").unwrap(), "running job is asked to pause");
+        assert_eq!(q.get("b").unwrap().status, "running");
+        assert!(q.pause("d").is_err());
+
+        q.resume("a").unwrap();
+        assert_eq!(q.get("a").unwrap().status, "pending");
+        assert!(q.resume("a").is_err());
+
+        assert!(q.retry("a").is_err());
+        q.retry("c").unwrap();
+        assert_eq!(q.get("c").unwrap().status, "pending");
+    }
+
+    #[test]
+    fn test_finish_keeps_checkpoint_and_error() {
+        let mut q = queue(&[("a", "running"), ("b", "running")]);
+        let cp = S3UploadCheckpoint {
+            files_completed: vec!["/tmp/a/1".into()],
+            current_file_upload_id: Some("upload".into()),
+            current_file_key: Some("dest/a/2".into()),
+            completed_parts: Vec::new(),
+            bytes_done: 40,
+            bytes_total: 100,
+            files_done: 1,
+            files_total: 2,
+        };
+        q.finish("a", Ok(Some(cp)));
+        let a = q.get("a").unwrap();
+        assert_eq!(a.status, "paused");
+        assert_eq!(a.bytes_done, 40);
+        assert_eq!(a.checkpoint.as_ref().unwrap().current_file_upload_id.as_deref(), Some("upload"));
+
+        q.finish("b", Err("S3: boom".into()));
+        assert_eq!(q.get("b").unwrap().status, "failed");
+        assert_eq!(q.get("b").unwrap().error.as_deref(), Some("S3: boom"));
+
+        q.finish("gone", Ok(None));
+        q.clear_done();
+        assert_eq!(ids(&q), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_checkpoint_of_running_job_survives_reload() {
+        let dir = std::env::temp_dir().join(format!("furman-queue-cp-{}", std::process::id()));
+        let path = dir.join("transfer-queue.json");
+        let mut q = queue(&[("a", "running")]);
+        q.set_checkpoint(
+            "a",
+            S3UploadCheckpoint {
+                files_completed: Vec::new(),
+                current_file_upload_id: Some("upload".into()),
+                current_file_key: Some("dest/a".into()),
+                completed_parts: Vec::new(),
+                bytes_done: 0,
+                bytes_total: 100,
+                files_done: 0,
+                files_total: 1,
+            },
+        );
+        q.set_checkpoint("gone", q.get("a").unwrap().checkpoint.clone().unwrap());
+        q.save(&path).unwrap();
+
+        let loaded = TransferQueue::load(&path).unwrap();
+        let a = loaded.get("a").unwrap();
+        assert_eq!(a.status, "paused");
+        let cp = a.checkpoint.as_ref().unwrap();
+        assert_eq!(cp.current_file_key.as_deref(), Some("dest/a"));
+        assert_eq!(cp.current_file_upload_id.as_deref(), Some("upload"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_pauses_interrupted_jobs() {
+        let dir = std::env::temp_dir().join(format!("furman-queue-{}", std::process::id()));
+        let path = dir.join("transfer-queue.json");
+        assert!(TransferQueue::load(&path).unwrap().jobs.is_empty());
+
+        let q = queue(&[("a", "running"), ("b", "pending"), ("c", "failed")]);
+        q.save(&path).unwrap();
+        let loaded = TransferQueue::load(&path).unwrap();
+        let statuses: Vec<&str> = loaded.jobs.iter().map(|j| j.status.as_str()).collect();
+        assert_eq!(statuses, vec!["paused", "paused", "failed"]);
+        assert_eq!(loaded.max_concurrent, 2);
+
+        std::fs::write(&path, b"{").unwrap();
+        assert!(TransferQueue::load(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
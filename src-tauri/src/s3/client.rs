@@ -205,6 +205,9 @@ pub async fn build_s3_client(
     };
 
     let mut s3_config_builder = aws_sdk_s3::config::Builder::from(&final_config);
+    // Every request on this client goes through `retry::send` under the
+    // user's policy; SDK retries underneath would multiply its attempts.
+    s3_config_builder = s3_config_builder.retry_config(aws_sdk_s3::config::retry::RetryConfig::disabled());
     if endpoint.is_some_and(|ep| !ep.is_empty()) {
         s3_config_builder = s3_config_builder.force_path_style(true);
     }
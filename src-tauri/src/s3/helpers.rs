@@ -1,27 +1,15 @@
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_s3::Client as S3Client;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::Semaphore;
 
+use crate::bandwidth::{self, throttle};
 use crate::models::FmError;
 
-// ── Bandwidth throttling ───────────────────────────────────────────────────
-
-/// Global bandwidth limit in bytes per second. 0 = unlimited.
-pub static BANDWIDTH_LIMIT: AtomicU64 = AtomicU64::new(0);
-
-/// Sleep to enforce the global bandwidth limit after transferring `bytes`.
-pub async fn throttle(bytes: u64) {
-    let limit = BANDWIDTH_LIMIT.load(Ordering::Relaxed);
-    if limit == 0 || bytes == 0 {
-        return;
-    }
-    let secs = bytes as f64 / limit as f64;
-    if secs > 0.001 {
-        tokio::time::sleep(Duration::from_secs_f64(secs)).await;
-    }
-}
+use super::pool::is_throttle_error;
+use super::retry;
 
 // ── Error helper ────────────────────────────────────────────────────────────
 
@@ -29,6 +17,25 @@ pub fn s3err(msg: impl Into<String>) -> FmError {
     FmError::S3(msg.into())
 }
 
+/// S3 error for a failed SDK call, keeping the service error code, message
+/// and HTTP status that `SdkError`'s Display leaves out.
+pub fn sdk_err<E: ProvideErrorMetadata>(e: SdkError<E, HttpResponse>) -> FmError {
+    s3err(sdk_message(&e))
+}
+
+fn sdk_message<E: ProvideErrorMetadata>(e: &SdkError<E, HttpResponse>) -> String {
+    let mut msg = match (e.code(), e.message()) {
+        (Some(code), Some(message)) => format!("{}: {}", code, message),
+        (Some(code), None) => code.to_string(),
+        (None, Some(message)) => message.to_string(),
+        (None, None) => e.to_string(),
+    };
+    if let Some(resp) = e.raw_response() {
+        msg.push_str(&format!(" (HTTP {})", resp.status().as_u16()));
+    }
+    msg
+}
+
 // ── Path utilities ──────────────────────────────────────────────────────────
 
 /// Extract the key portion from an s3://bucket/key path.
@@ -46,14 +53,39 @@ pub fn s3_path(bucket: &str, key: &str) -> String {
     format!("s3://{}/{}", bucket, key)
 }
 
+/// The prefix containing `key`, with its trailing `/`: "a/" for both
+/// "a/b.txt" and "a/b/". Empty at the bucket root.
+pub fn parent_prefix(key: &str) -> &str {
+    match key.trim_end_matches('/').rfind('/') {
+        Some(i) => &key[..i + 1],
+        None => "",
+    }
+}
+
+/// Numbered variant of `key` for resolving a name clash: "a/b (2).txt".
+pub fn numbered_key(key: &str, n: u32) -> String {
+    let (dir, name) = key.split_at(key.rfind('/').map(|i| i + 1).unwrap_or(0));
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}{} ({}){}", dir, &name[..dot], n, &name[dot..]),
+        _ => format!("{}{} ({})", dir, name, n),
+    }
+}
+
 // ── Multipart upload constants ──────────────────────────────────────────────
 
 pub const MULTIPART_THRESHOLD: u64 = 8 * 1024 * 1024; // 8 MiB
 pub const PART_SIZE: u64 = 8 * 1024 * 1024; // 8 MiB
 pub const MAX_CONCURRENT_PARTS: usize = 4;
-pub const PART_RETRIES: u32 = 2;
+/// Objects at least this large are downloaded as concurrent ranged GETs.
+pub const PARALLEL_DOWNLOAD_THRESHOLD: u64 = 64 * 1024 * 1024; // 64 MiB
 pub const COPY_MULTIPART_THRESHOLD: u64 = 5 * 1024 * 1024 * 1024; // 5 GiB
 
+/// Part size for a multipart upload of `file_size` bytes: `PART_SIZE`, or
+/// larger when needed to stay within S3's limit of 10,000 parts.
+pub fn part_size_for(file_size: u64) -> u64 {
+    std::cmp::max(PART_SIZE, file_size / 10_000 + 1)
+}
+
 /// Max size for preview download (50 MB).
 pub const PREVIEW_MAX_SIZE: u64 = 50 * 1024 * 1024;
 
@@ -78,7 +110,7 @@ pub async fn list_all_objects(
             req = req.continuation_token(token);
         }
 
-        let resp = req.send().await.map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
 
         for obj in resp.contents() {
             let key = obj.key().unwrap_or_default().to_string();
@@ -100,9 +132,25 @@ pub async fn list_all_objects(
     Ok(results)
 }
 
+/// Unquoted ETag and size of an object, or None if it does not exist.
+pub async fn head_etag(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+) -> Result<Option<(String, u64)>, FmError> {
+    match retry::send(|| client.head_object().bucket(bucket).key(key).send()).await {
+        Ok(head) => Ok(Some((
+            head.e_tag().unwrap_or_default().trim_matches('"').to_string(),
+            head.content_length().unwrap_or(0) as u64,
+        ))),
+        Err(e) if e.as_service_error().is_some_and(|s| s.is_not_found()) => Ok(None),
+        Err(e) => Err(sdk_err(e)),
+    }
+}
+
 // ── Multipart upload helpers ────────────────────────────────────────────────
 
-/// Upload a single part with retries and linear backoff.
+/// Upload a single part, retrying under the S3 retry policy.
 /// Reads `length` bytes from `file_path` at `offset` on each attempt (bounded memory).
 pub async fn upload_part_with_retry(
     client: &S3Client,
@@ -117,7 +165,8 @@ pub async fn upload_part_with_retry(
 ) -> Result<(i32, String, Option<String>), FmError> {
     use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
-    for attempt in 0..=PART_RETRIES {
+    let mut attempt = 1;
+    loop {
         if cancel_flag.load(Ordering::Relaxed) {
             return Err(FmError::Other("cancelled".into()));
         }
@@ -151,22 +200,114 @@ pub async fn upload_part_with_retry(
                 throttle(length).await;
                 return Ok((part_number, etag, crc32c));
             }
-            Err(e) => {
-                if attempt < PART_RETRIES {
-                    let backoff = std::time::Duration::from_millis(500 * (attempt as u64 + 1));
-                    tokio::time::sleep(backoff).await;
-                } else {
-                    return Err(s3err(format!(
-                        "Part {} failed after {} retries: {}",
-                        part_number,
-                        PART_RETRIES + 1,
-                        e,
-                    )));
+            Err(e) => match retry::next_delay(&e, attempt) {
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
                 }
+                None => return Err(part_failed(part_number, attempt, e)),
+            },
+        }
+    }
+}
+
+/// Upload a single part held in memory, retrying like
+/// `upload_part_with_retry`.
+pub async fn upload_part_bytes(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    part_number: i32,
+    data: Vec<u8>,
+) -> Result<(i32, String, Option<String>), FmError> {
+    let mut attempt = 1;
+    loop {
+        let result = client
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .checksum_algorithm(aws_sdk_s3::types::ChecksumAlgorithm::Crc32C)
+            .body(data.clone().into())
+            .send()
+            .await;
+
+        match result {
+            Ok(resp) => {
+                let etag = resp
+                    .e_tag()
+                    .ok_or_else(|| s3err("Missing ETag in upload_part response"))?
+                    .to_string();
+                let crc32c = resp.checksum_crc32_c().map(|s| s.to_string());
+                return Ok((part_number, etag, crc32c));
             }
+            Err(e) => match retry::next_delay(&e, attempt) {
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(part_failed(part_number, attempt, e)),
+            },
         }
     }
-    unreachable!()
+}
+
+fn part_failed<E: ProvideErrorMetadata>(
+    part_number: i32,
+    attempts: u32,
+    e: SdkError<E, HttpResponse>,
+) -> FmError {
+    s3err(format!(
+        "Part {} failed after {} attempts: {}",
+        part_number,
+        attempts,
+        sdk_message(&e),
+    ))
+}
+
+/// Complete a multipart upload from `(part_number, etag, crc32c)` tuples in
+/// any order.
+pub async fn complete_multipart(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    mut completed_parts: Vec<(i32, String, Option<String>)>,
+) -> Result<(), FmError> {
+    completed_parts.sort_by_key(|(num, _, _)| *num);
+
+    let parts: Vec<_> = completed_parts
+        .iter()
+        .map(|(num, etag, crc)| {
+            let mut b = aws_sdk_s3::types::CompletedPart::builder()
+                .part_number(*num)
+                .e_tag(etag);
+            if let Some(c) = crc {
+                b = b.checksum_crc32_c(c);
+            }
+            b.build()
+        })
+        .collect();
+
+    let completed_upload = aws_sdk_s3::types::CompletedMultipartUpload::builder()
+        .set_parts(Some(parts))
+        .build();
+
+    retry::send(|| {
+        client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(completed_upload.clone())
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(e.to_string()))?;
+
+    Ok(())
 }
 
 /// Orchestrate a full multipart upload for a single large file.
@@ -193,8 +334,7 @@ pub async fn upload_file_multipart(
             create_req = create_req.metadata(k, v);
         }
     }
-    let create_resp = create_req
-        .send()
+    let create_resp = retry::send(|| create_req.clone().send())
         .await
         .map_err(|e| s3err(e.to_string()))?;
 
@@ -204,7 +344,7 @@ pub async fn upload_file_multipart(
         .to_string();
 
     // 2. Calculate parts with dynamic part size (handle files > 80 GiB within 10k part limit)
-    let part_size = std::cmp::max(PART_SIZE, file_size / 10_000 + 1);
+    let part_size = part_size_for(file_size);
     let num_parts = ((file_size + part_size - 1) / part_size) as i32;
 
     let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PARTS));
@@ -225,7 +365,8 @@ pub async fn upload_file_multipart(
         let sem = semaphore.clone();
         let bytes_done = bytes_done.clone();
 
-        let handle = tokio::spawn(async move {
+        let (scope, tally) = (bandwidth::current_scope(), retry::current_tally());
+        let handle = tokio::spawn(bandwidth::in_scope(scope, retry::in_tally(tally, async move {
             let _permit = sem
                 .acquire()
                 .await
@@ -248,7 +389,7 @@ pub async fn upload_file_multipart(
             let new_bytes = bytes_done.fetch_add(length, Ordering::Relaxed) + length;
 
             Ok::<((i32, String, Option<String>), u64), FmError>((result, new_bytes))
-        });
+        })));
 
         handles.push(handle);
     }
@@ -278,47 +419,20 @@ pub async fn upload_file_multipart(
 
     // 5. On any failure → abort multipart upload (best-effort) → return error
     if let Some(err) = first_error {
-        let _ = client
-            .abort_multipart_upload()
-            .bucket(bucket)
-            .key(key)
-            .upload_id(&upload_id)
-            .send()
-            .await;
+        let _ = retry::send(|| {
+            client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+        })
+        .await;
         return Err(err);
     }
 
     // 6. Sort parts by number → complete multipart upload
-    completed_parts.sort_by_key(|(num, _, _)| *num);
-
-    let parts: Vec<_> = completed_parts
-        .iter()
-        .map(|(num, etag, crc)| {
-            let mut b = aws_sdk_s3::types::CompletedPart::builder()
-                .part_number(*num)
-                .e_tag(etag);
-            if let Some(c) = crc {
-                b = b.checksum_crc32_c(c);
-            }
-            b.build()
-        })
-        .collect();
-
-    let completed_upload = aws_sdk_s3::types::CompletedMultipartUpload::builder()
-        .set_parts(Some(parts))
-        .build();
-
-    client
-        .complete_multipart_upload()
-        .bucket(bucket)
-        .key(key)
-        .upload_id(&upload_id)
-        .multipart_upload(completed_upload)
-        .send()
-        .await
-        .map_err(|e| s3err(e.to_string()))?;
-
-    Ok(())
+    complete_multipart(client, bucket, key, &upload_id, completed_parts).await
 }
 
 // ── Multipart copy helpers ──────────────────────────────────────────────────
@@ -333,13 +447,15 @@ pub async fn copy_object_multipart(
     object_size: u64,
 ) -> Result<(), FmError> {
     // 1. Create multipart upload on destination
-    let create_resp = dest_client
-        .create_multipart_upload()
-        .bucket(dest_bucket)
-        .key(dest_key)
-        .send()
-        .await
-        .map_err(|e| s3err(e.to_string()))?;
+    let create_resp = retry::send(|| {
+        dest_client
+            .create_multipart_upload()
+            .bucket(dest_bucket)
+            .key(dest_key)
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(e.to_string()))?;
 
     let upload_id = create_resp
         .upload_id()
@@ -359,16 +475,18 @@ pub async fn copy_object_multipart(
         let end = std::cmp::min(offset + part_size, object_size) - 1;
         let part_number = i + 1;
 
-        let result = dest_client
-            .upload_part_copy()
-            .bucket(dest_bucket)
-            .key(dest_key)
-            .upload_id(&upload_id)
-            .part_number(part_number)
-            .copy_source(&copy_source)
-            .copy_source_range(format!("bytes={}-{}", offset, end))
-            .send()
-            .await;
+        let result = retry::send(|| {
+            dest_client
+                .upload_part_copy()
+                .bucket(dest_bucket)
+                .key(dest_key)
+                .upload_id(&upload_id)
+                .part_number(part_number)
+                .copy_source(&copy_source)
+                .copy_source_range(format!("bytes={}-{}", offset, end))
+                .send()
+        })
+        .await;
 
         match result {
             Ok(resp) => {
@@ -381,13 +499,15 @@ pub async fn copy_object_multipart(
             }
             Err(e) => {
                 // Abort on failure (best-effort)
-                let _ = dest_client
-                    .abort_multipart_upload()
-                    .bucket(dest_bucket)
-                    .key(dest_key)
-                    .upload_id(&upload_id)
-                    .send()
-                    .await;
+                let _ = retry::send(|| {
+                    dest_client
+                        .abort_multipart_upload()
+                        .bucket(dest_bucket)
+                        .key(dest_key)
+                        .upload_id(&upload_id)
+                        .send()
+                })
+                .await;
                 return Err(s3err(e.to_string()));
             }
         }
@@ -410,15 +530,17 @@ pub async fn copy_object_multipart(
         .set_parts(Some(parts))
         .build();
 
-    dest_client
-        .complete_multipart_upload()
-        .bucket(dest_bucket)
-        .key(dest_key)
-        .upload_id(&upload_id)
-        .multipart_upload(completed_upload)
-        .send()
-        .await
-        .map_err(|e| s3err(e.to_string()))?;
+    retry::send(|| {
+        dest_client
+            .complete_multipart_upload()
+            .bucket(dest_bucket)
+            .key(dest_key)
+            .upload_id(&upload_id)
+            .multipart_upload(completed_upload.clone())
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(e.to_string()))?;
 
     Ok(())
 }
@@ -437,16 +559,23 @@ pub async fn copy_single_or_multipart(
 ) -> Result<(), FmError> {
     if object_size < COPY_MULTIPART_THRESHOLD {
         let copy_source = format!("{}/{}", src_bucket, src_key);
-        let result = dest_client
-            .copy_object()
-            .bucket(dest_bucket)
-            .key(dest_key)
-            .copy_source(&copy_source)
-            .send()
-            .await;
+        let result = retry::send(|| {
+            dest_client
+                .copy_object()
+                .bucket(dest_bucket)
+                .key(dest_key)
+                .copy_source(&copy_source)
+                .send()
+        })
+        .await;
         match result {
             Ok(_) => return Ok(()),
-            Err(_) => {
+            Err(e) => {
+                let err = sdk_err(e);
+                if is_throttle_error(&err) {
+                    // Let the caller back off rather than doubling the load
+                    return Err(err);
+                }
                 // Server-side copy failed — fall back to download + upload
                 return copy_via_download(
                     src_client, src_bucket, src_key,
@@ -487,32 +616,39 @@ async fn copy_via_download(
 ) -> Result<(), FmError> {
     if object_size < MULTIPART_THRESHOLD {
         // Small file: single GET + PUT
-        let resp = src_client
-            .get_object()
-            .bucket(src_bucket)
-            .key(src_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| {
+            src_client
+                .get_object()
+                .bucket(src_bucket)
+                .key(src_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
         let body = resp.body.collect().await
-            .map_err(|e| s3err(e.to_string()))?;
-        dest_client
-            .put_object()
-            .bucket(dest_bucket)
-            .key(dest_key)
-            .body(body.into_bytes().into())
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+            .map_err(|e| s3err(e.to_string()))?
+            .into_bytes();
+        retry::send(|| {
+            dest_client
+                .put_object()
+                .bucket(dest_bucket)
+                .key(dest_key)
+                .body(body.clone().into())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
     } else {
         // Large file: multipart upload with range-based GETs from source
-        let create_resp = dest_client
-            .create_multipart_upload()
-            .bucket(dest_bucket)
-            .key(dest_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let create_resp = retry::send(|| {
+            dest_client
+                .create_multipart_upload()
+                .bucket(dest_bucket)
+                .key(dest_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let upload_id = create_resp
             .upload_id()
@@ -530,30 +666,35 @@ async fn copy_via_download(
             let chunk_size = end - start + 1;
 
             // Download chunk via range GET
-            let get_resp = src_client
-                .get_object()
-                .bucket(src_bucket)
-                .key(src_key)
-                .range(format!("bytes={}-{}", start, end))
-                .send()
-                .await
-                .map_err(|e| {
+            let get_resp = retry::send(|| {
+                src_client
+                    .get_object()
+                    .bucket(src_bucket)
+                    .key(src_key)
+                    .range(format!("bytes={}-{}", start, end))
+                    .send()
+            })
+            .await
+            .map_err(|e| {
                     s3err(format!("Range GET failed for part {}: {}", part_number, e))
                 })?;
 
             let chunk = get_resp.body.collect().await
-                .map_err(|e| s3err(e.to_string()))?;
+                .map_err(|e| s3err(e.to_string()))?
+                .into_bytes();
 
             // Upload chunk as part
-            let upload_result = dest_client
-                .upload_part()
-                .bucket(dest_bucket)
-                .key(dest_key)
-                .upload_id(&upload_id)
-                .part_number(part_number)
-                .body(chunk.into_bytes().into())
-                .send()
-                .await;
+            let upload_result = retry::send(|| {
+                dest_client
+                    .upload_part()
+                    .bucket(dest_bucket)
+                    .key(dest_key)
+                    .upload_id(&upload_id)
+                    .part_number(part_number)
+                    .body(chunk.clone().into())
+                    .send()
+            })
+            .await;
 
             match upload_result {
                 Ok(resp) => {
@@ -565,13 +706,15 @@ async fn copy_via_download(
                     throttle(chunk_size).await;
                 }
                 Err(e) => {
-                    let _ = dest_client
-                        .abort_multipart_upload()
-                        .bucket(dest_bucket)
-                        .key(dest_key)
-                        .upload_id(&upload_id)
-                        .send()
-                        .await;
+                    let _ = retry::send(|| {
+                        dest_client
+                            .abort_multipart_upload()
+                            .bucket(dest_bucket)
+                            .key(dest_key)
+                            .upload_id(&upload_id)
+                            .send()
+                    })
+                    .await;
                     return Err(s3err(e.to_string()));
                 }
             }
@@ -592,15 +735,17 @@ async fn copy_via_download(
             .set_parts(Some(parts))
             .build();
 
-        dest_client
-            .complete_multipart_upload()
-            .bucket(dest_bucket)
-            .key(dest_key)
-            .upload_id(&upload_id)
-            .multipart_upload(completed_upload)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            dest_client
+                .complete_multipart_upload()
+                .bucket(dest_bucket)
+                .key(dest_key)
+                .upload_id(&upload_id)
+                .multipart_upload(completed_upload.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
     }
 
     Ok(())
@@ -628,3 +773,28 @@ pub fn collect_local_files(
     }
     Ok(())
 }
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parent_prefix() {
+        assert_eq!(parent_prefix("a/b.txt"), "a/");
+        assert_eq!(parent_prefix("a/b/"), "a/");
+        assert_eq!(parent_prefix("a/b/c/d.bin"), "a/b/c/");
+        assert_eq!(parent_prefix("b.txt"), "");
+        assert_eq!(parent_prefix("b/"), "");
+    }
+
+    #[test]
+    fn test_numbered_key() {
+        assert_eq!(numbered_key("a/b.txt", 1), "a/b (1).txt");
+        assert_eq!(numbered_key("a/b.tar.gz", 2), "a/b.tar (2).gz");
+        assert_eq!(numbered_key("README", 3), "README (3)");
+        assert_eq!(numbered_key("dir/.env", 1), "dir/.env (1)");
+        assert_eq!(numbered_key("x.y/file", 1), "x.y/file (1)");
+    }
+}
@@ -1,13 +1,17 @@
 pub mod client;
 pub mod crypto;
 pub mod helpers;
+pub mod partial;
+pub mod pool;
+pub mod retry;
 pub mod service;
 
 pub use client::{build_s3_client, S3Connection, S3State};
 pub use helpers::{
-    collect_local_files, copy_object_multipart, copy_single_or_multipart, list_all_objects,
-    s3_path, s3err, strip_s3_prefix, throttle, upload_file_multipart, upload_part_with_retry,
-    BANDWIDTH_LIMIT, COPY_MULTIPART_THRESHOLD, MAX_CONCURRENT_PARTS, MULTIPART_THRESHOLD,
-    PART_RETRIES, PART_SIZE, PREVIEW_MAX_SIZE,
+    collect_local_files, complete_multipart, copy_object_multipart, copy_single_or_multipart,
+    head_etag, list_all_objects, numbered_key, parent_prefix, part_size_for, s3_path, s3err,
+    sdk_err, strip_s3_prefix, upload_file_multipart, upload_part_bytes, upload_part_with_retry,
+    COPY_MULTIPART_THRESHOLD, MAX_CONCURRENT_PARTS, MULTIPART_THRESHOLD,
+    PARALLEL_DOWNLOAD_THRESHOLD, PART_SIZE, PREVIEW_MAX_SIZE,
 };
 pub use service::S3Service;
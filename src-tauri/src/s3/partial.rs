@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::FmError;
+
+// ── Partial downloads ───────────────────────────────────────────────────────
+
+/// Bytes written between manifest saves while downloading.
+pub const MANIFEST_INTERVAL: u64 = 8 * 1024 * 1024; // 8 MiB
+
+/// Progress of an interrupted download, kept next to its `.part` file as
+/// `<name>.part.json` so a later download of the same object continues it
+/// with ranged GETs instead of starting over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialDownload {
+    pub key: String,
+    pub etag: Option<String>,
+    pub size: u64,
+    /// Byte ranges already written to the `.part` file, `[start, end)`,
+    /// sorted and merged.
+    pub completed: Vec<(u64, u64)>,
+}
+
+impl PartialDownload {
+    pub fn new(key: &str, etag: Option<&str>, size: u64) -> Self {
+        Self {
+            key: key.to_string(),
+            etag: etag.map(str::to_string),
+            size,
+            completed: Vec::new(),
+        }
+    }
+
+    /// Load the manifest at `path`; None if it is missing or unreadable.
+    pub fn load(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    /// Write the manifest atomically (temp file + rename).
+    pub fn save(&self, path: &Path) -> Result<(), FmError> {
+        let data = serde_json::to_vec(self).map_err(|e| FmError::Other(e.to_string()))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Whether this manifest was written for the same version of the object.
+    /// Without an ETag on either side there is no way to tell, so it is not.
+    pub fn matches(&self, key: &str, etag: Option<&str>, size: u64) -> bool {
+        self.key == key && self.size == size && self.etag.is_some() && self.etag.as_deref() == etag
+    }
+
+    /// Record `[start, end)` as written, merging it with adjacent ranges.
+    pub fn add(&mut self, start: u64, end: u64) {
+        if start >= end {
+            return;
+        }
+        self.completed.push((start, end));
+        self.completed.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(self.completed.len());
+        for &(s, e) in &self.completed {
+            match merged.last_mut() {
+                Some(last) if s <= last.1 => last.1 = last.1.max(e),
+                _ => merged.push((s, e)),
+            }
+        }
+        self.completed = merged;
+    }
+
+    /// The ranges still to fetch, in order.
+    pub fn missing(&self) -> Vec<(u64, u64)> {
+        let mut gaps = Vec::new();
+        let mut pos = 0;
+        for &(s, e) in &self.completed {
+            if s > pos {
+                gaps.push((pos, s.min(self.size)));
+            }
+            pos = pos.max(e);
+        }
+        if pos < self.size {
+            gaps.push((pos, self.size));
+        }
+        gaps
+    }
+
+    /// Number of bytes already written.
+    pub fn done(&self) -> u64 {
+        self.completed
+            .iter()
+            .map(|&(s, e)| e.min(self.size).saturating_sub(s))
+            .sum()
+    }
+}
+
+/// `<file>.part`, where a download is written until it is verified.
+pub fn part_path(local: &Path) -> PathBuf {
+    let mut name = local.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    local.with_file_name(name)
+}
+
+/// `<file>.part.json`, the manifest of a `.part` file.
+pub fn manifest_path(local: &Path) -> PathBuf {
+    let mut name = local.file_name().unwrap_or_default().to_os_string();
+    name.push(".part.json");
+    local.with_file_name(name)
+}
+
+/// MD5 (hex) and CRC32C of a file on disk, for verifying a download that
+/// was written in more than one session.
+pub fn file_checksums(path: &Path) -> Result<(String, u32), FmError> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut md5 = md5::Context::new();
+    let mut crc: u32 = 0;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        md5.consume(&buf[..n]);
+        crc = crc32c::crc32c_append(crc, &buf[..n]);
+    }
+    Ok((format!("{:x}", md5.compute()), crc))
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_merges_ranges() {
+        let mut p = PartialDownload::new("k", Some("e"), 100);
+        p.add(20, 30);
+        p.add(0, 10);
+        p.add(10, 20);
+        assert_eq!(p.completed, vec![(0, 30)]);
+        p.add(50, 60);
+        p.add(55, 70);
+        assert_eq!(p.completed, vec![(0, 30), (50, 70)]);
+        p.add(5, 5);
+        assert_eq!(p.completed, vec![(0, 30), (50, 70)]);
+    }
+
+    #[test]
+    fn test_missing_and_done() {
+        let mut p = PartialDownload::new("k", Some("e"), 100);
+        assert_eq!(p.missing(), vec![(0, 100)]);
+        assert_eq!(p.done(), 0);
+        p.add(0, 30);
+        p.add(50, 70);
+        assert_eq!(p.missing(), vec![(30, 50), (70, 100)]);
+        assert_eq!(p.done(), 50);
+        p.add(30, 50);
+        p.add(70, 100);
+        assert!(p.missing().is_empty());
+        assert_eq!(p.done(), 100);
+    }
+
+    #[test]
+    fn test_empty_object_has_nothing_missing() {
+        let p = PartialDownload::new("k", Some("e"), 0);
+        assert!(p.missing().is_empty());
+    }
+
+    #[test]
+    fn test_matches_requires_etag() {
+        let p = PartialDownload::new("a/b", Some("abc"), 10);
+        assert!(p.matches("a/b", Some("abc"), 10));
+        assert!(!p.matches("a/b", Some("abd"), 10));
+        assert!(!p.matches("a/b", Some("abc"), 11));
+        assert!(!p.matches("a/c", Some("abc"), 10));
+        let q = PartialDownload::new("a/b", None, 10);
+        assert!(!q.matches("a/b", None, 10));
+    }
+
+    #[test]
+    fn test_part_paths() {
+        let local = Path::new("/tmp/dl/movie.mkv");
+        assert_eq!(part_path(local), PathBuf::from("/tmp/dl/movie.mkv.part"));
+        assert_eq!(manifest_path(local), PathBuf::from("/tmp/dl/movie.mkv.part.json"));
+    }
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("furman-partial-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = manifest_path(&dir.join("f.bin"));
+        let mut p = PartialDownload::new("f.bin", Some("etag"), 42);
+        p.add(0, 16);
+        p.save(&path).unwrap();
+        assert_eq!(PartialDownload::load(&path), Some(p));
+        std::fs::write(&path, b"not json").unwrap();
+        assert_eq!(PartialDownload::load(&path), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
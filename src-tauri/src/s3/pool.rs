@@ -0,0 +1,224 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use crate::models::FmError;
+
+use super::retry;
+
+// ── Adaptive object concurrency ─────────────────────────────────────────────
+
+/// Upper bound on objects in flight within one transfer.
+pub const MAX_CONCURRENT_OBJECTS: usize = 16;
+/// Objects in flight when a transfer starts.
+const INITIAL_CONCURRENCY: usize = 4;
+/// How often one object is retried after S3 asked us to slow down.
+const SLOWDOWN_RETRIES: u32 = 5;
+
+/// Additive-increase / multiplicative-decrease concurrency limit: grows by
+/// one after a full window of successes, halves when S3 throttles.
+#[derive(Debug, Clone)]
+pub struct Aimd {
+    limit: usize,
+    max: usize,
+    streak: usize,
+}
+
+impl Aimd {
+    pub fn new(initial: usize, max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            limit: initial.clamp(1, max),
+            max,
+            streak: 0,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn success(&mut self) {
+        self.streak += 1;
+        if self.streak >= self.limit && self.limit < self.max {
+            self.limit += 1;
+            self.streak = 0;
+        }
+    }
+
+    pub fn throttled(&mut self) {
+        self.limit = (self.limit / 2).max(1);
+        self.streak = 0;
+    }
+}
+
+/// Whether an S3 error asks the client to reduce its request rate
+/// (`SlowDown`, 503, or the SDK's throttling codes).
+pub fn is_throttle_error(err: &FmError) -> bool {
+    let FmError::S3(msg) = err else {
+        return false;
+    };
+    [
+        "SlowDown",
+        "ServiceUnavailable",
+        "Throttling",
+        "RequestLimitExceeded",
+        "TooManyRequests",
+        "(HTTP 503)",
+    ]
+    .iter()
+    .any(|pattern| msg.contains(pattern))
+}
+
+/// Delay before retry `attempt` (1-based) of a throttled object.
+fn slowdown_backoff(attempt: u32) -> Duration {
+    Duration::from_millis(500 << attempt.saturating_sub(1).min(4))
+}
+
+/// Run `work` on every item with an adaptive number in flight, at most
+/// `max`. The limit halves whenever a request was retried for throttling
+/// (see `retry`), and an item that still fails with a throttling error is
+/// retried after a backoff. `done` gets each result as it completes.
+///
+/// No new items start once `stopped` returns true or an item has failed;
+/// the ones already running are awaited. Returns the first error.
+pub async fn run_adaptive<T, O, Fut>(
+    items: Vec<T>,
+    max: usize,
+    stopped: impl Fn() -> bool,
+    work: impl Fn(T) -> Fut,
+    mut done: impl FnMut(O),
+) -> Result<(), FmError>
+where
+    T: Clone,
+    Fut: Future<Output = Result<O, FmError>>,
+{
+    let mut limit = Aimd::new(INITIAL_CONCURRENCY, max);
+    let mut queue: VecDeque<(T, u32)> = items.into_iter().map(|item| (item, 0)).collect();
+    let mut running = FuturesUnordered::new();
+    let mut first_error: Option<FmError> = None;
+    let mut throttled = retry::throttled();
+
+    loop {
+        while first_error.is_none() && running.len() < limit.limit() && !stopped() {
+            let Some((item, attempt)) = queue.pop_front() else {
+                break;
+            };
+            let fut = work(item.clone());
+            running.push(async move {
+                if attempt > 0 {
+                    tokio::time::sleep(slowdown_backoff(attempt)).await;
+                }
+                (item, attempt, fut.await)
+            });
+        }
+
+        let Some((item, attempt, result)) = running.next().await else {
+            break;
+        };
+        let slowed_down = retry::throttled() > throttled;
+        throttled = retry::throttled();
+        match result {
+            Ok(out) => {
+                if slowed_down {
+                    limit.throttled();
+                } else {
+                    limit.success();
+                }
+                done(out);
+            }
+            Err(e) if is_throttle_error(&e) && attempt < SLOWDOWN_RETRIES => {
+                limit.throttled();
+                queue.push_front((item, attempt + 1));
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Bytes transferred across objects that run concurrently. Each object
+/// reports its own running total, so one retried from the start is not
+/// counted twice.
+#[derive(Default)]
+pub struct ByteTally {
+    inner: Mutex<(u64, HashMap<String, u64>)>,
+}
+
+impl ByteTally {
+    /// Record that `object` has `bytes` done; returns the overall total.
+    pub fn set(&self, object: &str, bytes: u64) -> u64 {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (total, per_object) = &mut *inner;
+        let previous = per_object.insert(object.to_string(), bytes).unwrap_or(0);
+        *total = *total - previous + bytes;
+        *total
+    }
+
+    pub fn total(&self) -> u64 {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).0
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aimd_grows_after_a_window_of_successes() {
+        let mut a = Aimd::new(2, 4);
+        a.success();
+        assert_eq!(a.limit(), 2);
+        a.success();
+        assert_eq!(a.limit(), 3);
+        for _ in 0..3 {
+            a.success();
+        }
+        assert_eq!(a.limit(), 4);
+        for _ in 0..10 {
+            a.success();
+        }
+        assert_eq!(a.limit(), 4, "never above max");
+    }
+
+    #[test]
+    fn test_aimd_halves_on_throttle() {
+        let mut a = Aimd::new(8, 16);
+        a.throttled();
+        assert_eq!(a.limit(), 4);
+        a.throttled();
+        a.throttled();
+        a.throttled();
+        assert_eq!(a.limit(), 1, "never below one");
+    }
+
+    #[test]
+    fn test_is_throttle_error() {
+        assert!(is_throttle_error(&FmError::S3("SlowDown: Please reduce your request rate. (HTTP 503)".into())));
+        assert!(is_throttle_error(&FmError::S3("service error (HTTP 503)".into())));
+        assert!(!is_throttle_error(&FmError::S3("NoSuchKey: The specified key does not exist. (HTTP 404)".into())));
+        assert!(!is_throttle_error(&FmError::Other("SlowDown".into())));
+    }
+
+    #[test]
+    fn test_byte_tally_replaces_retried_object() {
+        let tally = ByteTally::default();
+        assert_eq!(tally.set("a", 10), 10);
+        assert_eq!(tally.set("b", 5), 15);
+        assert_eq!(tally.set("a", 20), 25);
+        // "b" restarted from zero
+        assert_eq!(tally.set("b", 0), 20);
+        assert_eq!(tally.total(), 20);
+    }
+}
@@ -0,0 +1,257 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use rand::Rng;
+
+use crate::models::RetryPolicy;
+
+// ── Policy ──────────────────────────────────────────────────────────────────
+
+static POLICY: Mutex<Option<RetryPolicy>> = Mutex::new(None);
+
+pub fn policy() -> RetryPolicy {
+    POLICY.lock().unwrap_or_else(|e| e.into_inner()).unwrap_or_default()
+}
+
+/// Replace the policy; requests already waiting keep their delay.
+pub fn set_policy(policy: RetryPolicy) {
+    *POLICY.lock().unwrap_or_else(|e| e.into_inner()) = Some(policy);
+}
+
+// ── Classification ──────────────────────────────────────────────────────────
+
+/// Why a request failed, for the ones worth another attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// S3 asked us to slow down (SlowDown, 429, 503).
+    Throttling,
+    /// Any other 5xx.
+    Server,
+    /// The request or the connection timed out.
+    Timeout,
+    /// The connection was reset or dropped before a full response arrived.
+    Connection,
+}
+
+const THROTTLE_CODES: &[&str] = &[
+    "SlowDown",
+    "ServiceUnavailable",
+    "Throttling",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+    "TooManyRequests",
+    "TooManyRequestsException",
+];
+
+/// Class of an S3 error response from its error code and HTTP status, or
+/// None if retrying cannot help (404, 403, bad request, …).
+pub fn classify_response(code: Option<&str>, status: Option<u16>) -> Option<ErrorClass> {
+    if code.is_some_and(|c| THROTTLE_CODES.contains(&c)) || matches!(status, Some(429 | 503)) {
+        return Some(ErrorClass::Throttling);
+    }
+    // S3 answers a request whose body stalled with 400 RequestTimeout.
+    if matches!(code, Some("RequestTimeout" | "RequestTimeoutException")) {
+        return Some(ErrorClass::Timeout);
+    }
+    if code == Some("InternalError") || status.is_some_and(|s| (500..600).contains(&s)) {
+        return Some(ErrorClass::Server);
+    }
+    None
+}
+
+/// Class of a failed SDK call, or None if it should not be retried.
+pub fn classify<E: ProvideErrorMetadata>(err: &SdkError<E, HttpResponse>) -> Option<ErrorClass> {
+    match err {
+        SdkError::TimeoutError(_) => Some(ErrorClass::Timeout),
+        SdkError::DispatchFailure(failure) if failure.is_timeout() => Some(ErrorClass::Timeout),
+        SdkError::DispatchFailure(failure) if failure.is_user() => None,
+        SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => Some(ErrorClass::Connection),
+        SdkError::ServiceError(_) => classify_response(
+            err.code(),
+            err.raw_response().map(|resp| resp.status().as_u16()),
+        ),
+        _ => None,
+    }
+}
+
+/// Delay before retry `attempt` (1-based) of an error of `class`: the
+/// class's base delay doubled per attempt, capped, then jittered into its
+/// upper half with `jitter` in [0, 1).
+pub fn backoff(policy: &RetryPolicy, class: ErrorClass, attempt: u32, jitter: f64) -> Duration {
+    let base = match class {
+        ErrorClass::Throttling => policy.throttle_delay_ms,
+        _ => policy.base_delay_ms,
+    };
+    let ceiling = base
+        .saturating_mul(1u64 << attempt.saturating_sub(1).min(20))
+        .min(policy.max_delay_ms);
+    let half = ceiling / 2;
+    Duration::from_millis(half + ((ceiling - half) as f64 * jitter.clamp(0.0, 1.0)) as u64)
+}
+
+// ── Retry counts ────────────────────────────────────────────────────────────
+
+/// Retries made within one operation, reported in its progress events.
+#[derive(Debug, Default)]
+pub struct RetryTally {
+    retries: AtomicU32,
+    throttled: AtomicU32,
+}
+
+tokio::task_local! {
+    static TALLY: Arc<RetryTally>;
+}
+
+/// Run `fut` as one operation with its own retry counts, unless the task
+/// already counts them.
+pub async fn counted<F: Future>(fut: F) -> F::Output {
+    if TALLY.try_with(|_| ()).is_ok() {
+        fut.await
+    } else {
+        TALLY.scope(Arc::default(), fut).await
+    }
+}
+
+/// The counts of the running task, to carry into tasks it spawns.
+pub fn current_tally() -> Option<Arc<RetryTally>> {
+    TALLY.try_with(Arc::clone).ok()
+}
+
+/// Run `fut` counting into `tally`, e.g. inside a spawned task.
+pub async fn in_tally<F: Future>(tally: Option<Arc<RetryTally>>, fut: F) -> F::Output {
+    match tally {
+        Some(tally) => TALLY.scope(tally, fut).await,
+        None => fut.await,
+    }
+}
+
+/// Requests retried so far in the current operation.
+pub fn retries() -> u32 {
+    TALLY.try_with(|t| t.retries.load(Ordering::Relaxed)).unwrap_or(0)
+}
+
+/// Retries in the current operation that were caused by throttling.
+pub fn throttled() -> u32 {
+    TALLY.try_with(|t| t.throttled.load(Ordering::Relaxed)).unwrap_or(0)
+}
+
+// ── Retrying ────────────────────────────────────────────────────────────────
+
+/// How long to wait before retrying a call that failed with `err` on
+/// attempt `attempt` (1-based), or None to give up. Counts the retry.
+pub fn next_delay<E: ProvideErrorMetadata>(
+    err: &SdkError<E, HttpResponse>,
+    attempt: u32,
+) -> Option<Duration> {
+    let class = classify(err)?;
+    let policy = policy();
+    if attempt >= policy.max_attempts {
+        return None;
+    }
+    let delay = backoff(&policy, class, attempt, rand::thread_rng().gen());
+    let _ = TALLY.try_with(|t| {
+        t.retries.fetch_add(1, Ordering::Relaxed);
+        if class == ErrorClass::Throttling {
+            t.throttled.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    log::debug!(
+        "S3 request failed ({:?}: {}), retry {} in {:?}",
+        class,
+        err.code().unwrap_or("no code"),
+        attempt,
+        delay,
+    );
+    Some(delay)
+}
+
+/// Send the request built by `request`, building and sending it again
+/// after errors worth retrying under the current policy.
+pub async fn send<T, E, F, Fut>(mut request: F) -> Result<T, SdkError<E, HttpResponse>>
+where
+    E: ProvideErrorMetadata,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+{
+    let mut attempt = 1;
+    loop {
+        match request().await {
+            Ok(out) => return Ok(out),
+            Err(e) => match next_delay(&e, attempt) {
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(e),
+            },
+        }
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_response() {
+        use ErrorClass::*;
+        assert_eq!(classify_response(Some("SlowDown"), Some(503)), Some(Throttling));
+        assert_eq!(classify_response(None, Some(429)), Some(Throttling));
+        assert_eq!(classify_response(Some("InternalError"), Some(500)), Some(Server));
+        assert_eq!(classify_response(None, Some(502)), Some(Server));
+        assert_eq!(classify_response(Some("RequestTimeout"), Some(400)), Some(Timeout));
+        assert_eq!(classify_response(Some("NoSuchKey"), Some(404)), None);
+        assert_eq!(classify_response(Some("AccessDenied"), Some(403)), None);
+        assert_eq!(classify_response(None, None), None);
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy::default();
+        let server = |attempt| backoff(&policy, ErrorClass::Server, attempt, 1.0);
+        assert_eq!(server(1), Duration::from_millis(200));
+        assert_eq!(server(2), Duration::from_millis(400));
+        assert_eq!(server(3), Duration::from_millis(800));
+        assert_eq!(server(30), Duration::from_millis(20_000), "capped");
+        assert_eq!(
+            backoff(&policy, ErrorClass::Throttling, 1, 1.0),
+            Duration::from_millis(1000),
+            "throttling starts higher"
+        );
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_in_upper_half() {
+        let policy = RetryPolicy::default();
+        assert_eq!(backoff(&policy, ErrorClass::Timeout, 3, 0.0), Duration::from_millis(400));
+        assert_eq!(backoff(&policy, ErrorClass::Timeout, 3, 0.5), Duration::from_millis(600));
+        for _ in 0..100 {
+            let d = backoff(&policy, ErrorClass::Connection, 2, rand::thread_rng().gen());
+            assert!(d >= Duration::from_millis(200) && d <= Duration::from_millis(400));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tally_counts_per_operation() {
+        assert_eq!(retries(), 0, "outside an operation");
+        counted(async {
+            TALLY.with(|t| t.retries.fetch_add(2, Ordering::Relaxed));
+            let tally = current_tally();
+            tokio::spawn(in_tally(tally, async {
+                TALLY.with(|t| t.throttled.fetch_add(1, Ordering::Relaxed));
+            }))
+            .await
+            .unwrap();
+            // A nested operation keeps counting into the outer one.
+            counted(async { assert_eq!(retries(), 2) }).await;
+            assert_eq!(throttled(), 1);
+        })
+        .await;
+    }
+}
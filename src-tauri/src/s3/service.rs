@@ -1,11 +1,14 @@
 use aws_credential_types::provider::ProvideCredentials;
 use aws_config::BehaviorVersion;
 use aws_sdk_s3::Client as S3Client;
-use std::collections::HashMap;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::{HashMap, HashSet};
+use std::io::SeekFrom;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Semaphore;
 
 use crate::models::{
     DirListing, FileEntry, FmError, ProgressEvent, S3AccessPoint, S3AccessPointDetail,
@@ -18,8 +21,13 @@ use crate::models::{
     S3ReplicationDestination, S3ReplicationRule, S3Tag, SearchDone, SearchEvent, SearchResult,
     TransferCheckpoint,
 };
+use crate::storage::ChunkStream;
 
 use super::helpers::*;
+use crate::bandwidth::throttle;
+use super::partial::{self, PartialDownload};
+use super::pool::{self, ByteTally, MAX_CONCURRENT_OBJECTS};
+use super::retry;
 
 use super::crypto::EncryptionConfig;
 
@@ -48,11 +56,13 @@ pub async fn check_credentials() -> Result<bool, FmError> {
 
 /// List all accessible buckets using a client.
 pub async fn list_buckets(client: &S3Client) -> Result<Vec<S3Bucket>, FmError> {
-    let resp = client
-        .list_buckets()
-        .send()
-        .await
-        .map_err(|e| s3err(format!("Could not list buckets: {}", e)))?;
+    let resp = retry::send(|| {
+        client
+            .list_buckets()
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(format!("Could not list buckets: {}", e)))?;
 
     let buckets = resp
         .buckets()
@@ -82,7 +92,7 @@ pub async fn create_bucket(client: &S3Client, name: &str, region: &str) -> Resul
         req = req.create_bucket_configuration(constraint);
     }
 
-    req.send()
+    retry::send(|| req.clone().send())
         .await
         .map_err(|e| s3err(format!("Could not create bucket '{}': {}", name, e)))?;
 
@@ -91,12 +101,14 @@ pub async fn create_bucket(client: &S3Client, name: &str, region: &str) -> Resul
 
 /// Delete an S3 bucket (must be empty).
 pub async fn delete_bucket(client: &S3Client, name: &str) -> Result<(), FmError> {
-    client
-        .delete_bucket()
-        .bucket(name)
-        .send()
-        .await
-        .map_err(|e| s3err(format!("Could not delete bucket '{}': {}", name, e)))?;
+    retry::send(|| {
+        client
+            .delete_bucket()
+            .bucket(name)
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(format!("Could not delete bucket '{}': {}", name, e)))?;
 
     Ok(())
 }
@@ -162,7 +174,7 @@ impl S3Service {
                 req = req.continuation_token(token);
             }
 
-            let resp = req.send().await.map_err(|e| s3err(e.to_string()))?;
+            let resp = retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
 
             // Common prefixes → directories
             for cp in resp.common_prefixes() {
@@ -258,7 +270,8 @@ impl S3Service {
 
     // ── Data Transfer ───────────────────────────────────────────────────
 
-    /// Download S3 objects to a local destination directory.
+    /// Download S3 objects to a local destination directory, several objects
+    /// at a time.
     /// Returns None on success, Some(checkpoint) on pause.
     pub async fn download(
         &self,
@@ -282,40 +295,23 @@ impl S3Service {
                     resolved.push((k, size));
                 }
             } else {
-                let head = self
-                    .client
-                    .head_object()
-                    .bucket(&self.bucket)
-                    .key(&key)
-                    .send()
-                    .await
-                    .map_err(|e| s3err(e.to_string()))?;
+                let head = retry::send(|| {
+                    self.client
+                        .head_object()
+                        .bucket(&self.bucket)
+                        .key(&key)
+                        .send()
+                })
+                .await
+                .map_err(|e| s3err(e.to_string()))?;
                 let size = head.content_length().unwrap_or(0) as u64;
                 resolved.push((key, size));
             }
         }
 
-        let files_total = resolved.len() as u32;
-        let bytes_total: u64 = resolved.iter().map(|(_, s)| *s).sum();
-        let mut bytes_done: u64 = 0;
-        let mut files_done: u32 = 0;
-        let mut completed_files: Vec<String> = Vec::new();
-
-        for (key, _size) in &resolved {
-            if cancel.load(Ordering::Relaxed) {
-                return Err(FmError::Other("Operation cancelled".into()));
-            }
-            if pause.load(Ordering::Relaxed) {
-                return Ok(Some(TransferCheckpoint {
-                    files_completed: completed_files,
-                    bytes_done,
-                    bytes_total,
-                    files_done,
-                    files_total,
-                }));
-            }
-
-            let filename = key.rsplit('/').next().unwrap_or(key);
+        // (key, size, local path)
+        let mut items: Vec<(String, u64, PathBuf)> = Vec::with_capacity(resolved.len());
+        for (key, size) in resolved {
             let base_prefix = if keys.len() == 1 && keys[0].ends_with('/') {
                 strip_s3_prefix(&keys[0], &self.bucket)
             } else {
@@ -324,140 +320,427 @@ impl S3Service {
                     None => String::new(),
                 }
             };
-            let relative = key.strip_prefix(&base_prefix).unwrap_or(key);
+            let relative = key.strip_prefix(&base_prefix).unwrap_or(&key);
             let local_path = dest.join(relative);
-
             if let Some(parent) = local_path.parent() {
                 std::fs::create_dir_all(parent)?;
             }
+            items.push((key, size, local_path));
+        }
 
-            let resp = self
-                .client
-                .get_object()
-                .bucket(&self.bucket)
-                .key(key)
-                .checksum_mode(aws_sdk_s3::types::ChecksumMode::Enabled)
-                .send()
-                .await
-                .map_err(|e| s3err(e.to_string()))?;
+        let files_total = items.len() as u32;
+        let bytes_total: u64 = items.iter().map(|(_, s, _)| *s).sum();
+        let tally = ByteTally::default();
+        let files_done = AtomicU32::new(0);
+        let mut completed_files: Vec<String> = Vec::new();
 
-            let etag = resp.e_tag().map(|s| s.trim_matches('"').to_string());
-            let expected_crc32c = resp.checksum_crc32_c().map(|s| s.to_string());
-            let obj_metadata: HashMap<String, String> = resp.metadata().cloned().unwrap_or_default();
-            let expected_size = *_size;
-            let mut body = resp.body;
-            let mut file = tokio::fs::File::create(&local_path).await.map_err(FmError::Io)?;
-            let mut hasher = md5::Context::new();
-            let mut crc_state: u32 = 0;
-            let mut file_bytes: u64 = 0;
-            let bytes_done_base = bytes_done;
-
-            loop {
-                match body.try_next().await {
-                    Ok(Some(chunk)) => {
-                        hasher.consume(&chunk);
-                        crc_state = crc32c::crc32c_append(crc_state, &chunk);
-                        file.write_all(&chunk).await.map_err(FmError::Io)?;
-                        file_bytes += chunk.len() as u64;
-                        bytes_done = bytes_done_base + file_bytes;
-                        throttle(chunk.len() as u64).await;
+        let result = pool::run_adaptive(
+            items,
+            MAX_CONCURRENT_OBJECTS,
+            || cancel.load(Ordering::Relaxed) || pause.load(Ordering::Relaxed),
+            |(key, size, local_path)| {
+                let tally = &tally;
+                let files_done = &files_done;
+                async move {
+                    let filename = key.rsplit('/').next().unwrap_or(&key).to_string();
+                    let on_bytes = |n: u64| {
                         on_progress(ProgressEvent {
                             id: op_id.to_string(),
-                            bytes_done,
+                            bytes_done: tally.set(&key, n),
                             bytes_total,
-                            current_file: filename.to_string(),
-                            files_done,
+                            current_file: filename.clone(),
+                            files_done: files_done.load(Ordering::Relaxed),
                             files_total,
+                            retries: retry::retries(),
                         });
-                    }
-                    Ok(None) => break,
+                    };
+                    let complete = self
+                        .download_one(&key, size, &local_path, password, cancel, pause, &on_bytes)
+                        .await?;
+                    Ok((key, filename, complete))
+                }
+            },
+            |(key, filename, complete)| {
+                if !complete {
+                    return;
+                }
+                let done = files_done.fetch_add(1, Ordering::Relaxed) + 1;
+                completed_files.push(key);
+                on_progress(ProgressEvent {
+                    id: op_id.to_string(),
+                    bytes_done: tally.total(),
+                    bytes_total,
+                    current_file: filename,
+                    files_done: done,
+                    files_total,
+                    retries: retry::retries(),
+                });
+            },
+        )
+        .await;
+
+        result?;
+        if cancel.load(Ordering::Relaxed) {
+            return Err(FmError::Other("Operation cancelled".into()));
+        }
+        if completed_files.len() < files_total as usize {
+            return Ok(Some(TransferCheckpoint {
+                files_done: completed_files.len() as u32,
+                files_completed: completed_files,
+                bytes_done: tally.total(),
+                bytes_total,
+                files_total,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Download one object to `local_path` via its `.part` file, continuing
+    /// an earlier partial download of the same version. `on_bytes` gets the
+    /// bytes of this object on disk so far. Returns Ok(false) when paused;
+    /// the `.part` file is kept for the next attempt.
+    async fn download_one(
+        &self,
+        key: &str,
+        size: u64,
+        local_path: &std::path::Path,
+        password: Option<&str>,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_bytes: &(dyn Fn(u64) + Send + Sync),
+    ) -> Result<bool, FmError> {
+        let head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .checksum_mode(aws_sdk_s3::types::ChecksumMode::Enabled)
+                .send()
+        })
+        .await
+        .map_err(sdk_err)?;
+
+        let etag = head.e_tag().map(|s| s.trim_matches('"').to_string());
+        let expected_crc32c = head.checksum_crc32_c().map(|s| s.to_string());
+        let obj_metadata: HashMap<String, String> = head.metadata().cloned().unwrap_or_default();
+        let expected_size = size;
+        // Only a single-part CRC32C, or failing that a single-part ETag (the
+        // MD5), says anything about the content; composite ones are checked
+        // by size, so the data is only hashed when there is something to
+        // compare with.
+        let expected_crc = expected_crc32c.as_deref().filter(|c| !c.contains('-'));
+        let expected_md5 = etag
+            .as_deref()
+            .filter(|e| expected_crc32c.is_none() && !e.contains('-'));
+        let verifiable = expected_crc.is_some() || expected_md5.is_some();
+
+        if password.is_none() && super::crypto::EncryptionParams::is_encrypted(&obj_metadata) {
+            return Err(s3err(format!(
+                "File '{}' is encrypted — password required for download",
+                key
+            )));
+        }
+
+        // Continue an interrupted download of the same object version,
+        // otherwise start a fresh .part file.
+        let part = partial::part_path(local_path);
+        let manifest = partial::manifest_path(local_path);
+        let mut progress = match PartialDownload::load(&manifest) {
+            Some(p) if p.matches(key, etag.as_deref(), expected_size) && part.exists() => p,
+            _ => PartialDownload::new(key, etag.as_deref(), expected_size),
+        };
+        let resumed = !progress.completed.is_empty();
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(!resumed)
+            .open(&part)
+            .await
+            .map_err(FmError::Io)?;
+        let discard = || {
+            let _ = std::fs::remove_file(&part);
+            let _ = std::fs::remove_file(&manifest);
+        };
+
+        // Large objects are fetched as concurrent ranged GETs into a
+        // preallocated file. A single pass from the start is checksummed
+        // while it is written; anything else is read back once complete.
+        let parallel = expected_size >= PARALLEL_DOWNLOAD_THRESHOLD;
+        let mut hasher =
+            (verifiable && !resumed && !parallel).then(|| (md5::Context::new(), 0u32));
+        let mut file_bytes: u64 = progress.done();
+        let mut unsaved: u64 = 0;
+        on_bytes(file_bytes);
+        let mut interrupted = false;
+        let mut failure: Option<FmError> = None;
+
+        if parallel {
+            file.set_len(expected_size).await.map_err(FmError::Io)?;
+            let fetched = AtomicU64::new(file_bytes);
+            let report = |n: u64| on_bytes(fetched.fetch_add(n, Ordering::Relaxed) + n);
+            match self
+                .download_parts(key, etag.as_deref(), &part, &file, &manifest, &mut progress, cancel, pause, &report)
+                .await
+            {
+                Ok(complete) => interrupted = !complete,
+                Err(e) => failure = Some(e),
+            }
+        } else {
+            'ranges: for (start, end) in progress.missing() {
+                let mut req = self
+                    .client
+                    .get_object()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .range(format!("bytes={}-{}", start, end - 1));
+                if let Some(ref e) = etag {
+                    req = req.if_match(format!("\"{}\"", e));
+                }
+                let mut body = match retry::send(|| req.clone().send()).await {
+                    Ok(resp) => resp.body,
                     Err(e) => {
-                        drop(file);
-                        let _ = tokio::fs::remove_file(&local_path).await;
-                        return Err(s3err(e.to_string()));
+                        failure = Some(sdk_err(e));
+                        break;
+                    }
+                };
+                file.seek(SeekFrom::Start(start)).await.map_err(FmError::Io)?;
+                let mut pos = start;
+
+                loop {
+                    if cancel.load(Ordering::Relaxed) || pause.load(Ordering::Relaxed) {
+                        interrupted = true;
+                        break 'ranges;
+                    }
+                    match body.try_next().await {
+                        Ok(Some(chunk)) => {
+                            if let Some((md5, crc)) = hasher.as_mut() {
+                                md5.consume(&chunk);
+                                *crc = crc32c::crc32c_append(*crc, &chunk);
+                            }
+                            file.write_all(&chunk).await.map_err(FmError::Io)?;
+                            let n = chunk.len() as u64;
+                            progress.add(pos, pos + n);
+                            pos += n;
+                            file_bytes += n;
+                            unsaved += n;
+                            if unsaved >= partial::MANIFEST_INTERVAL {
+                                file.sync_data().await.map_err(FmError::Io)?;
+                                progress.save(&manifest)?;
+                                unsaved = 0;
+                            }
+                            throttle(n).await;
+                            on_bytes(file_bytes);
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            failure = Some(s3err(e.to_string()));
+                            break 'ranges;
+                        }
                     }
                 }
             }
-            file.flush().await.map_err(FmError::Io)?;
+        }
+        file.flush().await.map_err(FmError::Io)?;
+
+        if interrupted && cancel.load(Ordering::Relaxed) {
+            drop(file);
+            discard();
+            return Err(FmError::Other("Operation cancelled".into()));
+        }
+        if interrupted || failure.is_some() {
+            // Keep the .part file and record what it holds for the next attempt.
+            file.sync_data().await.map_err(FmError::Io)?;
             drop(file);
+            progress.save(&manifest)?;
+            if let Some(e) = failure {
+                return Err(e);
+            }
+            return Ok(false);
+        }
+        drop(file);
 
-            // Verify integrity: prefer CRC32C, fall back to MD5/ETag, then size
-            if let Some(ref expected) = expected_crc32c {
-                if !expected.contains('-') {
-                    // Single-part CRC32C: compare base64-encoded value
-                    use base64::Engine;
-                    let computed = base64::engine::general_purpose::STANDARD
-                        .encode(crc_state.to_be_bytes());
-                    if computed != *expected {
-                        let _ = tokio::fs::remove_file(&local_path).await;
-                        return Err(s3err(format!(
-                            "CRC32C mismatch for '{}': expected {} got {}",
-                            key, expected, computed
-                        )));
-                    }
-                } else {
-                    // Composite multipart CRC32C (has -N suffix): size check only
-                    if expected_size > 0 && file_bytes != expected_size {
-                        let _ = tokio::fs::remove_file(&local_path).await;
-                        return Err(s3err(format!(
-                            "Size mismatch for '{}': expected {} got {}",
-                            key, expected_size, file_bytes
-                        )));
-                    }
+        let sums = match hasher {
+            Some((md5, crc)) => Some((format!("{:x}", md5.compute()), crc)),
+            None if verifiable => Some(partial::file_checksums(&part)?),
+            None => None,
+        };
+        let written = std::fs::metadata(&part)?.len();
+
+        match (expected_crc, expected_md5, sums) {
+            (Some(expected), _, Some((_, crc_state))) => {
+                use base64::Engine;
+                let computed = base64::engine::general_purpose::STANDARD
+                    .encode(crc_state.to_be_bytes());
+                if computed != expected {
+                    discard();
+                    return Err(s3err(format!(
+                        "CRC32C mismatch for '{}': expected {} got {}",
+                        key, expected, computed
+                    )));
                 }
-            } else if let Some(ref etag_val) = etag {
-                if !etag_val.contains('-') {
-                    // No CRC32C available: fall back to MD5/ETag
-                    let computed = format!("{:x}", hasher.compute());
-                    if computed != *etag_val {
-                        let _ = tokio::fs::remove_file(&local_path).await;
-                        return Err(s3err(format!(
-                            "Checksum mismatch for '{}': expected {} got {}",
-                            key, etag_val, computed
-                        )));
-                    }
-                } else {
-                    // Multipart without CRC32C: size check
-                    if expected_size > 0 && file_bytes != expected_size {
-                        let _ = tokio::fs::remove_file(&local_path).await;
-                        return Err(s3err(format!(
-                            "Size mismatch for '{}': expected {} got {}",
-                            key, expected_size, file_bytes
-                        )));
-                    }
+            }
+            (None, Some(expected), Some((md5_hex, _))) => {
+                if md5_hex != expected {
+                    discard();
+                    return Err(s3err(format!(
+                        "Checksum mismatch for '{}': expected {} got {}",
+                        key, expected, md5_hex
+                    )));
                 }
             }
-
-            // Decrypt if encrypted and password provided
-            if let Some(pw) = password {
-                if let Some(enc_params) = super::crypto::EncryptionParams::from_metadata(&obj_metadata) {
-                    super::crypto::decrypt_file(&local_path, pw, &enc_params)?;
+            _ => {
+                if expected_size > 0 && written != expected_size {
+                    discard();
+                    return Err(s3err(format!(
+                        "Size mismatch for '{}': expected {} got {}",
+                        key, expected_size, written
+                    )));
                 }
-            } else if super::crypto::EncryptionParams::is_encrypted(&obj_metadata) {
-                let _ = tokio::fs::remove_file(&local_path).await;
-                return Err(s3err(format!(
-                    "File '{}' is encrypted — password required for download",
-                    key
-                )));
             }
+        }
 
-            files_done += 1;
-            completed_files.push(key.clone());
+        tokio::fs::rename(&part, local_path).await.map_err(FmError::Io)?;
+        let _ = std::fs::remove_file(&manifest);
 
-            on_progress(ProgressEvent {
-                id: op_id.to_string(),
-                bytes_done,
-                bytes_total,
-                current_file: filename.to_string(),
-                files_done,
-                files_total,
-            });
+        // Decrypt if encrypted and password provided
+        if let Some(pw) = password {
+            if let Some(enc_params) = super::crypto::EncryptionParams::from_metadata(&obj_metadata) {
+                super::crypto::decrypt_file(local_path, pw, &enc_params)?;
+            }
         }
 
-        Ok(None)
+        Ok(true)
+    }
+
+    /// Fetch the missing ranges of `progress` into the preallocated `.part`
+    /// file, `MAX_CONCURRENT_PARTS` ranged GETs at a time. Finished ranges are
+    /// recorded in `progress` and saved to `manifest` as they come in.
+    /// Returns Ok(false) when cancel or pause stopped it early.
+    async fn download_parts(
+        &self,
+        key: &str,
+        etag: Option<&str>,
+        part: &std::path::Path,
+        file: &tokio::fs::File,
+        manifest: &std::path::Path,
+        progress: &mut PartialDownload,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_bytes: &(dyn Fn(u64) + Send + Sync),
+    ) -> Result<bool, FmError> {
+        let piece = part_size_for(progress.size);
+        let pieces: Vec<(u64, u64)> = progress
+            .missing()
+            .into_iter()
+            .flat_map(|(start, end)| {
+                (start..end)
+                    .step_by(piece as usize)
+                    .map(move |s| (s, (s + piece).min(end)))
+            })
+            .collect();
+
+        // Set on the first failure so the remaining pieces are not started.
+        let failed = AtomicBool::new(false);
+        let mut results = stream::iter(pieces)
+            .map(|(start, end)| {
+                self.fetch_range(key, etag, part, start, end, &failed, cancel, pause, on_bytes)
+            })
+            .buffer_unordered(MAX_CONCURRENT_PARTS);
+
+        let mut first_error: Option<FmError> = None;
+        let mut unsaved: u64 = 0;
+        while let Some((start, end, result)) = results.next().await {
+            progress.add(start, end);
+            unsaved += end - start;
+            if let Err(e) = result {
+                failed.store(true, Ordering::Relaxed);
+                first_error.get_or_insert(e);
+            }
+            if unsaved >= partial::MANIFEST_INTERVAL {
+                file.sync_data().await.map_err(FmError::Io)?;
+                progress.save(manifest)?;
+                unsaved = 0;
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(progress.missing().is_empty()),
+        }
+    }
+
+    /// GET `[start, end)` of `key` and write it at the same offset of `part`.
+    /// Returns the range actually written, which ends early when the request
+    /// fails or `stop`, cancel or pause is raised.
+    async fn fetch_range(
+        &self,
+        key: &str,
+        etag: Option<&str>,
+        part: &std::path::Path,
+        start: u64,
+        end: u64,
+        stop: &AtomicBool,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_bytes: &(dyn Fn(u64) + Send + Sync),
+    ) -> (u64, u64, Result<(), FmError>) {
+        let halted = || {
+            stop.load(Ordering::Relaxed) || cancel.load(Ordering::Relaxed) || pause.load(Ordering::Relaxed)
+        };
+        if halted() {
+            return (start, start, Ok(()));
+        }
+
+        let mut req = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range(format!("bytes={}-{}", start, end - 1));
+        if let Some(e) = etag {
+            req = req.if_match(format!("\"{}\"", e));
+        }
+        let mut body = match retry::send(|| req.clone().send()).await {
+            Ok(resp) => resp.body,
+            Err(e) => return (start, start, Err(s3err(e.to_string()))),
+        };
+        let mut file = match tokio::fs::OpenOptions::new().write(true).open(part).await {
+            Ok(f) => f,
+            Err(e) => return (start, start, Err(FmError::Io(e))),
+        };
+        if let Err(e) = file.seek(SeekFrom::Start(start)).await {
+            return (start, start, Err(FmError::Io(e)));
+        }
+
+        let mut pos = start;
+        let result = loop {
+            if halted() {
+                break Ok(());
+            }
+            match body.try_next().await {
+                Ok(Some(chunk)) => {
+                    if let Err(e) = file.write_all(&chunk).await {
+                        break Err(FmError::Io(e));
+                    }
+                    let n = chunk.len() as u64;
+                    pos += n;
+                    throttle(n).await;
+                    on_bytes(n);
+                }
+                Ok(None) if pos < end => {
+                    break Err(s3err(format!("'{}' ended early at byte {} of {}", key, pos, end)));
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(s3err(e.to_string())),
+            }
+        };
+        if let Err(e) = file.flush().await {
+            return (start, start, Err(FmError::Io(e)));
+        }
+        (start, pos, result)
     }
 
-    /// Upload local files to an S3 prefix.
+    /// Upload local files to an S3 prefix, several files at a time.
     /// Returns None on success, Some(checkpoint) on pause.
     pub async fn upload(
         &self,
@@ -491,100 +774,111 @@ impl S3Service {
             .iter()
             .map(|(p, _)| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0))
             .sum();
-        let mut bytes_done: u64 = 0;
-        let mut files_done: u32 = 0;
+        let tally = ByteTally::default();
+        let files_done = AtomicU32::new(0);
         let mut completed_files: Vec<String> = Vec::new();
 
-        for (local_path, key) in &file_list {
-            if cancel.load(Ordering::Relaxed) {
-                return Err(FmError::Other("Operation cancelled".into()));
-            }
-            if pause.load(Ordering::Relaxed) {
-                return Ok(Some(TransferCheckpoint {
-                    files_completed: completed_files,
-                    bytes_done,
-                    bytes_total,
-                    files_done,
-                    files_total,
-                }));
-            }
-
-            let file_size = std::fs::metadata(local_path)
-                .map(|m| m.len())
-                .unwrap_or(0);
-            let filename = local_path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
-
-            if file_size > MULTIPART_THRESHOLD {
-                // Large file: multipart upload with concurrent parts
-                let atomic_bytes_done = Arc::new(AtomicU64::new(bytes_done));
-                let cancel_arc = Arc::new(AtomicBool::new(false));
-
-                let op_id_c = op_id.to_string();
-                let filename_c = filename.clone();
-                let progress_cb = |new_bytes: u64| {
-                    on_progress(ProgressEvent {
-                        id: op_id_c.clone(),
-                        bytes_done: new_bytes,
-                        bytes_total,
-                        current_file: filename_c.clone(),
-                        files_done,
-                        files_total,
-                    });
-                };
+        let result = pool::run_adaptive(
+            file_list,
+            MAX_CONCURRENT_OBJECTS,
+            || cancel.load(Ordering::Relaxed) || pause.load(Ordering::Relaxed),
+            |(local_path, key)| {
+                let tally = &tally;
+                let files_done = &files_done;
+                async move {
+                    let file_size = std::fs::metadata(&local_path)
+                        .map(|m| m.len())
+                        .unwrap_or(0);
+                    let filename = local_path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
 
-                upload_file_multipart(
-                    &self.client,
-                    &self.bucket,
-                    key,
-                    local_path,
-                    file_size,
-                    &cancel_arc,
-                    &atomic_bytes_done,
-                    &progress_cb,
-                    metadata,
-                )
-                .await?;
-                bytes_done = atomic_bytes_done.load(Ordering::Relaxed);
-            } else {
-                // Small file: single put_object
-                let data = std::fs::read(local_path)?;
-                let size = data.len() as u64;
+                    if file_size > MULTIPART_THRESHOLD {
+                        // Large file: multipart upload with concurrent parts
+                        let atomic_bytes_done = Arc::new(AtomicU64::new(0));
+                        let cancel_arc = Arc::new(AtomicBool::new(false));
+                        let progress_cb = |new_bytes: u64| {
+                            on_progress(ProgressEvent {
+                                id: op_id.to_string(),
+                                bytes_done: tally.set(&key, new_bytes),
+                                bytes_total,
+                                current_file: filename.clone(),
+                                files_done: files_done.load(Ordering::Relaxed),
+                                files_total,
+                                retries: retry::retries(),
+                            });
+                        };
+
+                        upload_file_multipart(
+                            &self.client,
+                            &self.bucket,
+                            &key,
+                            &local_path,
+                            file_size,
+                            &cancel_arc,
+                            &atomic_bytes_done,
+                            &progress_cb,
+                            metadata,
+                        )
+                        .await?;
+                    } else {
+                        // Small file: single put_object
+                        let data = std::fs::read(&local_path)?;
+                        let size = data.len() as u64;
+
+                        retry::send(|| {
+                            let mut req = self.client
+                                .put_object()
+                                .bucket(&self.bucket)
+                                .key(&key)
+                                .checksum_algorithm(aws_sdk_s3::types::ChecksumAlgorithm::Crc32C)
+                                .body(data.clone().into());
+                            if let Some(meta) = metadata {
+                                for (mk, mv) in meta {
+                                    req = req.metadata(mk, mv);
+                                }
+                            }
+                            req.send()
+                        })
+                        .await
+                        .map_err(sdk_err)?;
 
-                let mut req = self.client
-                    .put_object()
-                    .bucket(&self.bucket)
-                    .key(key)
-                    .checksum_algorithm(aws_sdk_s3::types::ChecksumAlgorithm::Crc32C)
-                    .body(data.into());
-                if let Some(meta) = metadata {
-                    for (mk, mv) in meta {
-                        req = req.metadata(mk, mv);
+                        throttle(size).await;
+                        tally.set(&key, size);
                     }
+                    Ok((key, filename))
                 }
-                req.send()
-                    .await
-                    .map_err(|e| s3err(e.to_string()))?;
-
-                throttle(size).await;
-                bytes_done += size;
-            }
-
-            files_done += 1;
-            completed_files.push(key.clone());
+            },
+            |(key, filename)| {
+                let done = files_done.fetch_add(1, Ordering::Relaxed) + 1;
+                completed_files.push(key);
+                on_progress(ProgressEvent {
+                    id: op_id.to_string(),
+                    bytes_done: tally.total(),
+                    bytes_total,
+                    current_file: filename,
+                    files_done: done,
+                    files_total,
+                    retries: retry::retries(),
+                });
+            },
+        )
+        .await;
 
-            on_progress(ProgressEvent {
-                id: op_id.to_string(),
-                bytes_done,
+        result?;
+        if cancel.load(Ordering::Relaxed) {
+            return Err(FmError::Other("Operation cancelled".into()));
+        }
+        if completed_files.len() < files_total as usize {
+            return Ok(Some(TransferCheckpoint {
+                files_done: completed_files.len() as u32,
+                files_completed: completed_files,
+                bytes_done: tally.total(),
                 bytes_total,
-                current_file: filename,
-                files_done,
                 files_total,
-            });
+            }));
         }
-
         Ok(None)
     }
 
@@ -656,6 +950,7 @@ impl S3Service {
                 current_file: format!("Encrypting {}", filename),
                 files_done,
                 files_total,
+                retries: retry::retries(),
             });
 
             // Encrypt
@@ -686,6 +981,7 @@ impl S3Service {
                         current_file: filename_c.clone(),
                         files_done,
                         files_total,
+                        retries: retry::retries(),
                     });
                 };
                 let r = upload_file_multipart(
@@ -706,16 +1002,20 @@ impl S3Service {
                     }
                 };
                 let size = data.len() as u64;
-                let mut req = self.client
-                    .put_object()
-                    .bucket(&self.bucket)
-                    .key(key)
-                    .checksum_algorithm(aws_sdk_s3::types::ChecksumAlgorithm::Crc32C)
-                    .body(data.into());
-                for (mk, mv) in &metadata {
-                    req = req.metadata(mk, mv);
-                }
-                let r = req.send().await.map_err(|e| s3err(e.to_string()));
+                let r = retry::send(|| {
+                    let mut req = self.client
+                        .put_object()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .checksum_algorithm(aws_sdk_s3::types::ChecksumAlgorithm::Crc32C)
+                        .body(data.clone().into());
+                    for (mk, mv) in &metadata {
+                        req = req.metadata(mk, mv);
+                    }
+                    req.send()
+                })
+                .await
+                .map_err(|e| s3err(e.to_string()));
                 if r.is_ok() {
                     throttle(size).await;
                     bytes_done += std::fs::metadata(local_path).map(|m| m.len()).unwrap_or(0);
@@ -737,6 +1037,7 @@ impl S3Service {
                 current_file: filename,
                 files_done,
                 files_total,
+                retries: retry::retries(),
             });
         }
 
@@ -747,19 +1048,28 @@ impl S3Service {
     /// Check if an object has client-side encryption metadata.
     pub async fn is_object_encrypted(&self, key: &str) -> Result<bool, FmError> {
         let actual_key = strip_s3_prefix(key, &self.bucket);
-        let head = self.client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let meta: HashMap<String, String> = head.metadata().cloned().unwrap_or_default();
         Ok(super::crypto::EncryptionParams::is_encrypted(&meta))
     }
 
-    /// Server-side copy between S3 locations.
+    /// Server-side copy between S3 locations. Each object keeps its path
+    /// relative to the folder it was selected from, so copying a prefix
+    /// recreates its tree under `dest_prefix`.
+    ///
+    /// `conflict_policy` decides what happens when a destination key exists:
+    /// "overwrite", "skip", "rename" (to "name (1).ext" etc.) or
+    /// "skip_identical" (skip when ETag and size match, overwrite otherwise).
+    /// Objects are copied several at a time.
     /// Returns None on success, Some(checkpoint) on pause.
     pub async fn copy_objects(
         &self,
@@ -769,91 +1079,190 @@ impl S3Service {
         dest_client: &S3Client,
         dest_bucket: &str,
         dest_prefix: &str,
+        conflict_policy: &str,
         op_id: &str,
         cancel: &AtomicBool,
         pause: &AtomicBool,
         on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
     ) -> Result<Option<TransferCheckpoint>, FmError> {
-        let mut resolved: Vec<(String, u64)> = Vec::new();
+        self.resume_copy_objects(
+            src_client, src_bucket, src_keys, dest_client, dest_bucket, dest_prefix,
+            conflict_policy, None, op_id, cancel, pause, on_progress,
+        )
+        .await
+    }
+
+    /// `copy_objects` continuing from the checkpoint of a paused copy: the
+    /// objects it lists as completed count as done and are not looked at
+    /// again, as a multipart copy's ETag never matches its source.
+    pub async fn resume_copy_objects(
+        &self,
+        src_client: &S3Client,
+        src_bucket: &str,
+        src_keys: &[String],
+        dest_client: &S3Client,
+        dest_bucket: &str,
+        dest_prefix: &str,
+        conflict_policy: &str,
+        checkpoint: Option<TransferCheckpoint>,
+        op_id: &str,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+    ) -> Result<Option<TransferCheckpoint>, FmError> {
+        if !matches!(conflict_policy, "overwrite" | "skip" | "rename" | "skip_identical") {
+            return Err(s3err(format!("Unknown conflict policy: {}", conflict_policy)));
+        }
+
+        // (key, size, destination key)
+        let mut resolved: Vec<(String, u64, String)> = Vec::new();
         for raw_key in src_keys {
             let key = strip_s3_prefix(raw_key, src_bucket);
+            let base = parent_prefix(&key).to_string();
             if key.ends_with('/') {
                 let children = list_all_objects(src_client, src_bucket, &key).await?;
                 for (k, size, _) in children {
-                    resolved.push((k, size));
+                    let dest_key = format!("{}{}", dest_prefix, &k[base.len()..]);
+                    resolved.push((k, size, dest_key));
                 }
             } else {
-                let head = src_client
-                    .head_object()
-                    .bucket(src_bucket)
-                    .key(&key)
-                    .send()
-                    .await
-                    .map_err(|e| s3err(e.to_string()))?;
+                let head = retry::send(|| {
+                    src_client
+                        .head_object()
+                        .bucket(src_bucket)
+                        .key(&key)
+                        .send()
+                })
+                .await
+                .map_err(|e| s3err(e.to_string()))?;
                 let size = head.content_length().unwrap_or(0) as u64;
-                resolved.push((key, size));
+                let dest_key = format!("{}{}", dest_prefix, &key[base.len()..]);
+                resolved.push((key, size, dest_key));
             }
         }
 
         let files_total = resolved.len() as u32;
-        let bytes_total: u64 = resolved.iter().map(|(_, s)| *s).sum();
+        let bytes_total: u64 = resolved.iter().map(|(_, s, _)| *s).sum();
         let mut bytes_done: u64 = 0;
-        let mut files_done: u32 = 0;
         let mut completed_files: Vec<String> = Vec::new();
+        if let Some(cp) = checkpoint {
+            let done: HashSet<String> = cp.files_completed.into_iter().collect();
+            resolved.retain(|(key, size, _)| {
+                if !done.contains(key) {
+                    return true;
+                }
+                bytes_done += size;
+                completed_files.push(key.clone());
+                false
+            });
+        }
 
-        for (key, size) in &resolved {
-            if cancel.load(Ordering::Relaxed) {
-                return Err(FmError::Other("Operation cancelled".into()));
-            }
-            if pause.load(Ordering::Relaxed) {
-                return Ok(Some(TransferCheckpoint {
-                    files_completed: completed_files,
+        let result = pool::run_adaptive(
+            resolved,
+            MAX_CONCURRENT_OBJECTS,
+            || cancel.load(Ordering::Relaxed) || pause.load(Ordering::Relaxed),
+            |(key, size, dest_key)| async move {
+                let target = if conflict_policy == "overwrite" {
+                    Some(dest_key)
+                } else {
+                    self.resolve_copy_conflict(src_client, src_bucket, &key, size, dest_client, dest_bucket, &dest_key, conflict_policy)
+                        .await?
+                };
+                if let Some(target) = target {
+                    copy_single_or_multipart(
+                        src_client, src_bucket, &key, dest_client, dest_bucket, &target, size,
+                    )
+                    .await?;
+                }
+                Ok((key, size))
+            },
+            |(key, size)| {
+                bytes_done += size;
+                let filename = key.rsplit('/').next().unwrap_or(&key).to_string();
+                completed_files.push(key);
+                on_progress(ProgressEvent {
+                    id: op_id.to_string(),
                     bytes_done,
                     bytes_total,
-                    files_done,
+                    current_file: filename,
+                    files_done: completed_files.len() as u32,
                     files_total,
-                }));
-            }
-
-            let filename = key.rsplit('/').next().unwrap_or(key);
-            let dest_key = format!("{}{}", dest_prefix, filename);
-
-            copy_single_or_multipart(
-                src_client, src_bucket, key, dest_client, dest_bucket, &dest_key, *size,
-            )
-            .await?;
-
-            bytes_done += size;
-            files_done += 1;
-            completed_files.push(key.clone());
+                    retries: retry::retries(),
+                });
+            },
+        )
+        .await;
 
-            on_progress(ProgressEvent {
-                id: op_id.to_string(),
+        result?;
+        if cancel.load(Ordering::Relaxed) {
+            return Err(FmError::Other("Operation cancelled".into()));
+        }
+        if completed_files.len() < files_total as usize {
+            return Ok(Some(TransferCheckpoint {
+                files_done: completed_files.len() as u32,
+                files_completed: completed_files,
                 bytes_done,
                 bytes_total,
-                current_file: filename.to_string(),
-                files_done,
                 files_total,
-            });
+            }));
         }
-
         Ok(None)
     }
 
+    /// Destination key to copy `key` to under `conflict_policy` when
+    /// `dest_key` may already exist, or None to skip the object.
+    async fn resolve_copy_conflict(
+        &self,
+        src_client: &S3Client,
+        src_bucket: &str,
+        key: &str,
+        size: u64,
+        dest_client: &S3Client,
+        dest_bucket: &str,
+        dest_key: &str,
+        conflict_policy: &str,
+    ) -> Result<Option<String>, FmError> {
+        let Some((dest_etag, dest_size)) = head_etag(dest_client, dest_bucket, dest_key).await? else {
+            return Ok(Some(dest_key.to_string()));
+        };
+        match conflict_policy {
+            "skip" => Ok(None),
+            "skip_identical" => {
+                let same = match head_etag(src_client, src_bucket, key).await? {
+                    Some((src_etag, _)) => !src_etag.is_empty() && src_etag == dest_etag && size == dest_size,
+                    None => false,
+                };
+                Ok(if same { None } else { Some(dest_key.to_string()) })
+            }
+            "rename" => {
+                let mut n = 1;
+                loop {
+                    let candidate = numbered_key(dest_key, n);
+                    if head_etag(dest_client, dest_bucket, &candidate).await?.is_none() {
+                        return Ok(Some(candidate));
+                    }
+                    n += 1;
+                }
+            }
+            _ => Ok(Some(dest_key.to_string())),
+        }
+    }
+
     // ── Object Operations ───────────────────────────────────────────────
 
     /// Get properties of a single S3 object via head_object.
     pub async fn head_object(&self, key: &str) -> Result<S3ObjectProperties, FmError> {
         let actual_key = strip_s3_prefix(key, &self.bucket);
 
-        let head = self
-            .client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let size = head.content_length().unwrap_or(0) as u64;
         let modified = head
@@ -893,33 +1302,42 @@ impl S3Service {
             }
         }
 
-        // Batch delete (max 1000 per request)
-        for chunk in to_delete.chunks(1000) {
-            let objects: Vec<_> = chunk
-                .iter()
-                .map(|k| {
-                    aws_sdk_s3::types::ObjectIdentifier::builder()
-                        .key(k)
-                        .build()
-                        .expect("valid object identifier")
-                })
-                .collect();
+        // Batch delete (max 1000 per request), several batches at once
+        let batches: Vec<Vec<String>> = to_delete.chunks(1000).map(|c| c.to_vec()).collect();
+        pool::run_adaptive(
+            batches,
+            MAX_CONCURRENT_OBJECTS,
+            || false,
+            |batch| async move {
+                let objects: Vec<_> = batch
+                    .iter()
+                    .map(|k| {
+                        aws_sdk_s3::types::ObjectIdentifier::builder()
+                            .key(k)
+                            .build()
+                            .expect("valid object identifier")
+                    })
+                    .collect();
 
-            let delete = aws_sdk_s3::types::Delete::builder()
-                .set_objects(Some(objects))
-                .build()
-                .map_err(|e| s3err(e.to_string()))?;
+                let delete = aws_sdk_s3::types::Delete::builder()
+                    .set_objects(Some(objects))
+                    .build()
+                    .map_err(|e| s3err(e.to_string()))?;
 
-            self.client
-                .delete_objects()
-                .bucket(&self.bucket)
-                .delete(delete)
-                .send()
+                retry::send(|| {
+                    self.client
+                        .delete_objects()
+                        .bucket(&self.bucket)
+                        .delete(delete.clone())
+                        .send()
+                })
                 .await
-                .map_err(|e| s3err(e.to_string()))?;
-        }
-
-        Ok(())
+                .map_err(sdk_err)?;
+                Ok(())
+            },
+            |()| {},
+        )
+        .await
     }
 
     /// Create a "folder" in S3 by putting a zero-byte object with a trailing-slash key.
@@ -932,29 +1350,32 @@ impl S3Service {
         };
 
         // Check if anything already exists under this prefix
-        let check = self
-            .client
-            .list_objects_v2()
-            .bucket(&self.bucket)
-            .prefix(&folder_key)
-            .max_keys(1)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let check = retry::send(|| {
+            self.client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&folder_key)
+                .max_keys(1)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         if !check.contents().is_empty() || !check.common_prefixes().is_empty() {
             return Err(FmError::AlreadyExists(folder_key));
         }
 
         // Put zero-byte object
-        self.client
-            .put_object()
-            .bucket(&self.bucket)
-            .key(&folder_key)
-            .body(aws_sdk_s3::primitives::ByteStream::from_static(b""))
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(&folder_key)
+                .body(aws_sdk_s3::primitives::ByteStream::from_static(b""))
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -986,26 +1407,28 @@ impl S3Service {
         };
 
         // Check destination doesn't already exist
-        let dest_head = self
-            .client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&dest_key)
-            .send()
-            .await;
+        let dest_head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&dest_key)
+                .send()
+        })
+        .await;
         if dest_head.is_ok() {
             return Err(FmError::AlreadyExists(dest_key));
         }
 
         // Get source object size for multipart copy routing
-        let src_head = self
-            .client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let src_head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
         let object_size = src_head.content_length().unwrap_or(0) as u64;
 
         copy_single_or_multipart(
@@ -1014,13 +1437,15 @@ impl S3Service {
         .await?;
 
         // Delete original
-        self.client
-            .delete_object()
-            .bucket(&self.bucket)
-            .key(key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .delete_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1034,15 +1459,16 @@ impl S3Service {
         };
 
         // Check target prefix is empty
-        let check = self
-            .client
-            .list_objects_v2()
-            .bucket(&self.bucket)
-            .prefix(&new_prefix)
-            .max_keys(1)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let check = retry::send(|| {
+            self.client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&new_prefix)
+                .max_keys(1)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         if !check.contents().is_empty() {
             return Err(FmError::AlreadyExists(new_prefix));
@@ -1085,13 +1511,15 @@ impl S3Service {
                 .build()
                 .map_err(|e| s3err(e.to_string()))?;
 
-            self.client
-                .delete_objects()
-                .bucket(&self.bucket)
-                .delete(delete)
-                .send()
-                .await
-                .map_err(|e| s3err(e.to_string()))?;
+            retry::send(|| {
+                self.client
+                    .delete_objects()
+                    .bucket(&self.bucket)
+                    .delete(delete.clone())
+                    .send()
+            })
+            .await
+            .map_err(|e| s3err(e.to_string()))?;
         }
 
         Ok(())
@@ -1130,7 +1558,7 @@ impl S3Service {
                 req = req.continuation_token(token);
             }
 
-            let resp = req.send().await.map_err(|e| s3err(e.to_string()))?;
+            let resp = retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
 
             for obj in resp.contents() {
                 if cancel.load(Ordering::Relaxed) {
@@ -1184,6 +1612,217 @@ impl S3Service {
         Ok(())
     }
 
+    // ── Streaming ───────────────────────────────────────────────────────
+
+    /// Stream the body of an object as it arrives. Bandwidth limits are left
+    /// to whoever copies the stream, so each byte is only paced once.
+    pub async fn read_stream(&self, key: &str) -> Result<ChunkStream<'static>, FmError> {
+        self.read_stream_from(key, 0).await
+    }
+
+    /// Like `read_stream`, starting at byte `offset` (ranged GET).
+    pub async fn read_stream_from(
+        &self,
+        key: &str,
+        offset: u64,
+    ) -> Result<ChunkStream<'static>, FmError> {
+        let mut req = self.client.get_object().bucket(&self.bucket).key(key);
+        if offset > 0 {
+            req = req.range(format!("bytes={}-", offset));
+        }
+        let resp = retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
+
+        Ok(stream::try_unfold(resp.body, |mut body| async move {
+            match body.try_next().await.map_err(|e| s3err(e.to_string()))? {
+                Some(chunk) => Ok(Some((Vec::from(chunk), body))),
+                None => Ok(None),
+            }
+        })
+        .boxed())
+    }
+
+    /// Store a stream as an object. Streams of `MULTIPART_THRESHOLD` bytes or
+    /// more go up as a multipart upload with up to `MAX_CONCURRENT_PARTS`
+    /// parts in flight, so memory stays bounded; the upload is aborted if the
+    /// stream fails.
+    pub async fn write_stream(
+        &self,
+        key: &str,
+        size: u64,
+        mut data: ChunkStream<'_>,
+    ) -> Result<(), FmError> {
+        if size < MULTIPART_THRESHOLD {
+            let mut buf = Vec::with_capacity(size as usize);
+            while let Some(chunk) = data.try_next().await? {
+                buf.extend_from_slice(&chunk);
+            }
+            retry::send(|| {
+                self.client
+                    .put_object()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .body(buf.clone().into())
+                    .send()
+            })
+            .await
+            .map_err(|e| s3err(e.to_string()))?;
+            return Ok(());
+        }
+
+        let upload_id = self.create_multipart(key).await?;
+        let (parts, result) = self
+            .upload_parts(key, &upload_id, part_size_for(size), 1, &mut data)
+            .await;
+        match result {
+            Ok(()) => complete_multipart(&self.client, &self.bucket, key, &upload_id, parts).await,
+            Err(e) => {
+                let _ = self.abort_multipart_upload(key, &upload_id).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Start a multipart upload with CRC32C part checksums, returning its id.
+    pub async fn create_multipart(&self, key: &str) -> Result<String, FmError> {
+        Ok(retry::send(|| {
+            self.client
+                .create_multipart_upload()
+                .bucket(&self.bucket)
+                .key(key)
+                .checksum_algorithm(aws_sdk_s3::types::ChecksumAlgorithm::Crc32C)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?
+        .upload_id()
+        .ok_or_else(|| s3err("Missing upload_id from create_multipart_upload"))?
+        .to_string())
+    }
+
+    /// Cut `data` into parts of `part_size` bytes, numbered from `first_part`,
+    /// and upload up to `MAX_CONCURRENT_PARTS` of them at a time. Returns the
+    /// completed parts along with the outcome. Parts in flight when the
+    /// stream fails are still finished, so after a clean stop such as a pause
+    /// the completed parts run without gaps from `first_part`.
+    pub async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_size: u64,
+        first_part: i32,
+        data: &mut ChunkStream<'_>,
+    ) -> (Vec<(i32, String, Option<String>)>, Result<(), FmError>) {
+        let part_size = part_size as usize;
+        let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PARTS));
+        let mut handles = Vec::new();
+        let mut pending: Vec<u8> = Vec::new();
+        let mut part_number = first_part;
+        let mut finished = false;
+
+        let read_result: Result<(), FmError> = async {
+            while !finished {
+                // Waiting for a permit before reading bounds the parts in memory.
+                let permit = semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| FmError::Other("semaphore closed".into()))?;
+                while pending.len() < part_size {
+                    match data.try_next().await? {
+                        Some(chunk) => pending.extend_from_slice(&chunk),
+                        None => {
+                            finished = true;
+                            break;
+                        }
+                    }
+                }
+                // An empty upload still needs one (empty) part.
+                if pending.is_empty() && part_number > 1 {
+                    break;
+                }
+                let rest = pending.split_off(pending.len().min(part_size));
+                let part = std::mem::replace(&mut pending, rest);
+
+                let (client, bucket, key, upload_id) = (
+                    self.client.clone(),
+                    self.bucket.clone(),
+                    key.to_string(),
+                    upload_id.to_string(),
+                );
+                let number = part_number;
+                let tally = retry::current_tally();
+                handles.push(tokio::spawn(retry::in_tally(tally, async move {
+                    let _permit = permit;
+                    upload_part_bytes(&client, &bucket, &key, &upload_id, number, part).await
+                })));
+                part_number += 1;
+            }
+            Ok(())
+        }
+        .await;
+
+        let mut parts = Vec::with_capacity(handles.len());
+        let mut first_error = read_result.err();
+        for handle in handles {
+            match handle.await {
+                Ok(Ok(part)) => parts.push(part),
+                Ok(Err(e)) => {
+                    first_error.get_or_insert(e);
+                }
+                Err(e) => {
+                    first_error.get_or_insert(FmError::Other(format!("Task join error: {}", e)));
+                }
+            }
+        }
+        match first_error {
+            Some(e) => (parts, Err(e)),
+            None => (parts, Ok(())),
+        }
+    }
+
+    /// Parts already uploaded to an open multipart upload as
+    /// (part_number, etag, crc32c, size), in part order.
+    pub async fn list_uploaded_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<Vec<(i32, String, Option<String>, u64)>, FmError> {
+        let mut parts = Vec::new();
+        let mut marker: Option<String> = None;
+        loop {
+            let mut req = self
+                .client
+                .list_parts()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id);
+            if let Some(m) = &marker {
+                req = req.part_number_marker(m);
+            }
+            let resp = retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
+            for part in resp.parts() {
+                if let (Some(number), Some(etag)) = (part.part_number(), part.e_tag()) {
+                    parts.push((
+                        number,
+                        etag.to_string(),
+                        part.checksum_crc32_c().map(|s| s.to_string()),
+                        part.size().unwrap_or(0) as u64,
+                    ));
+                }
+            }
+            if resp.is_truncated() == Some(true) {
+                marker = resp.next_part_number_marker().map(|s| s.to_string());
+                if marker.is_none() {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+        parts.sort_by_key(|(number, _, _, _)| *number);
+        Ok(parts)
+    }
+
     // ── File Editing & Preview ──────────────────────────────────────────
 
     /// Download a single S3 object to a temp file and return the local path.
@@ -1191,14 +1830,15 @@ impl S3Service {
         let stripped_key = strip_s3_prefix(key, &self.bucket);
 
         // Check object size via head_object
-        let head = self
-            .client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&stripped_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&stripped_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let size = head.content_length().unwrap_or(0) as u64;
         if size > PREVIEW_MAX_SIZE {
@@ -1224,14 +1864,15 @@ impl S3Service {
         }
 
         // Download the object
-        let resp = self
-            .client
-            .get_object()
-            .bucket(&self.bucket)
-            .key(&stripped_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| {
+            self.client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(&stripped_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let obj_metadata: HashMap<String, String> = resp.metadata().cloned().unwrap_or_default();
         let body = resp
@@ -1258,14 +1899,16 @@ impl S3Service {
     pub async fn put_text(&self, key: &str, content: &str) -> Result<(), FmError> {
         let stripped = strip_s3_prefix(key, &self.bucket);
 
-        self.client
-            .put_object()
-            .bucket(&self.bucket)
-            .key(&stripped)
-            .body(content.as_bytes().to_vec().into())
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(&stripped)
+                .body(content.as_bytes().to_vec().into())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1278,17 +1921,18 @@ impl S3Service {
         key: &str,
         target_class: &str,
     ) -> Result<(), FmError> {
-        let actual_key = strip_s3_prefix(key, &self.bucket);
-
-        // Check object size — reject >5 GiB (copy_object limit)
-        let head = self
-            .client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let actual_key = strip_s3_prefix(key, &self.bucket);
+
+        // Check object size — reject >5 GiB (copy_object limit)
+        let head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let size = head.content_length().unwrap_or(0) as u64;
         if size > COPY_MULTIPART_THRESHOLD {
@@ -1301,16 +1945,18 @@ impl S3Service {
         let copy_source = format!("{}/{}", self.bucket, actual_key);
         let storage_class = aws_sdk_s3::types::StorageClass::from(target_class);
 
-        self.client
-            .copy_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .copy_source(&copy_source)
-            .storage_class(storage_class)
-            .metadata_directive(aws_sdk_s3::types::MetadataDirective::Copy)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .copy_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .copy_source(&copy_source)
+                .storage_class(storage_class.clone())
+                .metadata_directive(aws_sdk_s3::types::MetadataDirective::Copy)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1328,16 +1974,17 @@ impl S3Service {
             let actual_key = strip_s3_prefix(key, &self.bucket);
             let copy_source = format!("{}/{}", self.bucket, actual_key);
 
-            let result = self
-                .client
-                .copy_object()
-                .bucket(&self.bucket)
-                .key(&actual_key)
-                .copy_source(&copy_source)
-                .storage_class(storage_class.clone())
-                .metadata_directive(aws_sdk_s3::types::MetadataDirective::Copy)
-                .send()
-                .await;
+            let result = retry::send(|| {
+                self.client
+                    .copy_object()
+                    .bucket(&self.bucket)
+                    .key(&actual_key)
+                    .copy_source(&copy_source)
+                    .storage_class(storage_class.clone())
+                    .metadata_directive(aws_sdk_s3::types::MetadataDirective::Copy)
+                    .send()
+            })
+            .await;
 
             if result.is_err() {
                 failed.push(key.clone());
@@ -1370,14 +2017,16 @@ impl S3Service {
             .glacier_job_parameters(glacier_params)
             .build();
 
-        self.client
-            .restore_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .restore_request(restore_request)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .restore_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .restore_request(restore_request.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1405,7 +2054,7 @@ impl S3Service {
                 req = req.version_id_marker(vm);
             }
 
-            let resp = req.send().await.map_err(|e| s3err(e.to_string()))?;
+            let resp = retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
 
             for v in resp.versions() {
                 let vkey = v.key().unwrap_or_default();
@@ -1467,15 +2116,16 @@ impl S3Service {
     ) -> Result<String, FmError> {
         let stripped_key = strip_s3_prefix(key, &self.bucket);
 
-        let resp = self
-            .client
-            .get_object()
-            .bucket(&self.bucket)
-            .key(&stripped_key)
-            .version_id(version_id)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| {
+            self.client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(&stripped_key)
+                .version_id(version_id)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let filename = stripped_key.rsplit('/').next().unwrap_or(&stripped_key);
         let short_vid = if version_id.len() > 8 { &version_id[..8] } else { version_id };
@@ -1511,14 +2161,16 @@ impl S3Service {
             urlencoding::encode(version_id)
         );
 
-        self.client
-            .copy_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .copy_source(&copy_source)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .copy_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .copy_source(&copy_source)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1543,7 +2195,7 @@ impl S3Service {
             req = req.mfa(val);
         }
 
-        req.send().await.map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1579,13 +2231,14 @@ impl S3Service {
 
     /// Get versioning status for the bucket.
     pub async fn get_bucket_versioning(&self) -> Result<S3BucketVersioning, FmError> {
-        let resp = self
-            .client
-            .get_bucket_versioning()
-            .bucket(&self.bucket)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_versioning()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let status = match resp.status() {
             Some(s) => s.as_str().to_string(),
@@ -1632,7 +2285,7 @@ impl S3Service {
             req = req.mfa(val);
         }
 
-        req.send().await.map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1641,12 +2294,13 @@ impl S3Service {
 
     /// Get encryption configuration for the bucket.
     pub async fn get_bucket_encryption(&self) -> Result<S3BucketEncryption, FmError> {
-        let resp = self
-            .client
-            .get_bucket_encryption()
-            .bucket(&self.bucket)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_encryption()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -1697,14 +2351,15 @@ impl S3Service {
     pub async fn get_object_metadata(&self, key: &str) -> Result<S3ObjectMetadata, FmError> {
         let actual_key = strip_s3_prefix(key, &self.bucket);
 
-        let head = self
-            .client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let custom: HashMap<String, String> = head
             .metadata()
@@ -1733,14 +2388,15 @@ impl S3Service {
         let actual_key = strip_s3_prefix(key, &self.bucket);
 
         // Check object size — reject >5 GiB (copy_object limit)
-        let head = self
-            .client
-            .head_object()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let head = retry::send(|| {
+            self.client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let size = head.content_length().unwrap_or(0) as u64;
         if size > COPY_MULTIPART_THRESHOLD {
@@ -1777,7 +2433,7 @@ impl S3Service {
             req = req.metadata(k, v);
         }
 
-        req.send()
+        retry::send(|| req.clone().send())
             .await
             .map_err(|e| s3err(e.to_string()))?;
 
@@ -1790,13 +2446,14 @@ impl S3Service {
     pub async fn get_object_tags(&self, key: &str) -> Result<Vec<S3Tag>, FmError> {
         let actual_key = strip_s3_prefix(key, &self.bucket);
 
-        let resp = self
-            .client
-            .get_object_tagging()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_object_tagging()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -1854,26 +2511,29 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_object_tagging()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .tagging(tagging)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_object_tagging()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .tagging(tagging.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
 
     /// Get tags for the bucket.
     pub async fn get_bucket_tags(&self) -> Result<Vec<S3Tag>, FmError> {
-        let resp = self
-            .client
-            .get_bucket_tagging()
-            .bucket(&self.bucket)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_tagging()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -1925,13 +2585,15 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_tagging()
-            .bucket(&self.bucket)
-            .tagging(tagging)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_tagging()
+                .bucket(&self.bucket)
+                .tagging(tagging.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -1960,7 +2622,7 @@ impl S3Service {
                 req = req.upload_id_marker(um);
             }
 
-            let resp = req.send().await.map_err(|e| s3err(e.to_string()))?;
+            let resp = retry::send(|| req.clone().send()).await.map_err(|e| s3err(e.to_string()))?;
 
             for upload in resp.uploads() {
                 let key = upload.key().unwrap_or_default().to_string();
@@ -1994,14 +2656,16 @@ impl S3Service {
         key: &str,
         upload_id: &str,
     ) -> Result<(), FmError> {
-        self.client
-            .abort_multipart_upload()
-            .bucket(&self.bucket)
-            .key(key)
-            .upload_id(upload_id)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2010,12 +2674,13 @@ impl S3Service {
 
     /// Get bucket lifecycle configuration rules.
     pub async fn get_bucket_lifecycle(&self) -> Result<Vec<S3LifecycleRule>, FmError> {
-        let resp = self
-            .client
-            .get_bucket_lifecycle_configuration()
-            .bucket(&self.bucket)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_lifecycle_configuration()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -2112,12 +2777,14 @@ impl S3Service {
         rules: &[S3LifecycleRule],
     ) -> Result<(), FmError> {
         if rules.is_empty() {
-            self.client
-                .delete_bucket_lifecycle()
-                .bucket(&self.bucket)
-                .send()
-                .await
-                .map_err(|e| s3err(e.to_string()))?;
+            retry::send(|| {
+                self.client
+                    .delete_bucket_lifecycle()
+                    .bucket(&self.bucket)
+                    .send()
+            })
+            .await
+            .map_err(|e| s3err(e.to_string()))?;
             return Ok(());
         }
 
@@ -2192,13 +2859,15 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_lifecycle_configuration()
-            .bucket(&self.bucket)
-            .lifecycle_configuration(config)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_lifecycle_configuration()
+                .bucket(&self.bucket)
+                .lifecycle_configuration(config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2207,7 +2876,7 @@ impl S3Service {
 
     /// Get CORS configuration for the bucket.
     pub async fn get_bucket_cors(&self) -> Result<Vec<S3CorsRule>, FmError> {
-        let resp = self.client.get_bucket_cors().bucket(&self.bucket).send().await;
+        let resp = retry::send(|| self.client.get_bucket_cors().bucket(&self.bucket).send()).await;
 
         match resp {
             Ok(r) => {
@@ -2242,12 +2911,14 @@ impl S3Service {
     /// Set CORS configuration for the bucket.
     pub async fn put_bucket_cors(&self, rules: &[S3CorsRule]) -> Result<(), FmError> {
         if rules.is_empty() {
-            self.client
-                .delete_bucket_cors()
-                .bucket(&self.bucket)
-                .send()
-                .await
-                .map_err(|e| s3err(e.to_string()))?;
+            retry::send(|| {
+                self.client
+                    .delete_bucket_cors()
+                    .bucket(&self.bucket)
+                    .send()
+            })
+            .await
+            .map_err(|e| s3err(e.to_string()))?;
             return Ok(());
         }
 
@@ -2271,13 +2942,15 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_cors()
-            .bucket(&self.bucket)
-            .cors_configuration(config)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_cors()
+                .bucket(&self.bucket)
+                .cors_configuration(config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2286,7 +2959,7 @@ impl S3Service {
 
     /// Get public access block configuration for the bucket.
     pub async fn get_public_access_block(&self) -> Result<S3PublicAccessBlock, FmError> {
-        let resp = self.client.get_public_access_block().bucket(&self.bucket).send().await;
+        let resp = retry::send(|| self.client.get_public_access_block().bucket(&self.bucket).send()).await;
 
         match resp {
             Ok(r) => {
@@ -2338,13 +3011,15 @@ impl S3Service {
             .restrict_public_buckets(config.restrict_public_buckets)
             .build();
 
-        self.client
-            .put_public_access_block()
-            .bucket(&self.bucket)
-            .public_access_block_configuration(sdk_config)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_public_access_block()
+                .bucket(&self.bucket)
+                .public_access_block_configuration(sdk_config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2353,7 +3028,7 @@ impl S3Service {
 
     /// Get bucket policy as a JSON string.
     pub async fn get_bucket_policy(&self) -> Result<String, FmError> {
-        let resp = self.client.get_bucket_policy().bucket(&self.bucket).send().await;
+        let resp = retry::send(|| self.client.get_bucket_policy().bucket(&self.bucket).send()).await;
 
         match resp {
             Ok(r) => Ok(r.policy().unwrap_or_default().to_string()),
@@ -2375,12 +3050,14 @@ impl S3Service {
     /// Set bucket policy from a JSON string. Empty string deletes the policy.
     pub async fn put_bucket_policy(&self, policy: &str) -> Result<(), FmError> {
         if policy.trim().is_empty() {
-            self.client
-                .delete_bucket_policy()
-                .bucket(&self.bucket)
-                .send()
-                .await
-                .map_err(|e| s3err(e.to_string()))?;
+            retry::send(|| {
+                self.client
+                    .delete_bucket_policy()
+                    .bucket(&self.bucket)
+                    .send()
+            })
+            .await
+            .map_err(|e| s3err(e.to_string()))?;
             return Ok(());
         }
 
@@ -2388,13 +3065,15 @@ impl S3Service {
         let _: serde_json::Value =
             serde_json::from_str(policy).map_err(|e| s3err(format!("Invalid JSON: {}", e)))?;
 
-        self.client
-            .put_bucket_policy()
-            .bucket(&self.bucket)
-            .policy(policy)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_policy()
+                .bucket(&self.bucket)
+                .policy(policy)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2403,13 +3082,14 @@ impl S3Service {
 
     /// Get bucket ACL.
     pub async fn get_bucket_acl(&self) -> Result<S3BucketAcl, FmError> {
-        let resp = self
-            .client
-            .get_bucket_acl()
-            .bucket(&self.bucket)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_acl()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         let owner_id = resp
             .owner()
@@ -2472,13 +3152,15 @@ impl S3Service {
             "authenticated-read" => BucketCannedAcl::AuthenticatedRead,
             other => return Err(s3err(format!("Unknown canned ACL: {}", other))),
         };
-        self.client
-            .put_bucket_acl()
-            .bucket(&self.bucket)
-            .acl(canned)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_acl()
+                .bucket(&self.bucket)
+                .acl(canned.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
         Ok(())
     }
 
@@ -2486,7 +3168,7 @@ impl S3Service {
 
     /// Get static website hosting configuration.
     pub async fn get_bucket_website(&self) -> Result<S3BucketWebsite, FmError> {
-        let resp = self.client.get_bucket_website().bucket(&self.bucket).send().await;
+        let resp = retry::send(|| self.client.get_bucket_website().bucket(&self.bucket).send()).await;
 
         match resp {
             Ok(r) => {
@@ -2525,12 +3207,14 @@ impl S3Service {
     /// Set or delete static website hosting configuration.
     pub async fn put_bucket_website(&self, config: &S3BucketWebsite) -> Result<(), FmError> {
         if !config.enabled {
-            self.client
-                .delete_bucket_website()
-                .bucket(&self.bucket)
-                .send()
-                .await
-                .map_err(|e| s3err(e.to_string()))?;
+            retry::send(|| {
+                self.client
+                    .delete_bucket_website()
+                    .bucket(&self.bucket)
+                    .send()
+            })
+            .await
+            .map_err(|e| s3err(e.to_string()))?;
             return Ok(());
         }
 
@@ -2554,13 +3238,15 @@ impl S3Service {
 
         let website_config = website_builder.build();
 
-        self.client
-            .put_bucket_website()
-            .bucket(&self.bucket)
-            .website_configuration(website_config)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_website()
+                .bucket(&self.bucket)
+                .website_configuration(website_config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2569,13 +3255,14 @@ impl S3Service {
 
     /// Get requester pays configuration.
     pub async fn get_request_payment(&self) -> Result<bool, FmError> {
-        let resp = self
-            .client
-            .get_bucket_request_payment()
-            .bucket(&self.bucket)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_request_payment()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(resp.payer() == Some(&aws_sdk_s3::types::Payer::Requester))
     }
@@ -2593,13 +3280,15 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_request_payment()
-            .bucket(&self.bucket)
-            .request_payment_configuration(config)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_request_payment()
+                .bucket(&self.bucket)
+                .request_payment_configuration(config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2608,12 +3297,13 @@ impl S3Service {
 
     /// Get bucket ownership controls.
     pub async fn get_bucket_ownership(&self) -> Result<S3BucketOwnership, FmError> {
-        let resp = self
-            .client
-            .get_bucket_ownership_controls()
-            .bucket(&self.bucket)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_ownership_controls()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -2664,13 +3354,15 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_ownership_controls()
-            .bucket(&self.bucket)
-            .ownership_controls(controls)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_ownership_controls()
+                .bucket(&self.bucket)
+                .ownership_controls(controls.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2679,13 +3371,14 @@ impl S3Service {
 
     /// Get server access logging configuration.
     pub async fn get_bucket_logging(&self) -> Result<S3BucketLogging, FmError> {
-        let resp = self
-            .client
-            .get_bucket_logging()
-            .bucket(&self.bucket)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_logging()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         if let Some(le) = resp.logging_enabled() {
             let tb = le.target_bucket().to_string();
@@ -2728,13 +3421,15 @@ impl S3Service {
 
         let status = status_builder.build();
 
-        self.client
-            .put_bucket_logging()
-            .bucket(&self.bucket)
-            .bucket_logging_status(status)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_logging()
+                .bucket(&self.bucket)
+                .bucket_logging_status(status.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2776,13 +3471,15 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_encryption()
-            .bucket(&self.bucket)
-            .server_side_encryption_configuration(config)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_encryption()
+                .bucket(&self.bucket)
+                .server_side_encryption_configuration(config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2791,12 +3488,13 @@ impl S3Service {
 
     /// Get Object Lock configuration for the bucket.
     pub async fn get_object_lock_configuration(&self) -> Result<S3ObjectLockConfig, FmError> {
-        let resp = self
-            .client
-            .get_object_lock_configuration()
-            .bucket(&self.bucket)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_object_lock_configuration()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -2884,13 +3582,15 @@ impl S3Service {
                 .build()
         };
 
-        self.client
-            .put_object_lock_configuration()
-            .bucket(&self.bucket)
-            .object_lock_configuration(config)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_object_lock_configuration()
+                .bucket(&self.bucket)
+                .object_lock_configuration(config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -2899,13 +3599,14 @@ impl S3Service {
     pub async fn get_object_retention(&self, key: &str) -> Result<S3ObjectRetention, FmError> {
         let actual_key = strip_s3_prefix(key, &self.bucket);
 
-        let resp = self
-            .client
-            .get_object_retention()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_object_retention()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -2980,7 +3681,7 @@ impl S3Service {
             req = req.bypass_governance_retention(true);
         }
 
-        req.send()
+        retry::send(|| req.clone().send())
             .await
             .map_err(|e| s3err(e.to_string()))?;
 
@@ -2991,13 +3692,14 @@ impl S3Service {
     pub async fn get_object_legal_hold(&self, key: &str) -> Result<S3ObjectLegalHold, FmError> {
         let actual_key = strip_s3_prefix(key, &self.bucket);
 
-        let resp = self
-            .client
-            .get_object_legal_hold()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_object_legal_hold()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -3045,14 +3747,16 @@ impl S3Service {
             .status(hold_status)
             .build();
 
-        self.client
-            .put_object_legal_hold()
-            .bucket(&self.bucket)
-            .key(&actual_key)
-            .legal_hold(legal_hold)
-            .send()
-            .await
-            .map_err(|e| s3err(e.to_string()))?;
+        retry::send(|| {
+            self.client
+                .put_object_legal_hold()
+                .bucket(&self.bucket)
+                .key(&actual_key)
+                .legal_hold(legal_hold.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(e.to_string()))?;
 
         Ok(())
     }
@@ -3098,7 +3802,7 @@ impl S3Service {
                 req = req.bypass_governance_retention(true);
             }
 
-            if req.send().await.is_err() {
+            if retry::send(|| req.clone().send()).await.is_err() {
                 failed.push(key.clone());
             }
         }
@@ -3134,6 +3838,7 @@ impl S3Service {
                 current_file: key.clone(),
                 files_done: i as u32,
                 files_total: total,
+                retries: retry::retries(),
             });
 
             if self
@@ -3159,6 +3864,7 @@ impl S3Service {
             current_file: String::new(),
             files_done: total,
             files_total: total,
+            retries: retry::retries(),
         });
 
         Ok(failed)
@@ -3190,6 +3896,7 @@ impl S3Service {
                 current_file: key.clone(),
                 files_done: i as u32,
                 files_total: total,
+                retries: retry::retries(),
             });
 
             let final_tags = if merge {
@@ -3232,6 +3939,7 @@ impl S3Service {
             current_file: String::new(),
             files_done: total,
             files_total: total,
+            retries: retry::retries(),
         });
 
         Ok(failed)
@@ -3256,7 +3964,7 @@ impl S3Service {
                 req = req.continuation_token(token);
             }
 
-            let resp = req.send().await;
+            let resp = retry::send(|| req.clone().send()).await;
 
             match resp {
                 Ok(r) => {
@@ -3410,27 +4118,31 @@ impl S3Service {
 
         let sdk_config = builder.build().map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_inventory_configuration()
-            .bucket(&self.bucket)
-            .id(&config.id)
-            .inventory_configuration(sdk_config)
-            .send()
-            .await
-            .map_err(|e| s3err(format!("Failed to put inventory configuration: {}", e)))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_inventory_configuration()
+                .bucket(&self.bucket)
+                .id(&config.id)
+                .inventory_configuration(sdk_config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("Failed to put inventory configuration: {}", e)))?;
 
         Ok(())
     }
 
     /// Delete an inventory configuration by ID.
     pub async fn delete_inventory_configuration(&self, config_id: &str) -> Result<(), FmError> {
-        self.client
-            .delete_bucket_inventory_configuration()
-            .bucket(&self.bucket)
-            .id(config_id)
-            .send()
-            .await
-            .map_err(|e| s3err(format!("Failed to delete inventory configuration: {}", e)))?;
+        retry::send(|| {
+            self.client
+                .delete_bucket_inventory_configuration()
+                .bucket(&self.bucket)
+                .id(config_id)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("Failed to delete inventory configuration: {}", e)))?;
 
         Ok(())
     }
@@ -3441,12 +4153,13 @@ impl S3Service {
     pub async fn get_replication_configuration(
         &self,
     ) -> Result<Option<S3ReplicationConfiguration>, FmError> {
-        let resp = self
-            .client
-            .get_bucket_replication()
-            .bucket(&self.bucket)
-            .send()
-            .await;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_replication()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await;
 
         match resp {
             Ok(r) => {
@@ -3607,25 +4320,29 @@ impl S3Service {
             .build()
             .map_err(|e| s3err(e.to_string()))?;
 
-        self.client
-            .put_bucket_replication()
-            .bucket(&self.bucket)
-            .replication_configuration(sdk_config)
-            .send()
-            .await
-            .map_err(|e| s3err(format!("Failed to put replication configuration: {}", e)))?;
+        retry::send(|| {
+            self.client
+                .put_bucket_replication()
+                .bucket(&self.bucket)
+                .replication_configuration(sdk_config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("Failed to put replication configuration: {}", e)))?;
 
         Ok(())
     }
 
     /// Delete the replication configuration for this bucket.
     pub async fn delete_replication_configuration(&self) -> Result<(), FmError> {
-        self.client
-            .delete_bucket_replication()
-            .bucket(&self.bucket)
-            .send()
-            .await
-            .map_err(|e| s3err(format!("Failed to delete replication configuration: {}", e)))?;
+        retry::send(|| {
+            self.client
+                .delete_bucket_replication()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("Failed to delete replication configuration: {}", e)))?;
 
         Ok(())
     }
@@ -3636,13 +4353,14 @@ impl S3Service {
     pub async fn get_notification_configuration(
         &self,
     ) -> Result<S3NotificationConfiguration, FmError> {
-        let resp = self
-            .client
-            .get_bucket_notification_configuration()
-            .bucket(&self.bucket)
-            .send()
-            .await
-            .map_err(|e| s3err(format!("Failed to get notification configuration: {}", e)))?;
+        let resp = retry::send(|| {
+            self.client
+                .get_bucket_notification_configuration()
+                .bucket(&self.bucket)
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("Failed to get notification configuration: {}", e)))?;
 
         let mut rules = Vec::new();
 
@@ -3786,13 +4504,15 @@ impl S3Service {
 
         let notif_config = notif_builder.build();
 
-        self.client
-            .put_bucket_notification_configuration()
-            .bucket(&self.bucket)
-            .notification_configuration(notif_config)
-            .send()
-            .await
-            .map_err(|e| {
+        retry::send(|| {
+            self.client
+                .put_bucket_notification_configuration()
+                .bucket(&self.bucket)
+                .notification_configuration(notif_config.clone())
+                .send()
+        })
+        .await
+        .map_err(|e| {
                 s3err(format!(
                     "Failed to put notification configuration: {}",
                     e
@@ -3905,10 +4625,12 @@ pub async fn list_access_points(
         if let Some(token) = &next_token {
             req = req.next_token(token);
         }
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| s3err(format!("ListAccessPoints: {e}")))?;
+        let resp = retry::send(|| {
+            req.clone()
+                .send()
+        })
+        .await
+        .map_err(|e| s3err(format!("ListAccessPoints: {e}")))?;
 
         for ap in resp.access_point_list() {
             let vpc_id = ap
@@ -3939,13 +4661,15 @@ pub async fn get_access_point(
     account_id: &str,
     name: &str,
 ) -> Result<S3AccessPointDetail, FmError> {
-    let resp = client
-        .get_access_point()
-        .account_id(account_id)
-        .name(name)
-        .send()
-        .await
-        .map_err(|e| s3err(format!("GetAccessPoint: {e}")))?;
+    let resp = retry::send(|| {
+        client
+            .get_access_point()
+            .account_id(account_id)
+            .name(name)
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(format!("GetAccessPoint: {e}")))?;
 
     let vpc_id = resp
         .vpc_configuration()
@@ -4013,7 +4737,7 @@ pub async fn create_access_point(
         req = req.public_access_block_configuration(pab_config);
     }
 
-    req.send()
+    retry::send(|| req.clone().send())
         .await
         .map_err(|e| s3err(format!("CreateAccessPoint: {e}")))?;
 
@@ -4025,13 +4749,15 @@ pub async fn delete_access_point(
     account_id: &str,
     name: &str,
 ) -> Result<(), FmError> {
-    client
-        .delete_access_point()
-        .account_id(account_id)
-        .name(name)
-        .send()
-        .await
-        .map_err(|e| s3err(format!("DeleteAccessPoint: {e}")))?;
+    retry::send(|| {
+        client
+            .delete_access_point()
+            .account_id(account_id)
+            .name(name)
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(format!("DeleteAccessPoint: {e}")))?;
     Ok(())
 }
 
@@ -4040,12 +4766,14 @@ pub async fn get_access_point_policy(
     account_id: &str,
     name: &str,
 ) -> Result<String, FmError> {
-    match client
-        .get_access_point_policy()
-        .account_id(account_id)
-        .name(name)
-        .send()
-        .await
+    match retry::send(|| {
+        client
+            .get_access_point_policy()
+            .account_id(account_id)
+            .name(name)
+            .send()
+    })
+    .await
     {
         Ok(resp) => Ok(resp.policy().unwrap_or_default().to_string()),
         Err(e) => {
@@ -4066,14 +4794,16 @@ pub async fn put_access_point_policy(
     name: &str,
     policy: &str,
 ) -> Result<(), FmError> {
-    client
-        .put_access_point_policy()
-        .account_id(account_id)
-        .name(name)
-        .policy(policy)
-        .send()
-        .await
-        .map_err(|e| s3err(format!("PutAccessPointPolicy: {e}")))?;
+    retry::send(|| {
+        client
+            .put_access_point_policy()
+            .account_id(account_id)
+            .name(name)
+            .policy(policy)
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(format!("PutAccessPointPolicy: {e}")))?;
     Ok(())
 }
 
@@ -4082,12 +4812,14 @@ pub async fn delete_access_point_policy(
     account_id: &str,
     name: &str,
 ) -> Result<(), FmError> {
-    client
-        .delete_access_point_policy()
-        .account_id(account_id)
-        .name(name)
-        .send()
-        .await
-        .map_err(|e| s3err(format!("DeleteAccessPointPolicy: {e}")))?;
+    retry::send(|| {
+        client
+            .delete_access_point_policy()
+            .account_id(account_id)
+            .name(name)
+            .send()
+    })
+    .await
+    .map_err(|e| s3err(format!("DeleteAccessPointPolicy: {e}")))?;
     Ok(())
 }
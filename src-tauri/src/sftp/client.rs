@@ -1,12 +1,18 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use russh::client;
+use russh::client::{self, KeyboardInteractiveAuthResponse};
+use russh_keys::agent::client::{AgentClient, AgentStream};
 use russh_keys::ssh_key;
 use russh_sftp::client::SftpSession;
+use tokio::sync::oneshot;
 
-use crate::models::FmError;
+use crate::models::{FmError, SftpAuthPrompt, SftpAuthPromptField, SftpHealthEvent};
 use super::helpers::sftperr;
+use super::known_hosts::{self, HostKeyStatus};
 
 // ── State ────────────────────────────────────────────────────────────────────
 
@@ -18,19 +24,89 @@ impl Default for SftpState {
     }
 }
 
+/// SFTP channels opened per connection so concurrent operations do not queue
+/// behind each other. Servers limiting sessions (`MaxSessions`) may get fewer.
+const POOL_SIZE: usize = 3;
+
 pub struct SftpConnection {
-    pub session: Arc<SftpSession>,
-    pub ssh_handle: client::Handle<SshHandler>,
+    /// Pool of SFTP channels on `ssh_handle`, handed out by `session()`.
+    pub sessions: Vec<Arc<SftpSession>>,
+    next_session: AtomicUsize,
+    pub ssh_handle: Arc<client::Handle<SshHandler>>,
     pub host: String,
     pub port: u16,
     pub username: String,
     pub home_dir: String,
+    /// Bastion sessions this connection tunnels through, outermost first.
+    /// Held so the forwarded channels stay open for the connection's lifetime.
+    pub jump_handles: Vec<Arc<client::Handle<SshHandler>>>,
+    /// Settings the connection was made with, kept in memory to reconnect.
+    pub target: SshTarget,
+    pub jump_hosts: Vec<SshTarget>,
+    pub health: Arc<ConnectionHealth>,
+}
+
+impl SftpConnection {
+    /// Next channel from the pool, round-robin.
+    pub fn session(&self) -> Arc<SftpSession> {
+        let i = self.next_session.fetch_add(1, Ordering::Relaxed);
+        self.sessions[i % self.sessions.len()].clone()
+    }
+
+    /// True once the SSH session (or a bastion it tunnels through) has dropped.
+    pub fn is_closed(&self) -> bool {
+        self.ssh_handle.is_closed() || self.jump_handles.iter().any(|h| h.is_closed())
+    }
+}
+
+/// Reconnect bookkeeping for a connection id, carried over when a dropped
+/// connection is replaced.
+pub struct ConnectionHealth {
+    /// Held while reconnecting so concurrent callers wait for one attempt.
+    pub reconnecting: tokio::sync::Mutex<()>,
+    notify: Box<dyn Fn(SftpHealthEvent) + Send + Sync>,
+}
+
+impl ConnectionHealth {
+    pub fn new(notify: impl Fn(SftpHealthEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            reconnecting: tokio::sync::Mutex::new(()),
+            notify: Box::new(notify),
+        }
+    }
+
+    pub fn notify(&self, event: SftpHealthEvent) {
+        (self.notify)(event);
+    }
+}
+
+impl Default for ConnectionHealth {
+    fn default() -> Self {
+        Self::new(|_| {})
+    }
 }
 
 // ── SSH Handler ──────────────────────────────────────────────────────────────
 
-/// Minimal SSH client handler — accepts all host keys (TODO: known_hosts).
-pub struct SshHandler;
+/// SSH client handler that verifies the server key against `~/.ssh/known_hosts`.
+pub struct SshHandler {
+    host: String,
+    port: u16,
+    /// Why the server key was rejected, read back after a failed connect.
+    rejection: Arc<Mutex<Option<FmError>>>,
+}
+
+impl SshHandler {
+    pub fn new(host: &str, port: u16) -> (Self, Arc<Mutex<Option<FmError>>>) {
+        let rejection = Arc::new(Mutex::new(None));
+        let handler = SshHandler {
+            host: host.to_string(),
+            port,
+            rejection: rejection.clone(),
+        };
+        (handler, rejection)
+    }
+}
 
 #[async_trait::async_trait]
 impl client::Handler for SshHandler {
@@ -38,85 +114,253 @@ impl client::Handler for SshHandler {
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &ssh_key::PublicKey,
+        server_public_key: &ssh_key::PublicKey,
     ) -> Result<bool, Self::Error> {
-        // Accept all host keys for now
-        // TODO: implement known_hosts verification
-        Ok(true)
+        let fingerprint = known_hosts::fingerprint(server_public_key);
+        let err = match known_hosts::verify(&self.host, self.port, server_public_key) {
+            HostKeyStatus::Trusted => return Ok(true),
+            HostKeyStatus::Unknown => {
+                known_hosts::remember_pending(&self.host, self.port, server_public_key);
+                FmError::UnknownHostKey {
+                    host: self.host.clone(),
+                    port: self.port,
+                    key_type: server_public_key.algorithm().to_string(),
+                    fingerprint,
+                }
+            }
+            HostKeyStatus::Mismatch => FmError::HostKeyRejected {
+                host: self.host.clone(),
+                port: self.port,
+                reason: "key changed".to_string(),
+                fingerprint,
+            },
+            HostKeyStatus::Revoked => FmError::HostKeyRejected {
+                host: self.host.clone(),
+                port: self.port,
+                reason: "key revoked".to_string(),
+                fingerprint,
+            },
+        };
+        if let Ok(mut slot) = self.rejection.lock() {
+            *slot = Some(err);
+        }
+        Ok(false)
     }
 }
 
 // ── Client Builder ──────────────────────────────────────────────────────────
 
-/// Establish an SSH connection and start the SFTP subsystem.
+/// Delivers keyboard-interactive challenges to the UI.
+pub type AuthPrompter = Arc<dyn Fn(SftpAuthPrompt) + Send + Sync>;
+
+/// Authentication settings for one SSH hop.
+#[derive(Clone, Default)]
+pub struct SshAuth {
+    pub method: String, // "password" | "key" | "agent" | "keyboard-interactive"
+    pub password: Option<String>,
+    pub key_path: Option<String>,
+    pub key_passphrase: Option<String>,
+    /// OpenSSH user certificate for `key_path`; defaults to `{key_path}-cert.pub`
+    /// when that file exists.
+    pub cert_path: Option<String>,
+    /// Only offer the agent identity matching `key_path` (ssh_config `IdentitiesOnly`).
+    pub identities_only: bool,
+    /// Required for "keyboard-interactive".
+    pub prompter: Option<AuthPrompter>,
+}
+
+/// Where to connect and as whom, after ssh_config resolution.
+#[derive(Clone, Default)]
+pub struct SshTarget {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: SshAuth,
+}
+
+/// Establish an SSH connection, tunnelling through `jump_hosts` in order
+/// (ProxyJump), and start the SFTP subsystem.
 pub async fn build_sftp_client(
-    host: &str,
-    port: u16,
-    username: &str,
-    auth_method: &str,
-    password: Option<&str>,
-    key_path: Option<&str>,
-    key_passphrase: Option<&str>,
+    target: &SshTarget,
+    jump_hosts: &[SshTarget],
 ) -> Result<SftpConnection, FmError> {
-    let config = client::Config {
+    let mut jump_handles: Vec<Arc<client::Handle<SshHandler>>> = Vec::new();
+    for hop in jump_hosts {
+        let handle = connect_ssh(hop, jump_handles.last()).await?;
+        jump_handles.push(Arc::new(handle));
+    }
+    let handle = connect_ssh(target, jump_handles.last()).await?;
+
+    let sftp = open_sftp(&handle).await?;
+
+    // Get the home directory
+    let home_dir = sftp
+        .canonicalize(".")
+        .await
+        .map_err(|e| sftperr(format!("Failed to resolve home dir: {e}")))?;
+
+    // The rest of the pool is best effort: one channel is enough to work.
+    let mut sessions = vec![Arc::new(sftp)];
+    for _ in 1..POOL_SIZE {
+        match open_sftp(&handle).await {
+            Ok(extra) => sessions.push(Arc::new(extra)),
+            Err(_) => break,
+        }
+    }
+
+    Ok(SftpConnection {
+        sessions,
+        next_session: AtomicUsize::new(0),
+        ssh_handle: Arc::new(handle),
+        host: target.host.clone(),
+        port: target.port,
+        username: target.username.clone(),
+        home_dir,
+        jump_handles,
+        target: target.clone(),
+        jump_hosts: jump_hosts.to_vec(),
+        health: Arc::new(ConnectionHealth::default()),
+    })
+}
+
+/// Open a session channel and start the SFTP subsystem on it.
+async fn open_sftp(handle: &client::Handle<SshHandler>) -> Result<SftpSession, FmError> {
+    let channel = handle
+        .channel_open_session()
+        .await
+        .map_err(|e| sftperr(format!("Channel open failed: {e}")))?;
+
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| sftperr(format!("SFTP subsystem request failed: {e}")))?;
+
+    SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| sftperr(format!("SFTP session init failed: {e}")))
+}
+
+/// Connect and authenticate one SSH hop, either directly or through a
+/// direct-tcpip channel opened on the previous hop.
+async fn connect_ssh(
+    target: &SshTarget,
+    via: Option<&Arc<client::Handle<SshHandler>>>,
+) -> Result<client::Handle<SshHandler>, FmError> {
+    let (host, port, username) = (target.host.as_str(), target.port, target.username.as_str());
+    let auth = &target.auth;
+
+    let config = Arc::new(client::Config {
         inactivity_timeout: Some(std::time::Duration::from_secs(300)),
         keepalive_interval: Some(std::time::Duration::from_secs(30)),
         keepalive_max: 3,
         ..Default::default()
-    };
+    });
 
-    let handler = SshHandler;
-    let mut handle = client::connect(Arc::new(config), (host, port), handler)
-        .await
-        .map_err(|e| sftperr(format!("SSH connect failed: {e}")))?;
+    let (handler, rejection) = SshHandler::new(host, port);
+    let connected = match via {
+        Some(jump) => {
+            let channel = jump
+                .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+                .await
+                .map_err(|e| sftperr(format!("Jump to {host}:{port} failed: {e}")))?;
+            client::connect_stream(config, channel.into_stream(), handler).await
+        }
+        None => client::connect(config, (host, port), handler).await,
+    };
+    let mut handle = match connected {
+        Ok(handle) => handle,
+        Err(e) => {
+            // Prefer the host key verdict over russh's generic "unknown key" error.
+            if let Some(err) = rejection.lock().ok().and_then(|mut r| r.take()) {
+                return Err(err);
+            }
+            return Err(sftperr(format!("SSH connect to {host}:{port} failed: {e}")));
+        }
+    };
 
     // Authenticate
-    let authenticated = match auth_method {
+    let authenticated = match auth.method.as_str() {
         "password" => {
-            let pw = password.ok_or_else(|| sftperr("Password required"))?;
+            let pw = auth.password.as_deref().ok_or_else(|| sftperr("Password required"))?;
             handle
                 .authenticate_password(username, pw)
                 .await
                 .map_err(|e| sftperr(format!("Password auth failed: {e}")))?
         }
         "key" => {
-            let path = key_path.ok_or_else(|| sftperr("Key path required"))?;
-            let key = if let Some(pp) = key_passphrase {
+            let path = auth.key_path.as_deref().ok_or_else(|| sftperr("Key path required"))?;
+            let key = if let Some(pp) = auth.key_passphrase.as_deref() {
                 russh_keys::load_secret_key(path, Some(pp))
                     .map_err(|e| sftperr(format!("Failed to load key: {e}")))?
             } else {
                 russh_keys::load_secret_key(path, None)
                     .map_err(|e| sftperr(format!("Failed to load key: {e}")))?
             };
+            let key = Arc::new(key);
 
-            let key_with_alg = russh_keys::key::PrivateKeyWithHashAlg::new(
-                Arc::new(key),
-                Some(russh_keys::HashAlg::Sha256),
-            )
-            .map_err(|e| sftperr(format!("Key hash alg failed: {e}")))?;
+            // Like OpenSSH, offer the certificate first and the bare key after.
+            let cert_path = auth
+                .cert_path
+                .clone()
+                .or_else(|| Some(format!("{path}-cert.pub")).filter(|p| Path::new(p).is_file()));
+            let mut authed = false;
+            if let Some(cert_path) = cert_path {
+                match russh_keys::load_openssh_certificate(&cert_path) {
+                    Ok(cert) => {
+                        match handle.authenticate_openssh_cert(username, key.clone(), cert).await {
+                            Ok(ok) => authed = ok,
+                            Err(e) => log::warn!("certificate auth with {cert_path} failed, trying the bare key: {e}"),
+                        }
+                    }
+                    Err(e) => log::warn!("cannot load certificate {cert_path}, trying the bare key: {e}"),
+                }
+            }
 
-            handle
-                .authenticate_publickey(username, key_with_alg)
-                .await
-                .map_err(|e| sftperr(format!("Key auth failed: {e}")))?
+            if !authed {
+                let key_with_alg = russh_keys::key::PrivateKeyWithHashAlg::new(
+                    key,
+                    Some(russh_keys::HashAlg::Sha256),
+                )
+                .map_err(|e| sftperr(format!("Key hash alg failed: {e}")))?;
+
+                authed = handle
+                    .authenticate_publickey(username, key_with_alg)
+                    .await
+                    .map_err(|e| sftperr(format!("Key auth failed: {e}")))?;
+            }
+            authed
         }
         "agent" => {
-            let mut agent = russh_keys::agent::client::AgentClient::connect_env()
+            let mut agent = AgentClient::connect_env()
                 .await
                 .map_err(|e| sftperr(format!("SSH agent connect failed: {e}")))?;
-            let identities = agent
+            let mut identities = agent
                 .request_identities()
                 .await
                 .map_err(|e| sftperr(format!("SSH agent identities failed: {e}")))?;
+            if auth.identities_only {
+                if let Some(path) = auth.key_path.as_deref() {
+                    let public = russh_keys::load_public_key(format!("{path}.pub"))
+                        .map_err(|e| sftperr(format!("Failed to load {path}.pub: {e}")))?;
+                    identities.retain(|id| id.key_data() == public.key_data());
+                }
+            }
             if identities.is_empty() {
                 return Err(sftperr("No keys found in SSH agent"));
             }
             let mut authed = false;
             for identity in identities {
-                match handle
-                    .authenticate_publickey_with(username, identity.clone(), &mut agent)
-                    .await
-                {
+                let result = if is_security_key(&identity) {
+                    let mut signer = SecurityKeySigner(&mut agent);
+                    handle
+                        .authenticate_publickey_with(username, identity.clone(), &mut signer)
+                        .await
+                } else {
+                    handle
+                        .authenticate_publickey_with(username, identity.clone(), &mut agent)
+                        .await
+                };
+                match result {
                     Ok(true) => {
                         authed = true;
                         break;
@@ -126,40 +370,171 @@ pub async fn build_sftp_client(
             }
             authed
         }
-        _ => return Err(sftperr(format!("Unknown auth method: {auth_method}"))),
+        "keyboard-interactive" => keyboard_interactive(&mut handle, target).await?,
+        other => return Err(sftperr(format!("Unknown auth method: {other}"))),
     };
 
     if !authenticated {
-        return Err(sftperr("Authentication failed"));
+        return Err(sftperr(format!("Authentication to {host} failed")));
     }
 
-    // Open a session channel and request the SFTP subsystem
-    let channel = handle
-        .channel_open_session()
-        .await
-        .map_err(|e| sftperr(format!("Channel open failed: {e}")))?;
+    Ok(handle)
+}
 
-    channel
-        .request_subsystem(true, "sftp")
-        .await
-        .map_err(|e| sftperr(format!("SFTP subsystem request failed: {e}")))?;
+// ── Security keys ───────────────────────────────────────────────────────────
 
-    let sftp = SftpSession::new(channel.into_stream())
-        .await
-        .map_err(|e| sftperr(format!("SFTP session init failed: {e}")))?;
+/// Whether `key` lives on a FIDO security key (`sk-ssh-ed25519`, `sk-ecdsa`).
+fn is_security_key(key: &ssh_key::PublicKey) -> bool {
+    matches!(
+        key.algorithm(),
+        ssh_key::Algorithm::SkEd25519 | ssh_key::Algorithm::SkEcdsaSha2NistP256
+    )
+}
 
-    // Get the home directory
-    let home_dir = sftp
-        .canonicalize(".")
+/// Signs with the agent like `AgentClient` does, but keeps the flags and
+/// counter that follow a security-key signature; `AgentClient` drops them,
+/// so servers reject its `sk-` signatures.
+struct SecurityKeySigner<'a, R: AgentStream>(&'a mut AgentClient<R>);
+
+#[async_trait::async_trait]
+impl<R> russh::Signer for SecurityKeySigner<'_, R>
+where
+    R: AgentStream + Unpin + Send + 'static,
+{
+    type Error = russh::AgentAuthError;
+
+    async fn auth_publickey_sign(
+        &mut self,
+        key: &ssh_key::PublicKey,
+        mut to_sign: russh::CryptoVec,
+    ) -> Result<russh::CryptoVec, Self::Error> {
+        let signature = self.0.sign_request_signature(key, &to_sign).await?;
+        let blob = signature_blob(&signature);
+        to_sign.extend(&(blob.len() as u32).to_be_bytes());
+        to_sign.extend(&blob);
+        Ok(to_sign)
+    }
+}
+
+/// SSH wire encoding of `signature`. Security-key signatures end with their
+/// flags byte and counter outside the signature string.
+fn signature_blob(signature: &ssh_key::Signature) -> Vec<u8> {
+    fn put_string(out: &mut Vec<u8>, bytes: &[u8]) {
+        out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        out.extend_from_slice(bytes);
+    }
+    let algorithm = signature.algorithm();
+    let data = signature.as_bytes();
+    let trailer = match algorithm {
+        ssh_key::Algorithm::SkEd25519 | ssh_key::Algorithm::SkEcdsaSha2NistP256 => 5,
+        _ => 0,
+    };
+    let (sig, rest) = data.split_at(data.len().saturating_sub(trailer));
+    let mut blob = Vec::new();
+    put_string(&mut blob, algorithm.as_str().as_bytes());
+    put_string(&mut blob, sig);
+    blob.extend_from_slice(rest);
+    blob
+}
+
+// ── Keyboard-interactive ────────────────────────────────────────────────────
+
+/// How long to wait for the user to answer a challenge.
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+static NEXT_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Request id and the sender that resumes the waiting login.
+type PendingPrompt = (String, oneshot::Sender<Option<Vec<String>>>);
+
+/// Challenges waiting for `answer_prompt`.
+static PENDING_PROMPTS: Mutex<Vec<PendingPrompt>> = Mutex::new(Vec::new());
+
+/// Run keyboard-interactive auth, forwarding each server challenge to the UI
+/// through the target's prompter and sending back the user's answers.
+async fn keyboard_interactive(
+    handle: &mut client::Handle<SshHandler>,
+    target: &SshTarget,
+) -> Result<bool, FmError> {
+    let prompter = target
+        .auth
+        .prompter
+        .as_ref()
+        .ok_or_else(|| sftperr("Keyboard-interactive auth needs a prompt channel"))?;
+    let auth_err = |e: russh::Error| sftperr(format!("Keyboard-interactive auth failed: {e}"));
+
+    let mut response = handle
+        .authenticate_keyboard_interactive_start(target.username.as_str(), None)
         .await
-        .map_err(|e| sftperr(format!("Failed to resolve home dir: {e}")))?;
+        .map_err(auth_err)?;
+    loop {
+        let (name, instructions, prompts) = match response {
+            KeyboardInteractiveAuthResponse::Success => return Ok(true),
+            KeyboardInteractiveAuthResponse::Failure => return Ok(false),
+            KeyboardInteractiveAuthResponse::InfoRequest {
+                name,
+                instructions,
+                prompts,
+            } => (name, instructions, prompts),
+        };
 
-    Ok(SftpConnection {
-        session: Arc::new(sftp),
-        ssh_handle: handle,
-        host: host.to_string(),
-        port,
-        username: username.to_string(),
-        home_dir,
-    })
+        // Servers may send empty rounds; they need an empty answer, not the user.
+        let answers = if prompts.is_empty() {
+            Vec::new()
+        } else {
+            let request_id = format!(
+                "{}:{}#{}",
+                target.host,
+                target.port,
+                NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed)
+            );
+            let (tx, rx) = oneshot::channel();
+            PENDING_PROMPTS
+                .lock()
+                .map_err(|e| sftperr(e.to_string()))?
+                .push((request_id.clone(), tx));
+            prompter(SftpAuthPrompt {
+                request_id: request_id.clone(),
+                host: target.host.clone(),
+                name,
+                instructions,
+                prompts: prompts
+                    .into_iter()
+                    .map(|p| SftpAuthPromptField {
+                        prompt: p.prompt,
+                        echo: p.echo,
+                    })
+                    .collect(),
+            });
+
+            let answer = tokio::time::timeout(PROMPT_TIMEOUT, rx).await;
+            if let Ok(mut pending) = PENDING_PROMPTS.lock() {
+                pending.retain(|(id, _)| *id != request_id);
+            }
+            match answer {
+                Ok(Ok(Some(answers))) => answers,
+                Ok(Ok(None)) | Ok(Err(_)) => return Err(sftperr("Authentication cancelled")),
+                Err(_) => return Err(sftperr("Timed out waiting for authentication input")),
+            }
+        };
+
+        response = handle
+            .authenticate_keyboard_interactive_respond(answers)
+            .await
+            .map_err(auth_err)?;
+    }
+}
+
+/// Deliver the user's answers to a pending challenge; None cancels the login.
+pub fn answer_prompt(request_id: &str, responses: Option<Vec<String>>) -> Result<(), FmError> {
+    let tx = {
+        let mut pending = PENDING_PROMPTS.lock().map_err(|e| sftperr(e.to_string()))?;
+        let idx = pending
+            .iter()
+            .position(|(id, _)| id == request_id)
+            .ok_or_else(|| sftperr(format!("No pending authentication prompt {request_id}")))?;
+        pending.remove(idx).1
+    };
+    tx.send(responses)
+        .map_err(|_| sftperr("Authentication attempt is no longer waiting"))
 }
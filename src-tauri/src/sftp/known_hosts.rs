@@ -0,0 +1,317 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use russh_keys::ssh_key::{HashAlg, PublicKey};
+use sha1::Sha1;
+
+use crate::models::FmError;
+
+use super::helpers::{sftperr, wildcard_match};
+
+// ── Verification ────────────────────────────────────────────────────────────
+
+/// Result of looking up a server key in known_hosts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostKeyStatus {
+    /// A matching entry exists.
+    Trusted,
+    /// No entry of this key type for the host (trust-on-first-use candidate).
+    Unknown,
+    /// The host is known with a different key of the same type.
+    Mismatch,
+    /// The key is listed under `@revoked`.
+    Revoked,
+}
+
+/// `~/.ssh/known_hosts`
+pub fn known_hosts_path() -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join(".ssh/known_hosts")
+}
+
+/// Check a server key against the user's known_hosts file.
+pub fn verify(host: &str, port: u16, key: &PublicKey) -> HostKeyStatus {
+    let contents = std::fs::read_to_string(known_hosts_path()).unwrap_or_default();
+    check_known_hosts(&contents, host, port, key)
+}
+
+/// Check a server key against known_hosts `contents`.
+///
+/// Supports plain and hashed (`|1|salt|hash`) host fields, `[host]:port`
+/// entries, `*`/`?` wildcards, `!` negation and the `@revoked` marker.
+/// `@cert-authority` lines are ignored.
+pub fn check_known_hosts(contents: &str, host: &str, port: u16, key: &PublicKey) -> HostKeyStatus {
+    let mut trusted = false;
+    let mut mismatch = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let Some(mut hosts) = fields.next() else {
+            continue;
+        };
+        let mut marker = None;
+        if hosts.starts_with('@') {
+            marker = Some(hosts);
+            hosts = match fields.next() {
+                Some(h) => h,
+                None => continue,
+            };
+        }
+        let (Some(key_type), Some(key_b64)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        if marker == Some("@cert-authority") || !hosts_match(hosts, host, port) {
+            continue;
+        }
+        let Ok(entry_key) = PublicKey::from_openssh(&format!("{key_type} {key_b64}")) else {
+            continue;
+        };
+        let same_key = entry_key.key_data() == key.key_data();
+
+        match marker {
+            Some("@revoked") if same_key => return HostKeyStatus::Revoked,
+            Some(_) => {}
+            None if same_key => trusted = true,
+            None if entry_key.algorithm() == key.algorithm() => mismatch = true,
+            None => {}
+        }
+    }
+
+    if trusted {
+        HostKeyStatus::Trusted
+    } else if mismatch {
+        HostKeyStatus::Mismatch
+    } else {
+        HostKeyStatus::Unknown
+    }
+}
+
+/// Host name as written in known_hosts: `host` for port 22, `[host]:port`
+/// otherwise. Lowercased like OpenSSH does, so hashed entries it wrote match.
+fn host_entry_name(host: &str, port: u16) -> String {
+    let host = host.to_ascii_lowercase();
+    if port == 22 {
+        host
+    } else {
+        format!("[{host}]:{port}")
+    }
+}
+
+/// Match a comma-separated host field. A matching negated pattern (`!pat`)
+/// rejects the line even if another pattern matches.
+fn hosts_match(field: &str, host: &str, port: u16) -> bool {
+    let name = host_entry_name(host, port);
+
+    if let Some(hashed) = field.strip_prefix("|1|") {
+        return hashed_match(hashed, &name);
+    }
+
+    let mut matched = false;
+    for pattern in field.split(',') {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, &name) {
+                return false;
+            }
+        } else if wildcard_match(pattern, &name) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// `salt|hash` where hash = HMAC-SHA1(salt, name), both base64.
+fn hashed_match(hashed: &str, name: &str) -> bool {
+    let Some((salt_b64, hash_b64)) = hashed.split_once('|') else {
+        return false;
+    };
+    let (Ok(salt), Ok(expected)) = (BASE64.decode(salt_b64), BASE64.decode(hash_b64)) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(name.as_bytes());
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// SHA256 fingerprint in OpenSSH format (`SHA256:…`).
+pub fn fingerprint(key: &PublicKey) -> String {
+    key.fingerprint(HashAlg::Sha256).to_string()
+}
+
+// ── Trust on first use ──────────────────────────────────────────────────────
+
+/// Keys presented by servers that were rejected as unknown, waiting for the
+/// user to accept them via `sftp_trust_host_key`.
+static PENDING_KEYS: Mutex<Vec<(String, u16, PublicKey)>> = Mutex::new(Vec::new());
+
+/// Remember an unknown key so it can be trusted later by fingerprint.
+pub fn remember_pending(host: &str, port: u16, key: &PublicKey) {
+    if let Ok(mut pending) = PENDING_KEYS.lock() {
+        pending.retain(|(h, p, _)| !(h == host && *p == port));
+        pending.push((host.to_string(), port, key.clone()));
+    }
+}
+
+/// Append the pending key for host:port to known_hosts, provided its
+/// fingerprint matches the one the user confirmed.
+pub fn trust_pending(host: &str, port: u16, expected_fingerprint: &str) -> Result<(), FmError> {
+    let key = {
+        let mut pending = PENDING_KEYS.lock().map_err(|e| sftperr(e.to_string()))?;
+        let idx = pending
+            .iter()
+            .position(|(h, p, k)| h == host && *p == port && fingerprint(k) == expected_fingerprint)
+            .ok_or_else(|| sftperr(format!("No pending host key {expected_fingerprint} for {host}:{port}")))?;
+        pending.remove(idx).2
+    };
+    append_known_host(host, port, &key)
+}
+
+/// Append `host key` to `~/.ssh/known_hosts`, creating the file if needed.
+pub fn append_known_host(host: &str, port: u16, key: &PublicKey) -> Result<(), FmError> {
+    let path = known_hosts_path();
+    if let Some(dir) = path.parent() {
+        if !dir.exists() {
+            std::fs::create_dir_all(dir)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+            }
+        }
+    }
+
+    // Strip any comment from the key; known_hosts lines are "host type base64".
+    let mut key = key.clone();
+    key.set_comment("");
+    let key_line = key
+        .to_openssh()
+        .map_err(|e| sftperr(format!("Failed to encode host key: {e}")))?;
+
+    // Make sure we start on a fresh line if the file lacks a trailing newline.
+    let needs_newline = std::fs::read(&path)
+        .map(|data| !data.is_empty() && !data.ends_with(b"\n"))
+        .unwrap_or(false);
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    writeln!(file, "{} {}", host_entry_name(host, port), key_line)?;
+    Ok(())
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+    const KEY_B: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAILM+rvN+ot98qgEN796jTiQfZfG1KaT0PtFDJ/XFSqti";
+
+    fn key(s: &str) -> PublicKey {
+        PublicKey::from_openssh(s).unwrap()
+    }
+
+    #[test]
+    fn test_plain_and_port_entries() {
+        let contents = format!("example.com {KEY_A}\n[example.com]:2222 {KEY_B}\n");
+        assert_eq!(
+            check_known_hosts(&contents, "example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        );
+        assert_eq!(
+            check_known_hosts(&contents, "example.com", 2222, &key(KEY_B)),
+            HostKeyStatus::Trusted
+        );
+        assert_eq!(
+            check_known_hosts(&contents, "example.com", 2222, &key(KEY_A)),
+            HostKeyStatus::Mismatch
+        );
+        assert_eq!(
+            check_known_hosts(&contents, "other.com", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn test_hashed_entry() {
+        let salt = [7u8; 20];
+        let mut mac = Hmac::<Sha1>::new_from_slice(&salt).unwrap();
+        mac.update(b"[10.0.0.5]:2200");
+        let hash = mac.finalize().into_bytes();
+        let contents = format!("|1|{}|{} {KEY_A}\n", BASE64.encode(salt), BASE64.encode(hash));
+
+        assert_eq!(
+            check_known_hosts(&contents, "10.0.0.5", 2200, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        );
+        assert_eq!(
+            check_known_hosts(&contents, "10.0.0.5", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn test_hashed_entry_ignores_host_case() {
+        let salt = [3u8; 20];
+        let mut mac = Hmac::<Sha1>::new_from_slice(&salt).unwrap();
+        mac.update(b"example.com");
+        let hash = mac.finalize().into_bytes();
+        let contents = format!("|1|{}|{} {KEY_A}\n", BASE64.encode(salt), BASE64.encode(hash));
+
+        assert_eq!(
+            check_known_hosts(&contents, "Example.COM", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        );
+        assert_eq!(host_entry_name("Example.COM", 2222), "[example.com]:2222");
+    }
+
+    #[test]
+    fn test_revoked_wins() {
+        let contents = format!("*.example.com {KEY_A}\n@revoked * {KEY_A}\n");
+        assert_eq!(
+            check_known_hosts(&contents, "db.example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Revoked
+        );
+    }
+
+    #[test]
+    fn test_wildcards_and_negation() {
+        let contents = format!("*.example.com,!secret.example.com {KEY_A}\n");
+        assert_eq!(
+            check_known_hosts(&contents, "WWW.Example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        );
+        assert_eq!(
+            check_known_hosts(&contents, "secret.example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        );
+        assert!(wildcard_match("host?", "host1"));
+        assert!(!wildcard_match("host?", "host12"));
+    }
+
+    #[test]
+    fn test_cert_authority_and_comments_ignored() {
+        let contents = format!("# comment\n@cert-authority * {KEY_A}\n\n");
+        assert_eq!(
+            check_known_hosts(&contents, "example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        );
+    }
+}
@@ -1,7 +1,9 @@
 pub mod client;
 pub mod helpers;
+pub mod known_hosts;
 pub mod service;
+pub mod ssh_config;
 
 pub use client::{SftpConnection, SftpState};
 pub use helpers::sftperr;
-pub use service::SftpService;
+pub use service::{ResumeMode, SftpService};
@@ -1,25 +1,81 @@
+use std::collections::HashMap;
+use std::io::SeekFrom;
 use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use russh_sftp::client::SftpSession;
-use russh_sftp::protocol::FileAttributes;
+use futures::future;
+use futures::stream::{self, FuturesUnordered, StreamExt, TryStreamExt};
+use russh::client;
+use russh::{ChannelMsg, Sig};
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::{RawSftpSession, SftpSession};
+use russh_sftp::protocol::{FileAttributes, OpenFlags, Packet, StatusCode, Version};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWriteExt};
 
-use crate::models::{DirListing, FileEntry, FmError, ProgressEvent, TransferCheckpoint};
+use crate::bandwidth::throttle;
+use crate::models::{
+    DirListing, ExecEvent, FileEntry, FmError, ProgressEvent, TransferCheckpoint,
+};
+use crate::storage::batch::{
+    check_stop, collect_local_files_recursive, parent_path, FileOutcome, TransferProgress,
+};
+use crate::storage::ChunkStream;
 
-use super::helpers::{sftp_path, sftperr};
+use super::client::SshHandler;
+use super::helpers::{sftp_path, sftperr, shell_quote};
+
+/// Bytes per SFTP read/write request. 32 KiB is the largest size every
+/// server is required to accept.
+const CHUNK_SIZE: u64 = 32 * 1024;
+
+/// Files transferred at once by `download`/`upload`.
+pub static CONCURRENT_FILES: AtomicUsize = AtomicUsize::new(4);
+
+/// Outstanding read/write requests per file.
+pub static REQUESTS_PER_FILE: AtomicUsize = AtomicUsize::new(64);
+
+/// How often a running `exec_stream` command checks for cancellation.
+const EXEC_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Bytes compared at the end of a partial file before resuming it.
+const VERIFY_WINDOW: u64 = 64 * 1024;
+
+/// How a transfer treats a destination file that already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeMode {
+    /// Overwrite from byte zero.
+    Restart,
+    /// Continue after the bytes already present; an existing file of the
+    /// same size counts as complete, a larger one is restarted.
+    Append,
+    /// Like `Append`, but first compare the tail of the existing bytes with
+    /// the source and restart on mismatch.
+    Verify,
+}
 
 // ── SftpService ──────────────────────────────────────────────────────────────
 
 pub struct SftpService {
     pub session: Arc<SftpSession>,
+    pub ssh_handle: Arc<client::Handle<SshHandler>>,
     pub host: String,
     pub port: u16,
 }
 
 impl SftpService {
-    pub fn new(session: Arc<SftpSession>, host: String, port: u16) -> Self {
-        Self { session, host, port }
+    pub fn new(
+        session: Arc<SftpSession>,
+        ssh_handle: Arc<client::Handle<SshHandler>>,
+        host: String,
+        port: u16,
+    ) -> Self {
+        Self {
+            session,
+            ssh_handle,
+            host,
+            port,
+        }
     }
 
     /// List directory contents, returning a DirListing with `..` entry.
@@ -197,14 +253,111 @@ impl SftpService {
             .map_err(|e| sftperr(format!("stat '{}': {}", clean, e)))
     }
 
-    /// Download remote files to a local destination directory.
+    /// Get metadata without following a trailing symlink.
+    pub async fn lstat(&self, path: &str) -> Result<FileAttributes, FmError> {
+        let clean = path.trim_end_matches('/');
+        self.session
+            .symlink_metadata(clean)
+            .await
+            .map_err(|e| sftperr(format!("lstat '{}': {}", clean, e)))
+    }
+
+    /// Set permission bits (e.g. 0o755) on a remote file or directory.
+    pub async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), FmError> {
+        let clean = path.trim_end_matches('/');
+        let attrs = FileAttributes {
+            permissions: Some(mode & 0o7777),
+            ..FileAttributes::empty()
+        };
+        self.session
+            .set_metadata(clean, attrs)
+            .await
+            .map_err(|e| sftperr(format!("chmod '{}': {}", clean, e)))
+    }
+
+    /// Change owner and/or group. SFTP v3 always sends both ids, so a missing
+    /// one is filled in from the file's current attributes.
+    pub async fn chown(&self, path: &str, uid: Option<u32>, gid: Option<u32>) -> Result<(), FmError> {
+        let clean = path.trim_end_matches('/');
+        let (uid, gid) = match (uid, gid) {
+            (Some(u), Some(g)) => (u, g),
+            (None, None) => return Ok(()),
+            _ => {
+                let current = self.stat(clean).await?;
+                match (uid.or(current.uid), gid.or(current.gid)) {
+                    (Some(u), Some(g)) => (u, g),
+                    _ => return Err(sftperr("chown: server did not report the current uid/gid")),
+                }
+            }
+        };
+        let attrs = FileAttributes {
+            uid: Some(uid),
+            gid: Some(gid),
+            ..FileAttributes::empty()
+        };
+        self.session
+            .set_metadata(clean, attrs)
+            .await
+            .map_err(|e| sftperr(format!("chown '{}': {}", clean, e)))
+    }
+
+    /// Create a symlink at `link_path` pointing to `target`.
+    pub async fn symlink(&self, link_path: &str, target: &str) -> Result<(), FmError> {
+        let link = link_path.trim_end_matches('/');
+        // OpenSSH's sftp-server takes SSH_FXP_SYMLINK arguments in reverse
+        // order (target first) and every mainstream client follows it.
+        self.session
+            .symlink(target, link)
+            .await
+            .map_err(|e| sftperr(format!("symlink '{}' → '{}': {}", link, target, e)))
+    }
+
+    /// Read the target of a symlink.
+    pub async fn read_link(&self, path: &str) -> Result<String, FmError> {
+        let clean = path.trim_end_matches('/');
+        self.session
+            .read_link(clean)
+            .await
+            .map_err(|e| sftperr(format!("readlink '{}': {}", clean, e)))
+    }
+
+    /// Resolve numeric uid/gid to names on the server via `getent` over an
+    /// exec channel. Falls back to the numbers when there is no shell or the
+    /// id is unknown.
+    pub async fn owner_names(&self, uid: Option<u32>, gid: Option<u32>) -> (String, String) {
+        let uid_str = uid.map(|u| u.to_string()).unwrap_or_default();
+        let gid_str = gid.map(|g| g.to_string()).unwrap_or_default();
+        if uid.is_none() && gid.is_none() {
+            return (uid_str, gid_str);
+        }
+
+        let command = format!(
+            "printf 'u:%s\\ng:%s\\n' \"$(getent passwd {} | cut -d: -f1)\" \"$(getent group {} | cut -d: -f1)\"",
+            uid.unwrap_or(u32::MAX),
+            gid.unwrap_or(u32::MAX)
+        );
+        let Ok((_, stdout, _)) = self.exec_capture(&command).await else {
+            return (uid_str, gid_str);
+        };
+        let (user, group) = parse_owner_names(&stdout);
+        (
+            user.filter(|_| uid.is_some()).unwrap_or(uid_str),
+            group.filter(|_| gid.is_some()).unwrap_or(gid_str),
+        )
+    }
+
+    /// Download remote files to a local destination directory, several files
+    /// at a time with pipelined reads. Returns None on success, Some(checkpoint)
+    /// on pause.
     pub async fn download(
         &self,
         remote_paths: &[String],
         local_dest: &str,
         op_id: &str,
         cancel: &AtomicBool,
+        pause: &AtomicBool,
         on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+        resume: ResumeMode,
     ) -> Result<Option<TransferCheckpoint>, FmError> {
         // First pass: collect all files and calculate total size
         let mut file_list: Vec<(String, String, u64)> = Vec::new(); // (remote_path, local_path, size)
@@ -222,46 +375,43 @@ impl SftpService {
             }
         }
 
-        let bytes_total: u64 = file_list.iter().map(|(_, _, s)| s).sum();
-        let files_total = file_list.len() as u32;
-        let mut bytes_done: u64 = 0;
-        let mut files_done: u32 = 0;
-
-        for (remote, local, _size) in &file_list {
-            if cancel.load(Ordering::Relaxed) {
-                return Err(FmError::Other("cancelled".into()));
-            }
-
-            // Ensure parent directory exists
+        // Ensure parent directories exist
+        for (_, local, _) in &file_list {
             if let Some(parent) = Path::new(local).parent() {
                 tokio::fs::create_dir_all(parent)
                     .await
                     .map_err(FmError::Io)?;
             }
+        }
 
-            // Download file
-            let data = self
-                .session
-                .read(remote)
-                .await
-                .map_err(|e| sftperr(format!("read '{}': {}", remote, e)))?;
-
-            tokio::fs::write(local, &data).await.map_err(FmError::Io)?;
-
-            bytes_done += data.len() as u64;
-            files_done += 1;
+        let (raw, _) = self.open_raw_session().await?;
+        let progress = TransferProgress::new(op_id, &file_list, |(_, _, s)| *s, on_progress);
+        let failed = AtomicBool::new(false);
+        let stop = StopFlags { cancel, pause, failed: &failed };
 
-            on_progress(ProgressEvent {
-                id: op_id.to_string(),
-                bytes_done,
-                bytes_total,
-                current_file: remote.rsplit('/').next().unwrap_or(remote).to_string(),
-                files_done,
-                files_total,
-            });
-        }
+        let tasks: Vec<_> = file_list
+            .iter()
+            .map(|(remote, local, size)| {
+                let (raw, stop, progress) = (&raw, &stop, &progress);
+                async move {
+                    let name = remote.rsplit('/').next().unwrap_or(remote);
+                    let result =
+                        download_file(raw, remote, local, *size, resume, stop, &|n| {
+                            progress.add_bytes(n, name)
+                        })
+                        .await;
+                    progress.finish_file(&result, name);
+                    stop.record(&result);
+                    (remote.clone(), result)
+                }
+            })
+            .collect();
 
-        Ok(None)
+        let results: Vec<(String, Result<FileOutcome, FmError>)> = stream::iter(tasks)
+            .buffer_unordered(concurrent_files())
+            .collect()
+            .await;
+        progress.outcome(results)
     }
 
     /// Recursively collect files for download.
@@ -293,14 +443,17 @@ impl SftpService {
         Ok(())
     }
 
-    /// Upload local files to a remote directory.
+    /// Upload local files to a remote directory, several files at a time with
+    /// pipelined writes. Returns None on success, Some(checkpoint) on pause.
     pub async fn upload(
         &self,
         local_paths: &[String],
         remote_dest: &str,
         op_id: &str,
         cancel: &AtomicBool,
+        pause: &AtomicBool,
         on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+        resume: ResumeMode,
     ) -> Result<Option<TransferCheckpoint>, FmError> {
         // Collect all local files
         let mut file_list: Vec<(std::path::PathBuf, String, u64)> = Vec::new();
@@ -324,50 +477,314 @@ impl SftpService {
             }
         }
 
-        let bytes_total: u64 = file_list.iter().map(|(_, _, s)| s).sum();
-        let files_total = file_list.len() as u32;
-        let mut bytes_done: u64 = 0;
-        let mut files_done: u32 = 0;
+        // Ensure remote parent directories exist
+        let mut parents: Vec<&str> = file_list
+            .iter()
+            .filter_map(|(_, remote, _)| remote.rsplit_once('/').map(|(p, _)| p))
+            .collect();
+        parents.sort_unstable();
+        parents.dedup();
+        for parent in parents {
+            self.ensure_remote_dir(parent).await?;
+        }
+
+        let (raw, _) = self.open_raw_session().await?;
+        let progress = TransferProgress::new(op_id, &file_list, |(_, _, s)| *s, on_progress);
+        let failed = AtomicBool::new(false);
+        let stop = StopFlags { cancel, pause, failed: &failed };
 
-        for (local, remote, _size) in &file_list {
-            if cancel.load(Ordering::Relaxed) {
-                return Err(FmError::Other("cancelled".into()));
-            }
+        let tasks: Vec<_> = file_list
+            .iter()
+            .map(|(local, remote, size)| {
+                let (raw, stop, progress) = (&raw, &stop, &progress);
+                async move {
+                    let name = local
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let result = upload_file(raw, local, remote, *size, resume, stop, &|n| {
+                        progress.add_bytes(n, &name)
+                    })
+                    .await;
+                    progress.finish_file(&result, &name);
+                    stop.record(&result);
+                    (local.to_string_lossy().into_owned(), result)
+                }
+            })
+            .collect();
+
+        let results: Vec<(String, Result<FileOutcome, FmError>)> = stream::iter(tasks)
+            .buffer_unordered(concurrent_files())
+            .collect()
+            .await;
+        progress.outcome(results)
+    }
+
+    /// Stream a remote file over a dedicated SFTP channel with several reads
+    /// in flight. The channel, and with it the open handle, is released when
+    /// the stream is dropped.
+    pub async fn read_stream(&self, path: &str) -> Result<ChunkStream<'static>, FmError> {
+        self.read_stream_from(path, 0).await
+    }
+
+    /// Like `read_stream`, starting at byte `offset`.
+    pub async fn read_stream_from(
+        &self,
+        path: &str,
+        offset: u64,
+    ) -> Result<ChunkStream<'static>, FmError> {
+        let size = self.stat(path).await?.size.unwrap_or(0);
+        let (raw, _) = self.open_raw_session().await?;
+        let handle = raw
+            .open(path, OpenFlags::READ, FileAttributes::default())
+            .await
+            .map_err(|e| sftperr(format!("open '{}': {}", path, e)))?
+            .handle;
+        let raw = Arc::new(raw);
+        let path = path.to_string();
 
-            // Ensure remote parent directory exists
-            if let Some(parent) = remote.rsplit_once('/').map(|(p, _)| p) {
+        Ok(stream::iter((offset..size).step_by(CHUNK_SIZE as usize))
+            .map(move |off| {
+                let (raw, handle, path) = (raw.clone(), handle.clone(), path.clone());
+                async move {
+                    read_remote(&raw, &handle, off, CHUNK_SIZE.min(size - off))
+                        .await
+                        .map_err(|e| sftperr(format!("read '{}': {}", path, e)))
+                }
+            })
+            .buffered(requests_per_file())
+            .try_take_while(|data| future::ready(Ok(!data.is_empty())))
+            .boxed())
+    }
+
+    /// Write a stream to a remote file over a dedicated SFTP channel with
+    /// several writes in flight, creating missing parent directories. The
+    /// partial file is removed if the stream or a write fails.
+    pub async fn write_stream(&self, path: &str, data: ChunkStream<'_>) -> Result<(), FmError> {
+        let result = self.write_stream_at(path, 0, data).await;
+        if result.is_err() {
+            let _ = self.session.remove_file(path).await;
+        }
+        result
+    }
+
+    /// Write a stream into a remote file from byte `offset` on; an offset of
+    /// 0 truncates the file. When a read or write fails, the writes still in
+    /// flight are completed and the file is cut back to its first failed
+    /// byte, so it always holds a contiguous prefix that can be continued
+    /// from its length.
+    pub async fn write_stream_at(
+        &self,
+        path: &str,
+        offset: u64,
+        mut data: ChunkStream<'_>,
+    ) -> Result<(), FmError> {
+        if let Some((parent, _)) = path.rsplit_once('/') {
+            if !parent.is_empty() {
                 self.ensure_remote_dir(parent).await?;
             }
+        }
+        let (raw, _) = self.open_raw_session().await?;
+        let write_err = |e: SftpError| sftperr(format!("write '{}': {}", path, e));
+        let flags = if offset > 0 {
+            OpenFlags::WRITE
+        } else {
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
+        };
+        let handle = raw
+            .open(path, flags, FileAttributes::default())
+            .await
+            .map_err(|e| sftperr(format!("open '{}': {}", path, e)))?
+            .handle;
+
+        let (raw, handle) = (&raw, handle.as_str());
+        let requests = requests_per_file();
+        let mut writes = FuturesUnordered::new();
+        let mut pos = offset;
+        let mut result = Ok(());
+        // Offset of the first write that failed, if any did.
+        let mut failed_at: Option<u64> = None;
+        'read: loop {
+            let chunk = match data.try_next().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            };
+            for piece in chunk.chunks(CHUNK_SIZE as usize) {
+                let (at, buf) = (pos, piece.to_vec());
+                pos += buf.len() as u64;
+                writes.push(async move { (at, raw.write(handle, at, buf).await) });
+                while writes.len() >= requests {
+                    if let Some((at, Err(e))) = writes.next().await {
+                        failed_at = Some(at);
+                        result = Err(write_err(e));
+                        break 'read;
+                    }
+                }
+            }
+        }
+        while let Some((at, written)) = writes.next().await {
+            if let Err(e) = written {
+                if failed_at.map_or(true, |first| at < first) {
+                    failed_at = Some(at);
+                }
+                if result.is_ok() {
+                    result = Err(write_err(e));
+                }
+            }
+        }
+        if let Some(at) = failed_at {
+            let attrs = FileAttributes { size: Some(at), ..FileAttributes::empty() };
+            if let Err(e) = raw.fsetstat(handle, attrs).await {
+                log::warn!("could not truncate '{}' to {} after a failed write: {}", path, at, e);
+            }
+        }
+
+        let _ = raw.close(handle).await;
+        result
+    }
+
+    /// Copy files and directories into `dest_dir` on the same server without
+    /// routing the data through this machine. Uses the `copy-data` extension
+    /// when the server advertises it, otherwise `cp -a` over an exec channel.
+    pub async fn copy(
+        &self,
+        paths: &[String],
+        dest_dir: &str,
+        op_id: &str,
+        cancel: &AtomicBool,
+        on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+    ) -> Result<(), FmError> {
+        let dest_dir = dest_dir.trim_end_matches('/');
+        let mut items = Vec::new();
+        // (source, index into `items` after its last entry)
+        let mut roots: Vec<(&str, usize)> = Vec::new();
+        for path in paths {
+            let src = path.trim_end_matches('/');
+            let name = src.rsplit('/').next().unwrap_or(src);
+            let dst = format!("{}/{}", dest_dir, name);
+            if dst == src {
+                return Err(FmError::AlreadyExists(dst));
+            }
+            if dest_dir == src || dest_dir.starts_with(&format!("{}/", src)) {
+                return Err(sftperr(format!("Cannot copy '{}' into itself", src)));
+            }
+            let attrs = self.lstat(src).await?;
+            Box::pin(self.collect_copy_items(src, &dst, attrs, &mut items)).await?;
+            roots.push((src, items.len()));
+        }
+
+        let files: Vec<&CopyItem> = items.iter().filter(|i| i.is_file()).collect();
+        let progress = TransferProgress::new(op_id, &files, |i| i.size(), on_progress);
+        let (raw, version) = self.open_raw_session().await?;
+
+        if !version.extensions.contains_key(COPY_DATA_EXT) {
+            let dest = if dest_dir.is_empty() { "/" } else { dest_dir };
+            let mut start = 0;
+            for (src, end) in roots {
+                let command = format!("cp -a -- {} {}", shell_quote(src), shell_quote(dest));
+                self.exec_checked(&command, cancel).await?;
+                let copied = &items[start..end];
+                let name = src.rsplit('/').next().unwrap_or(src);
+                progress.finish_files(
+                    copied.iter().filter(|i| i.is_file()).count() as u32,
+                    copied.iter().map(|i| i.size()).sum(),
+                    name,
+                );
+                start = end;
+            }
+            return Ok(());
+        }
 
-            let data = tokio::fs::read(local).await.map_err(FmError::Io)?;
-            let len = data.len() as u64;
+        for item in &items {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(FmError::Other("cancelled".into()));
+            }
+            let name = item.dst.rsplit('/').next().unwrap_or(&item.dst);
+            if item.attrs.is_dir() {
+                if !self.session.try_exists(&item.dst).await.unwrap_or(false) {
+                    self.create_folder(&item.dst).await?;
+                }
+            } else if item.attrs.is_symlink() {
+                let target = self.read_link(&item.src).await?;
+                let _ = self.session.remove_file(&item.dst).await;
+                self.symlink(&item.dst, &target).await?;
+            } else {
+                copy_data_file(&raw, item, cancel, &|n| progress.add_bytes(n, name)).await?;
+                progress.finish_file(&Ok(FileOutcome::Done), name);
+            }
+        }
 
-            self.session
-                .write(remote, &data)
+        // Directory modes and times go last, deepest first, so new entries do
+        // not bump mtimes and read-only directories stay writable meanwhile.
+        for item in items.iter().rev().filter(|i| i.attrs.is_dir()) {
+            raw.setstat(&item.dst, copied_attrs(&item.attrs))
                 .await
-                .map_err(|e| sftperr(format!("write '{}': {}", remote, e)))?;
-
-            bytes_done += len;
-            files_done += 1;
-
-            on_progress(ProgressEvent {
-                id: op_id.to_string(),
-                bytes_done,
-                bytes_total,
-                current_file: local
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_default(),
-                files_done,
-                files_total,
-            });
+                .map_err(|e| sftperr(format!("setstat '{}': {}", item.dst, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Recursively collect the entries to copy, directories before their
+    /// contents. Symlinks are not followed.
+    async fn collect_copy_items(
+        &self,
+        src: &str,
+        dst: &str,
+        attrs: FileAttributes,
+        out: &mut Vec<CopyItem>,
+    ) -> Result<(), FmError> {
+        let is_dir = attrs.is_dir();
+        out.push(CopyItem {
+            src: src.to_string(),
+            dst: dst.to_string(),
+            attrs,
+        });
+        if !is_dir {
+            return Ok(());
         }
 
-        Ok(None)
+        let entries = self
+            .session
+            .read_dir(src)
+            .await
+            .map_err(|e| sftperr(format!("readdir '{}': {}", src, e)))?;
+        for entry in entries {
+            let name = entry.file_name();
+            let src_child = format!("{}/{}", src, name);
+            let dst_child = format!("{}/{}", dst, name);
+            Box::pin(self.collect_copy_items(&src_child, &dst_child, entry.metadata(), out))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Open a dedicated SFTP channel for a transfer so its pipelined requests
+    /// do not queue behind browsing traffic on the shared session. Also
+    /// returns the server's version packet, which lists its extensions.
+    async fn open_raw_session(&self) -> Result<(RawSftpSession, Version), FmError> {
+        let channel = self
+            .ssh_handle
+            .channel_open_session()
+            .await
+            .map_err(|e| sftperr(format!("Channel open failed: {e}")))?;
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .map_err(|e| sftperr(format!("SFTP subsystem request failed: {e}")))?;
+        let raw = RawSftpSession::new(channel.into_stream());
+        let version = raw
+            .init()
+            .await
+            .map_err(|e| sftperr(format!("SFTP session init failed: {e}")))?;
+        Ok((raw, version))
     }
 
     /// Ensure a remote directory and all parents exist.
-    async fn ensure_remote_dir(&self, path: &str) -> Result<(), FmError> {
+    pub(crate) async fn ensure_remote_dir(&self, path: &str) -> Result<(), FmError> {
         if self.session.try_exists(path).await.unwrap_or(false) {
             return Ok(());
         }
@@ -382,6 +799,221 @@ impl SftpService {
         Ok(())
     }
 
+    /// Recursively list all files under `root`, returning
+    /// (relative_path, size, modified_ms).
+    pub async fn list_files_recursive(
+        &self,
+        root: &str,
+    ) -> Result<Vec<(String, u64, i64)>, FmError> {
+        let mut out = Vec::new();
+        Box::pin(self.walk_remote(root, "", &mut out)).await?;
+        Ok(out)
+    }
+
+    async fn walk_remote(
+        &self,
+        dir: &str,
+        rel_dir: &str,
+        out: &mut Vec<(String, u64, i64)>,
+    ) -> Result<(), FmError> {
+        let entries = self
+            .session
+            .read_dir(dir)
+            .await
+            .map_err(|e| sftperr(format!("readdir '{}': {}", dir, e)))?;
+
+        for entry in entries {
+            let name = entry.file_name();
+            let meta = entry.metadata();
+            let child = format!("{}/{}", dir.trim_end_matches('/'), name);
+            let rel = if rel_dir.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel_dir, name)
+            };
+
+            if meta.is_dir() {
+                Box::pin(self.walk_remote(&child, &rel, out)).await?;
+            } else {
+                let modified = meta.mtime.map(|t| t as i64 * 1000).unwrap_or(0);
+                out.push((rel, meta.size.unwrap_or(0), modified));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compute MD5 digests of all files under `root` on the server, returning
+    /// relative_path → hex digest. Uses `md5sum` (or BSD `md5 -r`) over an exec
+    /// channel, so it fails on SFTP-only accounts without a shell.
+    pub async fn remote_md5sums(&self, root: &str) -> Result<HashMap<String, String>, FmError> {
+        let command = format!(
+            "cd {} && if command -v md5sum >/dev/null 2>&1; \
+             then find . -type f -exec md5sum {{}} +; \
+             else find . -type f -exec md5 -r {{}} +; fi",
+            shell_quote(root)
+        );
+        let (status, stdout, stderr) = self.exec_capture(&command).await?;
+        if status != 0 && stdout.is_empty() {
+            return Err(sftperr(format!(
+                "remote checksum failed ({}): {}",
+                status,
+                stderr.trim()
+            )));
+        }
+
+        let mut result = HashMap::new();
+        for line in stdout.lines() {
+            // md5sum: "<hash>  ./path" (a leading '\' marks escaped names, skipped);
+            // md5 -r: "<hash> ./path"
+            let Some((hash, path)) = line.split_once(' ') else {
+                continue;
+            };
+            if hash.len() != 32 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                continue;
+            }
+            let path = path.trim_start_matches([' ', '*']);
+            let rel = path.strip_prefix("./").unwrap_or(path);
+            result.insert(rel.to_string(), hash.to_ascii_lowercase());
+        }
+        Ok(result)
+    }
+
+    /// Run a command over an SSH exec channel and collect its output.
+    /// Returns (exit_status, stdout, stderr).
+    pub async fn exec_capture(&self, command: &str) -> Result<(u32, String, String), FmError> {
+        let mut channel = self
+            .ssh_handle
+            .channel_open_session()
+            .await
+            .map_err(|e| sftperr(format!("Channel open failed: {e}")))?;
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| sftperr(format!("exec failed: {e}")))?;
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut exit_status = None;
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Data { ref data } => stdout.extend_from_slice(data),
+                ChannelMsg::ExtendedData { ref data, ext: 1 } => stderr.extend_from_slice(data),
+                ChannelMsg::ExitStatus { exit_status: code } => exit_status = Some(code),
+                _ => {}
+            }
+        }
+
+        let status = exit_status.ok_or_else(|| sftperr("Remote command exited without status"))?;
+        Ok((
+            status,
+            String::from_utf8_lossy(&stdout).into_owned(),
+            String::from_utf8_lossy(&stderr).into_owned(),
+        ))
+    }
+
+    /// Run a command over an SSH exec channel until it exits, failing with its
+    /// stderr on a non-zero status. Sends SIGTERM on cancel.
+    async fn exec_checked(&self, command: &str, cancel: &AtomicBool) -> Result<(), FmError> {
+        let stderr = std::sync::Mutex::new(String::new());
+        let status = std::sync::Mutex::new(None);
+        self.exec_stream(command, cancel, &|evt| match evt {
+            ExecEvent::Stderr { data } => {
+                if let Ok(mut s) = stderr.lock() {
+                    s.push_str(&data);
+                }
+            }
+            ExecEvent::Exit { code, .. } => {
+                if let Ok(mut s) = status.lock() {
+                    *s = code;
+                }
+            }
+            ExecEvent::Stdout { .. } => {}
+        })
+        .await?;
+
+        let status = status.into_inner().unwrap_or(None);
+        if status == Some(0) {
+            return Ok(());
+        }
+        let stderr = stderr.into_inner().unwrap_or_default();
+        let reason = match stderr.trim() {
+            "" => format!("exit status {}", status.map_or("unknown".into(), |c| c.to_string())),
+            msg => msg.to_string(),
+        };
+        Err(sftperr(format!("'{}' failed: {}", command, reason)))
+    }
+
+    /// Run a command over an SSH exec channel, streaming stdout/stderr as
+    /// they arrive and finishing with an Exit event. On cancel the remote
+    /// process is sent SIGTERM and the channel closed.
+    pub async fn exec_stream(
+        &self,
+        command: &str,
+        cancel: &AtomicBool,
+        on_event: &(dyn Fn(ExecEvent) + Send + Sync),
+    ) -> Result<(), FmError> {
+        let mut channel = self
+            .ssh_handle
+            .channel_open_session()
+            .await
+            .map_err(|e| sftperr(format!("Channel open failed: {e}")))?;
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| sftperr(format!("exec failed: {e}")))?;
+
+        let mut stdout = Utf8Chunks::default();
+        let mut stderr = Utf8Chunks::default();
+        let mut code = None;
+        let mut signal = None;
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                let _ = channel.signal(Sig::TERM).await;
+                let _ = channel.close().await;
+                return Err(FmError::Other("cancelled".into()));
+            }
+            // Wake up periodically to notice cancellation.
+            let msg = match tokio::time::timeout(EXEC_POLL_INTERVAL, channel.wait()).await {
+                Ok(Some(msg)) => msg,
+                Ok(None) => break,
+                Err(_) => continue,
+            };
+            match msg {
+                ChannelMsg::Data { ref data } => {
+                    let text = stdout.push(data);
+                    if !text.is_empty() {
+                        on_event(ExecEvent::Stdout { data: text });
+                    }
+                }
+                ChannelMsg::ExtendedData { ref data, ext: 1 } => {
+                    let text = stderr.push(data);
+                    if !text.is_empty() {
+                        on_event(ExecEvent::Stderr { data: text });
+                    }
+                }
+                ChannelMsg::ExitStatus { exit_status } => code = Some(exit_status),
+                ChannelMsg::ExitSignal { signal_name, .. } => {
+                    signal = Some(match signal_name {
+                        Sig::Custom(name) => name,
+                        other => format!("{other:?}"),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let rest = stdout.finish();
+        if !rest.is_empty() {
+            on_event(ExecEvent::Stdout { data: rest });
+        }
+        let rest = stderr.finish();
+        if !rest.is_empty() {
+            on_event(ExecEvent::Stderr { data: rest });
+        }
+        on_event(ExecEvent::Exit { code, signal });
+        Ok(())
+    }
+
     /// Download a remote file to a temp location, returning the local path.
     pub async fn download_temp(&self, remote_path: &str) -> Result<String, FmError> {
         let name = remote_path.rsplit('/').next().unwrap_or("file");
@@ -412,34 +1044,422 @@ impl SftpService {
     }
 }
 
-// ── Helpers ──────────────────────────────────────────────────────────────────
+// ── Transfer pipeline ────────────────────────────────────────────────────────
+
+/// Cancel/pause flags of the operation, plus a flag raised when any file of
+/// the batch fails so the others stop early.
+struct StopFlags<'a> {
+    cancel: &'a AtomicBool,
+    pause: &'a AtomicBool,
+    failed: &'a AtomicBool,
+}
 
-fn parent_path(p: &str) -> String {
-    let clean = p.trim_end_matches('/');
-    match clean.rsplit_once('/') {
-        Some(("", _)) => "/".to_string(),
-        Some((parent, _)) => parent.to_string(),
-        None => "/".to_string(),
+impl StopFlags<'_> {
+    /// Err when cancelled or another file failed, Ok(true) when paused.
+    fn check(&self) -> Result<bool, FmError> {
+        if self.failed.load(Ordering::Relaxed) {
+            return Err(FmError::Other("cancelled".into()));
+        }
+        check_stop(self.cancel, self.pause)
+    }
+
+    fn record(&self, result: &Result<FileOutcome, FmError>) {
+        if result.is_err() {
+            self.failed.store(true, Ordering::Relaxed);
+        }
     }
 }
 
-fn collect_local_files_recursive(
-    dir: &Path,
-    prefix: &str,
-    out: &mut Vec<(std::path::PathBuf, String, u64)>,
-) -> Result<(), FmError> {
-    for entry in std::fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let name = entry.file_name().to_string_lossy().to_string();
-        let remote = format!("{}/{}", prefix, name);
-
-        if path.is_dir() {
-            collect_local_files_recursive(&path, &remote, out)?;
+fn concurrent_files() -> usize {
+    CONCURRENT_FILES.load(Ordering::Relaxed).max(1)
+}
+
+fn requests_per_file() -> usize {
+    REQUESTS_PER_FILE.load(Ordering::Relaxed).max(1)
+}
+
+/// Copy one remote file to `local` with several reads in flight, continuing
+/// after an existing partial file when `resume` allows.
+async fn download_file(
+    raw: &RawSftpSession,
+    remote: &str,
+    local: &str,
+    size: u64,
+    resume: ResumeMode,
+    stop: &StopFlags<'_>,
+    on_bytes: &(dyn Fn(u64) + Send + Sync),
+) -> Result<FileOutcome, FmError> {
+    if stop.check()? {
+        return Ok(FileOutcome::Paused);
+    }
+    let read_err = |e: SftpError| sftperr(format!("read '{}': {}", remote, e));
+    let handle = raw
+        .open(remote, OpenFlags::READ, FileAttributes::default())
+        .await
+        .map_err(|e| sftperr(format!("open '{}': {}", remote, e)))?
+        .handle;
+
+    let result = async {
+        let existing = match resume {
+            ResumeMode::Restart => 0,
+            _ => tokio::fs::metadata(local).await.map(|m| m.len()).unwrap_or(0),
+        };
+        let mut offset = if existing > size { 0 } else { existing };
+        if offset > 0 && resume == ResumeMode::Verify {
+            let mut local_file = tokio::fs::File::open(local).await?;
+            let ours = read_tail(&mut local_file, offset).await?;
+            let len = offset.min(VERIFY_WINDOW);
+            let theirs = read_remote(raw, &handle, offset - len, len)
+                .await
+                .map_err(read_err)?;
+            if ours != theirs {
+                offset = 0;
+            }
+        }
+
+        let mut local_file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(offset == 0)
+            .open(local)
+            .await?;
+        if offset > 0 {
+            local_file.set_len(offset).await?;
+            local_file.seek(SeekFrom::Start(offset)).await?;
+            on_bytes(offset);
+        }
+
+        // Reads are issued ahead but yielded in order, so the local file
+        // always holds a contiguous prefix and can be resumed after a pause.
+        let handle = handle.as_str();
+        let mut reads = stream::iter((offset..size).step_by(CHUNK_SIZE as usize))
+            .map(|off| read_remote(raw, handle, off, CHUNK_SIZE.min(size - off)))
+            .buffered(requests_per_file());
+        while let Some(data) = reads.next().await {
+            let data = data.map_err(read_err)?;
+            if data.is_empty() {
+                break;
+            }
+            local_file.write_all(&data).await?;
+            throttle(data.len() as u64).await;
+            on_bytes(data.len() as u64);
+            if stop.check()? {
+                local_file.flush().await?;
+                return Ok(FileOutcome::Paused);
+            }
+        }
+        local_file.flush().await?;
+        Ok(FileOutcome::Done)
+    }
+    .await;
+
+    let _ = raw.close(handle.as_str()).await;
+    result
+}
+
+/// Copy one local file to `remote` with several writes in flight, continuing
+/// after an existing partial remote file when `resume` allows.
+async fn upload_file(
+    raw: &RawSftpSession,
+    local: &Path,
+    remote: &str,
+    size: u64,
+    resume: ResumeMode,
+    stop: &StopFlags<'_>,
+    on_bytes: &(dyn Fn(u64) + Send + Sync),
+) -> Result<FileOutcome, FmError> {
+    if stop.check()? {
+        return Ok(FileOutcome::Paused);
+    }
+    let open_err = |e: SftpError| sftperr(format!("open '{}': {}", remote, e));
+    let write_err = |e: SftpError| sftperr(format!("write '{}': {}", remote, e));
+    let mut local_file = tokio::fs::File::open(local).await?;
+
+    let existing = match resume {
+        ResumeMode::Restart => 0,
+        _ => match raw.stat(remote).await {
+            Ok(stat) if !stat.attrs.is_dir() => stat.attrs.size.unwrap_or(0),
+            _ => 0,
+        },
+    };
+    let mut offset = if existing > size { 0 } else { existing };
+    if offset > 0 && resume == ResumeMode::Verify {
+        let handle = raw
+            .open(remote, OpenFlags::READ, FileAttributes::default())
+            .await
+            .map_err(open_err)?
+            .handle;
+        let len = offset.min(VERIFY_WINDOW);
+        let theirs = read_remote(raw, &handle, offset - len, len).await;
+        let _ = raw.close(handle).await;
+        let theirs = theirs.map_err(|e| sftperr(format!("read '{}': {}", remote, e)))?;
+        if read_tail(&mut local_file, offset).await? != theirs {
+            offset = 0;
+        }
+    }
+
+    let flags = if offset > 0 {
+        OpenFlags::WRITE
+    } else {
+        OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
+    };
+    let handle = raw
+        .open(remote, flags, FileAttributes::default())
+        .await
+        .map_err(open_err)?
+        .handle;
+
+    let result = async {
+        local_file.seek(SeekFrom::Start(offset)).await?;
+        if offset > 0 {
+            on_bytes(offset);
+        }
+
+        // Writes carry explicit offsets and may complete out of order; all of
+        // them are drained before returning so a paused file has no holes.
+        let handle = handle.as_str();
+        let requests = requests_per_file();
+        let mut writes = FuturesUnordered::new();
+        let mut pos = offset;
+        let mut outcome = FileOutcome::Done;
+        loop {
+            if stop.check()? {
+                outcome = FileOutcome::Paused;
+                break;
+            }
+            let mut buf = vec![0u8; CHUNK_SIZE as usize];
+            let n = fill_buf(&mut local_file, &mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            buf.truncate(n);
+            throttle(n as u64).await;
+            let at = pos;
+            pos += n as u64;
+            writes.push(async move { raw.write(handle, at, buf).await.map(|_| n as u64) });
+            while writes.len() >= requests {
+                if let Some(written) = writes.next().await {
+                    on_bytes(written.map_err(write_err)?);
+                }
+            }
+        }
+        while let Some(written) = writes.next().await {
+            on_bytes(written.map_err(write_err)?);
+        }
+        Ok(outcome)
+    }
+    .await;
+
+    let _ = raw.close(handle.as_str()).await;
+    result
+}
+
+/// Read `len` bytes at `offset`, re-requesting after short reads. Returns
+/// fewer bytes only at end of file.
+async fn read_remote(
+    raw: &RawSftpSession,
+    handle: &str,
+    offset: u64,
+    len: u64,
+) -> Result<Vec<u8>, SftpError> {
+    let mut buf = Vec::with_capacity(len as usize);
+    while (buf.len() as u64) < len {
+        let pos = offset + buf.len() as u64;
+        match raw.read(handle, pos, (len - buf.len() as u64) as u32).await {
+            Ok(data) if data.data.is_empty() => break,
+            Ok(data) => buf.extend_from_slice(&data.data),
+            Err(SftpError::Status(status)) if status.status_code == StatusCode::Eof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(buf)
+}
+
+/// Read until `buf` is full or the file ends, returning the byte count.
+async fn fill_buf(file: &mut tokio::fs::File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file.read(&mut buf[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+// ── Server-side copy ─────────────────────────────────────────────────────────
+
+/// SFTP extension that copies a byte range between two open handles.
+const COPY_DATA_EXT: &str = "copy-data";
+
+/// Bytes per `copy-data` request; progress and cancellation are checked
+/// between requests.
+const COPY_DATA_CHUNK: u64 = 8 * 1024 * 1024;
+
+/// One entry of a server-side copy with its source attributes (lstat).
+struct CopyItem {
+    src: String,
+    dst: String,
+    attrs: FileAttributes,
+}
+
+impl CopyItem {
+    fn is_file(&self) -> bool {
+        !self.attrs.is_dir() && !self.attrs.is_symlink()
+    }
+
+    fn size(&self) -> u64 {
+        if self.is_file() {
+            self.attrs.size.unwrap_or(0)
         } else {
-            let size = path.metadata().map(|m| m.len()).unwrap_or(0);
-            out.push((path, remote, size));
+            0
+        }
+    }
+}
+
+/// Copy one file with `copy-data` requests, then apply its mode and times.
+async fn copy_data_file(
+    raw: &RawSftpSession,
+    item: &CopyItem,
+    cancel: &AtomicBool,
+    on_bytes: &(dyn Fn(u64) + Send + Sync),
+) -> Result<(), FmError> {
+    let src = raw
+        .open(&item.src, OpenFlags::READ, FileAttributes::empty())
+        .await
+        .map_err(|e| sftperr(format!("open '{}': {}", item.src, e)))?
+        .handle;
+    let dst = match raw
+        .open(
+            &item.dst,
+            OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE,
+            FileAttributes::empty(),
+        )
+        .await
+    {
+        Ok(handle) => handle.handle,
+        Err(e) => {
+            let _ = raw.close(src).await;
+            return Err(sftperr(format!("open '{}': {}", item.dst, e)));
+        }
+    };
+
+    let size = item.size();
+    let mut offset = 0;
+    let mut result = Ok(());
+    while offset < size {
+        if cancel.load(Ordering::Relaxed) {
+            result = Err(FmError::Other("cancelled".into()));
+            break;
+        }
+        let len = COPY_DATA_CHUNK.min(size - offset);
+        let request = copy_data_request(&src, offset, len, &dst);
+        result = match raw.extended(COPY_DATA_EXT, request).await {
+            Ok(Packet::Status(status)) if status.status_code == StatusCode::Ok => Ok(()),
+            Ok(Packet::Status(status)) => Err(sftperr(format!(
+                "copy '{}': {}",
+                item.src, status.error_message
+            ))),
+            Ok(_) => Err(sftperr(format!("copy '{}': unexpected reply", item.src))),
+            Err(e) => Err(sftperr(format!("copy '{}': {}", item.src, e))),
+        };
+        if result.is_err() {
+            break;
         }
+        offset += len;
+        on_bytes(len);
     }
+
+    let _ = raw.close(src).await;
+    let _ = raw.close(dst).await;
+    result?;
+    raw.setstat(&item.dst, copied_attrs(&item.attrs))
+        .await
+        .map_err(|e| sftperr(format!("setstat '{}': {}", item.dst, e)))?;
     Ok(())
 }
+
+/// `copy-data` payload: read handle, read offset, length, write handle,
+/// write offset (same as the read offset).
+fn copy_data_request(read_handle: &str, offset: u64, len: u64, write_handle: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(32 + read_handle.len() + write_handle.len());
+    data.extend_from_slice(&(read_handle.len() as u32).to_be_bytes());
+    data.extend_from_slice(read_handle.as_bytes());
+    data.extend_from_slice(&offset.to_be_bytes());
+    data.extend_from_slice(&len.to_be_bytes());
+    data.extend_from_slice(&(write_handle.len() as u32).to_be_bytes());
+    data.extend_from_slice(write_handle.as_bytes());
+    data.extend_from_slice(&offset.to_be_bytes());
+    data
+}
+
+/// Mode and times of a copied entry, as `cp -a` preserves them.
+fn copied_attrs(src: &FileAttributes) -> FileAttributes {
+    FileAttributes {
+        permissions: src.permissions.map(|p| p & 0o7777),
+        atime: src.atime,
+        mtime: src.mtime,
+        ..FileAttributes::empty()
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Decodes a byte stream as UTF-8 across chunk boundaries, holding back an
+/// incomplete trailing sequence until the next chunk arrives.
+#[derive(Default)]
+struct Utf8Chunks {
+    pending: Vec<u8>,
+}
+
+impl Utf8Chunks {
+    fn push(&mut self, data: &[u8]) -> String {
+        self.pending.extend_from_slice(data);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // error_len() is None only for a truncated sequence at the end.
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let rest = self.pending.split_off(valid);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+
+    fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+/// The last `VERIFY_WINDOW` bytes before `end`, used to check that a partial
+/// destination file holds the same data as the source before resuming it.
+async fn read_tail<R>(file: &mut R, end: u64) -> std::io::Result<Vec<u8>>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let len = end.min(VERIFY_WINDOW);
+    file.seek(SeekFrom::Start(end - len)).await?;
+    let mut buf = vec![0u8; len as usize];
+    file.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Parse `owner_names` output ("u:<user>\ng:<group>\n", either name possibly
+/// empty) into the two names.
+fn parse_owner_names(stdout: &str) -> (Option<String>, Option<String>) {
+    let mut user = None;
+    let mut group = None;
+    for line in stdout.lines() {
+        let name = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        if let Some(u) = line.strip_prefix("u:") {
+            user = name(u);
+        } else if let Some(g) = line.strip_prefix("g:") {
+            group = name(g);
+        }
+    }
+    (user, group)
+}
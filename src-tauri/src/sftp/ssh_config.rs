@@ -0,0 +1,501 @@
+use std::path::{Path, PathBuf};
+
+use crate::models::SshConfigHost;
+
+use super::helpers::wildcard_match;
+
+/// Nesting limit for `Include`, matching OpenSSH.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+// ── Parsing ─────────────────────────────────────────────────────────────────
+
+/// One `Host` block (or the implicit global block before the first `Host`).
+#[derive(Debug, Clone, Default)]
+pub struct HostBlock {
+    /// Host patterns; a `Match` block has none and never applies.
+    pub patterns: Vec<String>,
+    /// (lowercased keyword, value) in file order.
+    pub options: Vec<(String, String)>,
+}
+
+impl HostBlock {
+    fn global() -> Self {
+        HostBlock {
+            patterns: vec!["*".to_string()],
+            options: Vec::new(),
+        }
+    }
+
+    /// Whether `alias` matches this block. A matching negated pattern (`!pat`)
+    /// excludes the host even if another pattern matches.
+    pub fn matches(&self, alias: &str) -> bool {
+        let mut matched = false;
+        for pattern in &self.patterns {
+            if let Some(negated) = pattern.strip_prefix('!') {
+                if wildcard_match(negated, alias) {
+                    return false;
+                }
+            } else if wildcard_match(pattern, alias) {
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+/// `~/.ssh/config`
+pub fn config_path() -> PathBuf {
+    ssh_dir().join("config")
+}
+
+fn ssh_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join(".ssh")
+}
+
+/// Read and parse the user's ssh config, following `Include` directives.
+/// A missing file yields no blocks.
+pub fn load() -> Vec<HostBlock> {
+    let contents = std::fs::read_to_string(config_path()).unwrap_or_default();
+    parse_config(&contents)
+}
+
+/// Parse ssh config `contents` into host blocks, reading `Include`d files
+/// from disk.
+pub fn parse_config(contents: &str) -> Vec<HostBlock> {
+    let mut blocks = vec![HostBlock::global()];
+    parse_into(contents, 0, &mut blocks);
+    blocks
+}
+
+fn parse_into(contents: &str, depth: usize, blocks: &mut Vec<HostBlock>) {
+    for line in contents.lines() {
+        let Some((keyword, value)) = split_line(line) else {
+            continue;
+        };
+
+        match keyword.as_str() {
+            "host" => blocks.push(HostBlock {
+                patterns: split_args(value),
+                options: Vec::new(),
+            }),
+            // Match criteria are not evaluated; skip the whole block.
+            "match" => blocks.push(HostBlock::default()),
+            "include" => {
+                if depth >= MAX_INCLUDE_DEPTH {
+                    continue;
+                }
+                // Included lines before their first Host belong to the current
+                // block; afterwards the current block continues.
+                let current = blocks.last().map(|b| b.patterns.clone()).unwrap_or_default();
+                for pattern in split_args(value) {
+                    for path in include_paths(&pattern) {
+                        if let Ok(included) = std::fs::read_to_string(&path) {
+                            parse_into(&included, depth + 1, blocks);
+                        }
+                    }
+                }
+                if blocks.last().map(|b| &b.patterns) != Some(&current) {
+                    blocks.push(HostBlock {
+                        patterns: current,
+                        options: Vec::new(),
+                    });
+                }
+            }
+            _ => {
+                if let Some(block) = blocks.last_mut() {
+                    block.options.push((keyword, unquote(value).to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// Split a config line into a lowercased keyword and its value. Keyword and
+/// value may be separated by whitespace and/or a single `=`.
+fn split_line(line: &str) -> Option<(String, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    if keyword.is_empty() || rest.is_empty() {
+        return None;
+    }
+    Some((keyword.to_ascii_lowercase(), rest))
+}
+
+/// Split a multi-valued argument on whitespace, honouring double quotes.
+fn split_args(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Files named by an `Include` argument. Relative paths are taken from
+/// `~/.ssh`; `*` and `?` are allowed in the file name.
+fn include_paths(pattern: &str) -> Vec<PathBuf> {
+    let expanded = expand_tilde(pattern);
+    let path = if Path::new(&expanded).is_absolute() {
+        PathBuf::from(expanded)
+    } else {
+        ssh_dir().join(expanded)
+    };
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| wildcard_match(&name, &e.file_name().to_string_lossy()))
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+fn expand_tilde(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .unwrap_or_default()
+            .join(rest)
+            .to_string_lossy()
+            .to_string(),
+        None if path == "~" => dirs::home_dir().unwrap_or_default().to_string_lossy().to_string(),
+        None => path.to_string(),
+    }
+}
+
+// ── Resolution ──────────────────────────────────────────────────────────────
+
+/// Effective settings for a host alias. Unset fields fall back to the
+/// caller's values or SSH defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostConfig {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// In config order, `~` and `%` tokens expanded.
+    pub identity_files: Vec<String>,
+    /// `CertificateFile` entries, expanded like `identity_files`.
+    pub certificate_files: Vec<String>,
+    pub identities_only: bool,
+    /// Raw `ProxyJump` value (`[user@]host[:port]`, comma-separated).
+    pub proxy_jump: Option<String>,
+}
+
+impl HostConfig {
+    /// The host name to actually connect to.
+    pub fn effective_host(&self, alias: &str) -> String {
+        self.host_name.clone().unwrap_or_else(|| alias.to_string())
+    }
+
+    /// First configured identity file that exists on disk.
+    pub fn existing_identity(&self) -> Option<String> {
+        self.identity_files
+            .iter()
+            .find(|p| Path::new(p).is_file())
+            .cloned()
+    }
+
+    /// First configured certificate file that exists on disk.
+    pub fn existing_certificate(&self) -> Option<String> {
+        self.certificate_files
+            .iter()
+            .find(|p| Path::new(p).is_file())
+            .cloned()
+    }
+}
+
+/// Resolve `alias` against the user's ssh config.
+pub fn resolve(alias: &str) -> HostConfig {
+    resolve_in(&load(), alias)
+}
+
+/// Resolve `alias` against parsed blocks. As in OpenSSH the first value
+/// obtained for each option wins, except `IdentityFile` and `CertificateFile`
+/// which accumulate.
+pub fn resolve_in(blocks: &[HostBlock], alias: &str) -> HostConfig {
+    let mut cfg = HostConfig::default();
+    let mut identities_only = None;
+    let mut proxy_jump = None;
+    let mut raw_identities = Vec::new();
+    let mut raw_certificates = Vec::new();
+
+    for block in blocks.iter().filter(|b| b.matches(alias)) {
+        for (keyword, value) in &block.options {
+            match keyword.as_str() {
+                "hostname" if cfg.host_name.is_none() => cfg.host_name = Some(value.clone()),
+                "user" if cfg.user.is_none() => cfg.user = Some(value.clone()),
+                "port" if cfg.port.is_none() => cfg.port = value.parse().ok(),
+                "identityfile" => raw_identities.push(value.clone()),
+                "certificatefile" => raw_certificates.push(value.clone()),
+                "identitiesonly" if identities_only.is_none() => {
+                    identities_only = Some(value.eq_ignore_ascii_case("yes"));
+                }
+                "proxyjump" if proxy_jump.is_none() => proxy_jump = Some(value.clone()),
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(host_name) = cfg.host_name.take() {
+        cfg.host_name = Some(expand_tokens(&host_name, alias, &cfg));
+    }
+    cfg.identity_files = raw_identities
+        .iter()
+        .filter(|p| !p.eq_ignore_ascii_case("none"))
+        .map(|p| expand_tilde(&expand_tokens(p, alias, &cfg)))
+        .collect();
+    cfg.certificate_files = raw_certificates
+        .iter()
+        .filter(|p| !p.eq_ignore_ascii_case("none"))
+        .map(|p| expand_tilde(&expand_tokens(p, alias, &cfg)))
+        .collect();
+    cfg.identities_only = identities_only.unwrap_or(false);
+    cfg.proxy_jump = proxy_jump.filter(|p| !p.eq_ignore_ascii_case("none"));
+    cfg
+}
+
+/// Expand `%h`, `%n`, `%p`, `%r`, `%u`, `%d` and `%%`.
+fn expand_tokens(value: &str, alias: &str, cfg: &HostConfig) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('h') => out.push_str(cfg.host_name.as_deref().unwrap_or(alias)),
+            Some('n') => out.push_str(alias),
+            Some('p') => out.push_str(&cfg.port.unwrap_or(22).to_string()),
+            Some('r') => out.push_str(cfg.user.as_deref().unwrap_or(&local_user())),
+            Some('u') => out.push_str(&local_user()),
+            Some('d') => out.push_str(&dirs::home_dir().unwrap_or_default().to_string_lossy()),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// One hop of a `ProxyJump` list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpSpec {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// Parse a `ProxyJump` value: comma-separated `[user@]host[:port]` or
+/// `ssh://[user@]host[:port]`, outermost hop first. IPv6 hosts use `[addr]:port`.
+pub fn parse_proxy_jump(value: &str) -> Vec<JumpSpec> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(|hop| {
+            let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+            let (user, host_port) = match hop.rsplit_once('@') {
+                Some((user, rest)) => (Some(user.to_string()), rest),
+                None => (None, hop),
+            };
+            let (host, port) = if let Some(bracketed) = host_port.strip_prefix('[') {
+                match bracketed.split_once(']') {
+                    Some((host, rest)) => (host, rest.strip_prefix(':').and_then(|p| p.parse().ok())),
+                    None => (bracketed, None),
+                }
+            } else {
+                match host_port.rsplit_once(':') {
+                    Some((host, port)) if !host.contains(':') => (host, port.parse().ok()),
+                    _ => (host_port, None),
+                }
+            };
+            JumpSpec {
+                user,
+                host: host.to_string(),
+                port,
+            }
+        })
+        .collect()
+}
+
+/// Name of the local user, used when the config sets no `User`.
+pub fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+// ── Listing ─────────────────────────────────────────────────────────────────
+
+/// Concrete (non-wildcard, non-negated) host aliases with their resolved
+/// settings, in config order.
+pub fn list_hosts() -> Vec<SshConfigHost> {
+    list_hosts_in(&load())
+}
+
+pub fn list_hosts_in(blocks: &[HostBlock]) -> Vec<SshConfigHost> {
+    let mut aliases: Vec<&str> = Vec::new();
+    for pattern in blocks.iter().flat_map(|b| b.patterns.iter()) {
+        if pattern.contains(['*', '?', '!']) || aliases.contains(&pattern.as_str()) {
+            continue;
+        }
+        aliases.push(pattern);
+    }
+
+    aliases
+        .into_iter()
+        .map(|alias| {
+            let cfg = resolve_in(blocks, alias);
+            SshConfigHost {
+                alias: alias.to_string(),
+                host_name: cfg.effective_host(alias),
+                user: cfg.user,
+                port: cfg.port,
+                identity_file: cfg.identity_files.into_iter().next(),
+                proxy_jump: cfg.proxy_jump,
+            }
+        })
+        .collect()
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Defaults
+IdentitiesOnly no
+
+Host web prod-*
+    HostName %h.example.com
+    User deploy
+    Port=2222
+    IdentityFile "/keys/%r@%h"
+
+Host prod-db !prod-web
+    ProxyJump bastion
+    IdentitiesOnly yes
+    CertificateFile /keys/%r-cert.pub
+
+Match host web
+    User ignored
+
+Host *
+    User fallback
+    Port 22
+    IdentityFile /keys/default
+"#;
+
+    #[test]
+    fn test_first_value_wins() {
+        let blocks = parse_config(CONFIG);
+        let cfg = resolve_in(&blocks, "web");
+        assert_eq!(cfg.host_name.as_deref(), Some("web.example.com"));
+        assert_eq!(cfg.user.as_deref(), Some("deploy"));
+        assert_eq!(cfg.port, Some(2222));
+        assert!(!cfg.identities_only);
+        assert_eq!(cfg.proxy_jump, None);
+    }
+
+    #[test]
+    fn test_identity_files_accumulate_and_expand() {
+        let blocks = parse_config(CONFIG);
+        let cfg = resolve_in(&blocks, "prod-db");
+        assert_eq!(
+            cfg.identity_files,
+            vec!["/keys/deploy@prod-db.example.com".to_string(), "/keys/default".to_string()]
+        );
+        assert_eq!(cfg.certificate_files, vec!["/keys/deploy-cert.pub".to_string()]);
+        assert_eq!(cfg.proxy_jump.as_deref(), Some("bastion"));
+    }
+
+    #[test]
+    fn test_negated_pattern() {
+        let blocks = parse_config(CONFIG);
+        let cfg = resolve_in(&blocks, "prod-web");
+        assert_eq!(cfg.proxy_jump, None);
+        // The global IdentitiesOnly applies before any Host block.
+        assert!(!cfg.identities_only);
+    }
+
+    #[test]
+    fn test_unknown_host_uses_wildcard_block() {
+        let blocks = parse_config(CONFIG);
+        let cfg = resolve_in(&blocks, "other");
+        assert_eq!(cfg.host_name, None);
+        assert_eq!(cfg.effective_host("other"), "other");
+        assert_eq!(cfg.user.as_deref(), Some("fallback"));
+        assert_eq!(cfg.port, Some(22));
+    }
+
+    #[test]
+    fn test_list_hosts_skips_patterns() {
+        let blocks = parse_config(CONFIG);
+        let hosts = list_hosts_in(&blocks);
+        let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["web", "prod-db"]);
+        assert_eq!(hosts[1].host_name, "prod-db.example.com");
+        assert_eq!(hosts[1].proxy_jump.as_deref(), Some("bastion"));
+    }
+
+    #[test]
+    fn test_parse_proxy_jump() {
+        let hops = parse_proxy_jump("admin@gw.example.com:2200, ssh://inner,[fd00::1]:22");
+        assert_eq!(
+            hops,
+            vec![
+                JumpSpec { user: Some("admin".into()), host: "gw.example.com".into(), port: Some(2200) },
+                JumpSpec { user: None, host: "inner".into(), port: None },
+                JumpSpec { user: None, host: "fd00::1".into(), port: Some(22) },
+            ]
+        );
+    }
+}
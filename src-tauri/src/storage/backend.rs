@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+
+use crate::models::FmError;
+
+/// File contents as a stream of chunks. Producers keep only a few chunks in
+/// memory, so a stream can carry a file of any size.
+pub type ChunkStream<'a> = BoxStream<'a, Result<Vec<u8>, FmError>>;
+
+/// A file or directory as seen through `StorageBackend`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageEntry {
+    pub name: String,
+    /// Backend-native path, see `StorageBackend`.
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Epoch milliseconds, 0 if unknown.
+    pub modified: i64,
+}
+
+/// Operations shared by every storage backend, so data can move between any
+/// two of them without a protocol-specific code path.
+///
+/// Paths are backend-native: an absolute path for local and SFTP, a key for
+/// S3 (directories are prefixes ending in `/`). No `s3://` or `sftp://`
+/// prefix.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Path of the entry `name` inside the directory `dir`.
+    fn join(&self, dir: &str, name: &str) -> String {
+        format!("{}/{}", dir.trim_end_matches('/'), name)
+    }
+
+    /// Entries of a directory, without `.` and `..`.
+    async fn list(&self, path: &str) -> Result<Vec<StorageEntry>, FmError>;
+
+    /// Metadata of a single file or directory.
+    async fn stat(&self, path: &str) -> Result<StorageEntry, FmError>;
+
+    /// Stream the contents of a file.
+    async fn read_stream(&self, path: &str) -> Result<ChunkStream<'static>, FmError> {
+        self.read_stream_from(path, 0).await
+    }
+
+    /// Stream the contents of a file from byte `offset` on.
+    async fn read_stream_from(&self, path: &str, offset: u64) -> Result<ChunkStream<'static>, FmError>;
+
+    /// Create or replace the file `path` with the contents of `data`,
+    /// creating missing parent directories. `size` is the expected length
+    /// and only used to plan the write. Nothing is left behind on error.
+    async fn write_stream(&self, path: &str, size: u64, data: ChunkStream<'_>) -> Result<(), FmError>;
+
+    /// Delete a file, or a directory with everything in it.
+    async fn delete(&self, path: &str) -> Result<(), FmError>;
+
+    /// Move `from` to the full path `to` on the same backend.
+    async fn rename(&self, from: &str, to: &str) -> Result<(), FmError>;
+
+    /// Create a directory and any missing parents. An existing directory is
+    /// not an error.
+    async fn mkdir(&self, path: &str) -> Result<(), FmError>;
+}
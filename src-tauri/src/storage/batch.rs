@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+use crate::models::{FmError, ProgressEvent, TransferCheckpoint};
+
+// ── Per-file transfers ──────────────────────────────────────────────────────
+
+/// Result of transferring one file.
+pub(crate) enum FileOutcome {
+    Done,
+    Paused,
+}
+
+/// Err when cancelled, Ok(true) when paused.
+pub(crate) fn check_stop(cancel: &AtomicBool, pause: &AtomicBool) -> Result<bool, FmError> {
+    if cancel.load(Ordering::Relaxed) {
+        return Err(FmError::Other("cancelled".into()));
+    }
+    Ok(pause.load(Ordering::Relaxed))
+}
+
+// ── Progress ────────────────────────────────────────────────────────────────
+
+/// Progress of a batch of files, transferred one after another or
+/// concurrently.
+pub(crate) struct TransferProgress<'a> {
+    op_id: &'a str,
+    bytes_done: AtomicU64,
+    bytes_total: u64,
+    files_done: AtomicU32,
+    files_total: u32,
+    on_progress: &'a (dyn Fn(ProgressEvent) + Send + Sync),
+}
+
+impl<'a> TransferProgress<'a> {
+    pub(crate) fn new<T>(
+        op_id: &'a str,
+        files: &[T],
+        size: impl Fn(&T) -> u64,
+        on_progress: &'a (dyn Fn(ProgressEvent) + Send + Sync),
+    ) -> Self {
+        Self {
+            op_id,
+            bytes_done: AtomicU64::new(0),
+            bytes_total: files.iter().map(size).sum(),
+            files_done: AtomicU32::new(0),
+            files_total: files.len() as u32,
+            on_progress,
+        }
+    }
+
+    pub(crate) fn add_bytes(&self, n: u64, current_file: &str) {
+        let bytes_done = self.bytes_done.fetch_add(n, Ordering::Relaxed) + n;
+        self.emit(bytes_done, self.files_done.load(Ordering::Relaxed), current_file);
+    }
+
+    pub(crate) fn finish_file(&self, result: &Result<FileOutcome, FmError>, current_file: &str) {
+        if let Ok(FileOutcome::Done) = result {
+            let files_done = self.files_done.fetch_add(1, Ordering::Relaxed) + 1;
+            self.emit(self.bytes_done.load(Ordering::Relaxed), files_done, current_file);
+        }
+    }
+
+    /// Count a batch of files copied in one step (no per-byte progress).
+    pub(crate) fn finish_files(&self, files: u32, bytes: u64, current_file: &str) {
+        let bytes_done = self.bytes_done.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let files_done = self.files_done.fetch_add(files, Ordering::Relaxed) + files;
+        self.emit(bytes_done, files_done, current_file);
+    }
+
+    fn emit(&self, bytes_done: u64, files_done: u32, current_file: &str) {
+        (self.on_progress)(ProgressEvent {
+            id: self.op_id.to_string(),
+            bytes_done,
+            bytes_total: self.bytes_total,
+            current_file: current_file.to_string(),
+            files_done,
+            files_total: self.files_total,
+            retries: 0,
+        });
+    }
+
+    /// Fold per-file results (in completion order) into the transfer result:
+    /// the first error, else a checkpoint if any file paused, else None.
+    pub(crate) fn outcome(
+        &self,
+        results: Vec<(String, Result<FileOutcome, FmError>)>,
+    ) -> Result<Option<TransferCheckpoint>, FmError> {
+        let mut completed_files = Vec::new();
+        let mut paused = false;
+        for (name, result) in results {
+            match result? {
+                FileOutcome::Done => completed_files.push(name),
+                FileOutcome::Paused => paused = true,
+            }
+        }
+        if !paused {
+            return Ok(None);
+        }
+        Ok(Some(TransferCheckpoint {
+            files_done: completed_files.len() as u32,
+            files_completed: completed_files,
+            bytes_done: self.bytes_done.load(Ordering::Relaxed),
+            bytes_total: self.bytes_total,
+            files_total: self.files_total,
+        }))
+    }
+}
+
+// ── Paths ───────────────────────────────────────────────────────────────────
+
+/// Files under the local directory `dir` as (local path, remote path under
+/// `prefix`, size).
+pub(crate) fn collect_local_files_recursive(
+    dir: &Path,
+    prefix: &str,
+    out: &mut Vec<(PathBuf, String, u64)>,
+) -> Result<(), FmError> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let remote = format!("{}/{}", prefix, name);
+
+        if path.is_dir() {
+            collect_local_files_recursive(&path, &remote, out)?;
+        } else {
+            let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+            out.push((path, remote, size));
+        }
+    }
+    Ok(())
+}
+
+/// `name` inside the remote directory `dir`.
+pub(crate) fn join(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// Parent of a remote path; `/` for top-level entries.
+pub(crate) fn parent_path(p: &str) -> String {
+    let clean = p.trim_end_matches('/');
+    match clean.rsplit_once('/') {
+        Some(("", _)) => "/".to_string(),
+        Some((parent, _)) => parent.to_string(),
+        None => "/".to_string(),
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::models::FmError;
+
+use super::backend::{ChunkStream, StorageBackend, StorageEntry};
+
+/// Bytes per chunk when reading local files.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// The local filesystem.
+pub struct LocalStorage;
+
+fn entry_from_meta(path: &Path, meta: &std::fs::Metadata) -> StorageEntry {
+    StorageEntry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().into_owned(),
+        is_dir: meta.is_dir(),
+        size: if meta.is_dir() { 0 } else { meta.len() },
+        modified: meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0),
+    }
+}
+
+fn not_found(path: &str, e: std::io::Error) -> FmError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        FmError::NotFound(path.to_string())
+    } else {
+        FmError::Io(e)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    async fn list(&self, path: &str) -> Result<Vec<StorageEntry>, FmError> {
+        let mut dir = tokio::fs::read_dir(path).await.map_err(|e| not_found(path, e))?;
+        let mut entries = Vec::new();
+        while let Some(item) = dir.next_entry().await? {
+            let child = item.path();
+            // Follow symlinks; skip dangling ones.
+            if let Ok(meta) = tokio::fs::metadata(&child).await {
+                entries.push(entry_from_meta(&child, &meta));
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn stat(&self, path: &str) -> Result<StorageEntry, FmError> {
+        let meta = tokio::fs::metadata(path).await.map_err(|e| not_found(path, e))?;
+        Ok(entry_from_meta(Path::new(path), &meta))
+    }
+
+    async fn read_stream_from(&self, path: &str, offset: u64) -> Result<ChunkStream<'static>, FmError> {
+        let mut file = tokio::fs::File::open(path).await.map_err(|e| not_found(path, e))?;
+        if offset > 0 {
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+        }
+        Ok(stream::try_unfold(file, |mut file| async move {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            buf.truncate(n);
+            Ok(Some((buf, file)))
+        })
+        .boxed())
+    }
+
+    async fn write_stream(&self, path: &str, _size: u64, mut data: ChunkStream<'_>) -> Result<(), FmError> {
+        if let Some(parent) = Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(path).await?;
+        let result = async {
+            while let Some(chunk) = data.try_next().await? {
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+            Ok(())
+        }
+        .await;
+        if result.is_err() {
+            drop(file);
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        result
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), FmError> {
+        let meta = tokio::fs::symlink_metadata(path)
+            .await
+            .map_err(|e| not_found(path, e))?;
+        if meta.is_dir() {
+            tokio::fs::remove_dir_all(path).await?;
+        } else {
+            tokio::fs::remove_file(path).await?;
+        }
+        Ok(())
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), FmError> {
+        tokio::fs::rename(from, to).await.map_err(|e| not_found(from, e))
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), FmError> {
+        tokio::fs::create_dir_all(path).await?;
+        Ok(())
+    }
+}
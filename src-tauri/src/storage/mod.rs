@@ -0,0 +1,12 @@
+pub mod backend;
+pub(crate) mod batch;
+pub mod local;
+pub mod s3;
+pub mod s3_sftp;
+pub mod sftp;
+pub mod transfer;
+
+pub use backend::{ChunkStream, StorageBackend, StorageEntry};
+pub use local::LocalStorage;
+pub use s3_sftp::{s3_to_sftp, sftp_to_s3, upload_to_s3};
+pub use transfer::transfer;
@@ -0,0 +1,142 @@
+use async_trait::async_trait;
+
+use crate::models::FmError;
+use crate::s3::{copy_single_or_multipart, list_all_objects, retry, s3err, strip_s3_prefix, S3Service};
+
+use super::backend::{ChunkStream, StorageBackend, StorageEntry};
+
+/// A key as a directory prefix: with trailing slash, "" for the bucket root.
+fn dir_prefix(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", trimmed)
+    }
+}
+
+fn dir_entry(prefix: &str) -> StorageEntry {
+    StorageEntry {
+        name: prefix
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or("")
+            .to_string(),
+        path: prefix.to_string(),
+        is_dir: true,
+        size: 0,
+        modified: 0,
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Service {
+    fn join(&self, dir: &str, name: &str) -> String {
+        format!("{}{}", dir_prefix(dir), name)
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<StorageEntry>, FmError> {
+        let listing = self.list_objects(&dir_prefix(path)).await?;
+        Ok(listing
+            .entries
+            .into_iter()
+            .filter(|e| e.name != "..")
+            .map(|e| StorageEntry {
+                path: strip_s3_prefix(&e.path, &self.bucket),
+                name: e.name,
+                is_dir: e.is_dir,
+                size: e.size,
+                modified: e.modified,
+            })
+            .collect())
+    }
+
+    async fn stat(&self, path: &str) -> Result<StorageEntry, FmError> {
+        if path.is_empty() || path.ends_with('/') {
+            return Ok(dir_entry(path));
+        }
+        match retry::send(|| self.client.head_object().bucket(&self.bucket).key(path).send()).await {
+            Ok(head) => Ok(StorageEntry {
+                name: path.rsplit('/').next().unwrap_or(path).to_string(),
+                path: path.to_string(),
+                is_dir: false,
+                size: head.content_length().unwrap_or(0) as u64,
+                modified: head
+                    .last_modified()
+                    .and_then(|t| t.to_millis().ok())
+                    .unwrap_or(0),
+            }),
+            Err(e) if e.as_service_error().is_some_and(|s| s.is_not_found()) => {
+                // No object by that name; it may still be a prefix.
+                let prefix = dir_prefix(path);
+                let check = retry::send(|| {
+                    self.client
+                        .list_objects_v2()
+                        .bucket(&self.bucket)
+                        .prefix(&prefix)
+                        .max_keys(1)
+                        .send()
+                })
+                .await
+                .map_err(|e| s3err(e.to_string()))?;
+                if check.contents().is_empty() && check.common_prefixes().is_empty() {
+                    return Err(FmError::NotFound(path.to_string()));
+                }
+                Ok(dir_entry(&prefix))
+            }
+            Err(e) => Err(s3err(e.to_string())),
+        }
+    }
+
+    async fn read_stream_from(&self, path: &str, offset: u64) -> Result<ChunkStream<'static>, FmError> {
+        S3Service::read_stream_from(self, path, offset).await
+    }
+
+    async fn write_stream(&self, path: &str, size: u64, data: ChunkStream<'_>) -> Result<(), FmError> {
+        S3Service::write_stream(self, path, size, data).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), FmError> {
+        self.delete_objects(&[path.to_string()]).await
+    }
+
+    /// Copy to the new key(s) server-side, then delete the originals.
+    async fn rename(&self, from: &str, to: &str) -> Result<(), FmError> {
+        let moves: Vec<(String, String, u64)> = if from.ends_with('/') {
+            let to_prefix = dir_prefix(to);
+            list_all_objects(&self.client, &self.bucket, from)
+                .await?
+                .into_iter()
+                .map(|(key, size, _)| {
+                    let dest = format!("{}{}", to_prefix, &key[from.len()..]);
+                    (key, dest, size)
+                })
+                .collect()
+        } else {
+            let size = self.stat(from).await?.size;
+            vec![(from.to_string(), to.to_string(), size)]
+        };
+
+        for (src, dest, size) in &moves {
+            copy_single_or_multipart(
+                &self.client,
+                &self.bucket,
+                src,
+                &self.client,
+                &self.bucket,
+                dest,
+                *size,
+            )
+            .await?;
+        }
+        self.delete_objects(&[from.to_string()]).await
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), FmError> {
+        match self.create_folder(path).await {
+            Ok(()) | Err(FmError::AlreadyExists(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
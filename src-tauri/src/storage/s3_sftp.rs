@@ -0,0 +1,328 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::models::{
+    FmError, ProgressEvent, S3CompletedPart, S3UploadCheckpoint, TransferCheckpoint,
+};
+use crate::s3::{complete_multipart, part_size_for, retry, S3Service, MULTIPART_THRESHOLD};
+use crate::sftp::SftpService;
+
+use super::backend::StorageBackend;
+use super::transfer::{plan_items, tracked};
+
+/// Stream S3 objects (keys, or prefixes ending in `/`) into the SFTP
+/// directory `dest_dir`, feeding each `get_object` body straight into
+/// pipelined SFTP writes. Memory use is a few chunks per file.
+///
+/// With `resume`, an existing remote file shorter than its object is
+/// continued with a ranged GET from its length, and one of the same size is
+/// taken as complete. Returns None on success, Some(checkpoint) on pause;
+/// `files_completed` holds keys. A paused file keeps its partial data.
+pub async fn s3_to_sftp(
+    s3: &S3Service,
+    keys: &[String],
+    sftp: &SftpService,
+    dest_dir: &str,
+    resume: bool,
+    op_id: &str,
+    cancel: &AtomicBool,
+    pause: &AtomicBool,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let (items, empty_dirs) = plan_items(s3, keys, sftp, dest_dir).await?;
+    for dir in &empty_dirs {
+        StorageBackend::mkdir(sftp, dir).await?;
+    }
+
+    let files_total = items.len() as u32;
+    let bytes_total: u64 = items.iter().map(|item| item.size).sum();
+    let mut bytes_done: u64 = 0;
+    let mut completed_files: Vec<String> = Vec::new();
+    let checkpoint = |completed_files: Vec<String>, bytes_done: u64| {
+        Some(TransferCheckpoint {
+            files_done: completed_files.len() as u32,
+            files_completed: completed_files,
+            bytes_done,
+            bytes_total,
+            files_total,
+        })
+    };
+
+    for (i, item) in items.iter().enumerate() {
+        let files_done = i as u32;
+        if cancel.load(Ordering::Relaxed) {
+            return Err(FmError::Other("cancelled".into()));
+        }
+        if pause.load(Ordering::Relaxed) {
+            return Ok(checkpoint(completed_files, bytes_done));
+        }
+
+        let existing = if resume {
+            match SftpService::stat(sftp, &item.dst).await {
+                Ok(attrs) if !attrs.is_dir() => attrs.size.unwrap_or(0),
+                _ => 0,
+            }
+        } else {
+            0
+        };
+        // A larger remote file is not ours to continue; start over.
+        let offset = if existing <= item.size { existing } else { 0 };
+        let base = bytes_done;
+        let file_bytes = AtomicU64::new(offset);
+
+        if offset < item.size || item.size == 0 {
+            let report = |done: u64| {
+                on_progress(ProgressEvent {
+                    id: op_id.to_string(),
+                    bytes_done: base + done,
+                    bytes_total,
+                    current_file: item.name().to_string(),
+                    files_done,
+                    files_total,
+                    retries: retry::retries(),
+                })
+            };
+            let reader = s3.read_stream_from(&item.src, offset).await?;
+            let counted = tracked(reader, cancel, pause, &file_bytes, &report);
+            if let Err(e) = sftp.write_stream_at(&item.dst, offset, counted).await {
+                if pause.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+                    return Ok(checkpoint(completed_files, bytes_done));
+                }
+                return Err(e);
+            }
+        }
+
+        bytes_done += file_bytes.load(Ordering::Relaxed);
+        completed_files.push(item.src.clone());
+        on_progress(ProgressEvent {
+            id: op_id.to_string(),
+            bytes_done,
+            bytes_total,
+            current_file: item.name().to_string(),
+            files_done: files_done + 1,
+            files_total,
+            retries: retry::retries(),
+        });
+    }
+
+    Ok(None)
+}
+
+/// Stream SFTP files and directories into the S3 prefix `dest_prefix`.
+/// Files below `MULTIPART_THRESHOLD` go up with a single PUT; larger ones are
+/// read straight into multipart upload parts, a few parts in flight at a
+/// time.
+///
+/// Pausing lets the parts in flight finish and leaves the multipart upload
+/// open; the returned checkpoint names it. Passing that checkpoint back skips
+/// `files_completed` and continues the open upload after the parts S3 still
+/// reports for it. Cancel and errors abort the upload.
+pub async fn sftp_to_s3(
+    sftp: &SftpService,
+    paths: &[String],
+    s3: &S3Service,
+    dest_prefix: &str,
+    checkpoint: Option<S3UploadCheckpoint>,
+    op_id: &str,
+    cancel: &AtomicBool,
+    pause: &AtomicBool,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+) -> Result<Option<S3UploadCheckpoint>, FmError> {
+    upload_to_s3(sftp, paths, s3, dest_prefix, checkpoint, op_id, cancel, pause, on_progress, &|_| {}).await
+}
+
+/// `sftp_to_s3` for files on any backend, e.g. local disk. `on_checkpoint`
+/// gets a checkpoint to resume from whenever a multipart upload is opened
+/// and after each file, so a caller can persist it before the run ends.
+pub async fn upload_to_s3(
+    source: &dyn StorageBackend,
+    paths: &[String],
+    s3: &S3Service,
+    dest_prefix: &str,
+    checkpoint: Option<S3UploadCheckpoint>,
+    op_id: &str,
+    cancel: &AtomicBool,
+    pause: &AtomicBool,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+    on_checkpoint: &(dyn Fn(S3UploadCheckpoint) + Send + Sync),
+) -> Result<Option<S3UploadCheckpoint>, FmError> {
+    let (items, empty_dirs) = plan_items(source, paths, s3, dest_prefix).await?;
+    for dir in &empty_dirs {
+        StorageBackend::mkdir(s3, dir).await?;
+    }
+
+    let mut completed_files: Vec<String> = Vec::new();
+    let mut open_upload: Option<(String, String)> = None;
+    if let Some(cp) = checkpoint {
+        completed_files = cp.files_completed;
+        if let (Some(key), Some(upload_id)) = (cp.current_file_key, cp.current_file_upload_id) {
+            open_upload = Some((key, upload_id));
+        }
+    }
+    let already_done: HashSet<String> = completed_files.iter().cloned().collect();
+
+    let files_total = items.len() as u32;
+    let bytes_total: u64 = items.iter().map(|item| item.size).sum();
+    let mut bytes_done: u64 = items
+        .iter()
+        .filter(|item| already_done.contains(&item.src))
+        .map(|item| item.size)
+        .sum();
+    let paused = |completed_files: Vec<String>, bytes_done: u64| S3UploadCheckpoint {
+        files_done: completed_files.len() as u32,
+        files_completed: completed_files,
+        current_file_upload_id: None,
+        current_file_key: None,
+        completed_parts: Vec::new(),
+        bytes_done,
+        bytes_total,
+        files_total,
+    };
+
+    for item in items.iter().filter(|item| !already_done.contains(&item.src)) {
+        let files_done = completed_files.len() as u32;
+        if cancel.load(Ordering::Relaxed) {
+            abort_open_upload(s3, open_upload.take()).await;
+            return Err(FmError::Other("cancelled".into()));
+        }
+        if pause.load(Ordering::Relaxed) {
+            let mut cp = paused(completed_files, bytes_done);
+            if let Some((key, upload_id)) = open_upload {
+                cp.current_file_key = Some(key);
+                cp.current_file_upload_id = Some(upload_id);
+            }
+            return Ok(Some(cp));
+        }
+
+        let base = bytes_done;
+        let file_bytes = AtomicU64::new(0);
+        let report = |done: u64| {
+            on_progress(ProgressEvent {
+                id: op_id.to_string(),
+                bytes_done: base + done,
+                bytes_total,
+                current_file: item.name().to_string(),
+                files_done,
+                files_total,
+                retries: retry::retries(),
+            })
+        };
+        let stopped = || pause.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed);
+
+        if item.size < MULTIPART_THRESHOLD {
+            let reader = source.read_stream(&item.src).await?;
+            let counted = tracked(reader, cancel, pause, &file_bytes, &report);
+            if let Err(e) = S3Service::write_stream(s3, &item.dst, item.size, counted).await {
+                if stopped() {
+                    return Ok(Some(paused(completed_files, bytes_done)));
+                }
+                return Err(e);
+            }
+        } else {
+            let part_size = part_size_for(item.size);
+            let resumable = match open_upload.take() {
+                Some((key, upload_id)) if key == item.dst => Some(upload_id),
+                other => {
+                    open_upload = other;
+                    None
+                }
+            };
+            let (upload_id, mut parts) = match resumable {
+                Some(upload_id) => match s3.list_uploaded_parts(&item.dst, &upload_id).await {
+                    Ok(listed) => (upload_id, leading_parts(listed, part_size, item.size)),
+                    Err(_) => (s3.create_multipart(&item.dst).await?, Vec::new()),
+                },
+                None => (s3.create_multipart(&item.dst).await?, Vec::new()),
+            };
+            let mut cp = paused(completed_files.clone(), bytes_done);
+            cp.current_file_key = Some(item.dst.clone());
+            cp.current_file_upload_id = Some(upload_id.clone());
+            on_checkpoint(cp);
+
+            let offset = (parts.len() as u64 * part_size).min(item.size);
+            file_bytes.store(offset, Ordering::Relaxed);
+            let reader = match source.read_stream_from(&item.src, offset).await {
+                Ok(reader) => reader,
+                Err(e) => {
+                    let _ = s3.abort_multipart_upload(&item.dst, &upload_id).await;
+                    return Err(e);
+                }
+            };
+            let mut counted = tracked(reader, cancel, pause, &file_bytes, &report);
+            let (new_parts, result) = s3
+                .upload_parts(&item.dst, &upload_id, part_size, parts.len() as i32 + 1, &mut counted)
+                .await;
+            parts.extend(new_parts);
+
+            let result = match result {
+                Ok(()) => complete_multipart(&s3.client, &s3.bucket, &item.dst, &upload_id, parts).await,
+                Err(_) if stopped() => {
+                    parts.sort_by_key(|(number, _, _)| *number);
+                    let uploaded = (parts.len() as u64 * part_size).min(item.size);
+                    let mut cp = paused(completed_files, bytes_done + uploaded);
+                    cp.current_file_key = Some(item.dst.clone());
+                    cp.current_file_upload_id = Some(upload_id);
+                    cp.completed_parts = parts
+                        .into_iter()
+                        .map(|(part_number, etag, _)| S3CompletedPart { part_number, etag })
+                        .collect();
+                    return Ok(Some(cp));
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                let _ = s3.abort_multipart_upload(&item.dst, &upload_id).await;
+                return Err(e);
+            }
+        }
+
+        bytes_done += item.size;
+        completed_files.push(item.src.clone());
+        // Keep naming a checkpointed upload that has not been reached yet.
+        let mut cp = paused(completed_files.clone(), bytes_done);
+        if let Some((key, upload_id)) = &open_upload {
+            cp.current_file_key = Some(key.clone());
+            cp.current_file_upload_id = Some(upload_id.clone());
+        }
+        on_checkpoint(cp);
+        on_progress(ProgressEvent {
+            id: op_id.to_string(),
+            bytes_done,
+            bytes_total,
+            current_file: item.name().to_string(),
+            files_done: files_done + 1,
+            files_total,
+            retries: retry::retries(),
+        });
+    }
+
+    // An upload from the checkpoint that no longer matches any file.
+    abort_open_upload(s3, open_upload).await;
+    Ok(None)
+}
+
+/// The uploaded parts that can be kept: a run without gaps from part 1,
+/// each of the size a fresh upload would give it.
+fn leading_parts(
+    listed: Vec<(i32, String, Option<String>, u64)>,
+    part_size: u64,
+    file_size: u64,
+) -> Vec<(i32, String, Option<String>)> {
+    listed
+        .into_iter()
+        .enumerate()
+        .take_while(|(i, (number, _, _, size))| {
+            let offset = *i as u64 * part_size;
+            *number == *i as i32 + 1
+                && offset < file_size
+                && *size == part_size.min(file_size - offset)
+        })
+        .map(|(_, (number, etag, crc, _))| (number, etag, crc))
+        .collect()
+}
+
+async fn abort_open_upload(s3: &S3Service, open_upload: Option<(String, String)>) {
+    if let Some((key, upload_id)) = open_upload {
+        let _ = s3.abort_multipart_upload(&key, &upload_id).await;
+    }
+}
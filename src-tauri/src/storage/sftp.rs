@@ -0,0 +1,61 @@
+use async_trait::async_trait;
+
+use crate::models::FmError;
+use crate::sftp::helpers::strip_sftp_prefix;
+use crate::sftp::{sftperr, SftpService};
+
+use super::backend::{ChunkStream, StorageBackend, StorageEntry};
+
+#[async_trait]
+impl StorageBackend for SftpService {
+    async fn list(&self, path: &str) -> Result<Vec<StorageEntry>, FmError> {
+        let listing = self.list_objects(path).await?;
+        Ok(listing
+            .entries
+            .into_iter()
+            .filter(|e| e.name != "..")
+            .map(|e| StorageEntry {
+                path: strip_sftp_prefix(&e.path).trim_end_matches('/').to_string(),
+                name: e.name,
+                is_dir: e.is_dir,
+                size: e.size,
+                modified: e.modified,
+            })
+            .collect())
+    }
+
+    async fn stat(&self, path: &str) -> Result<StorageEntry, FmError> {
+        let attrs = SftpService::stat(self, path).await?;
+        let clean = path.trim_end_matches('/');
+        Ok(StorageEntry {
+            name: clean.rsplit('/').next().unwrap_or(clean).to_string(),
+            path: if clean.is_empty() { "/".to_string() } else { clean.to_string() },
+            is_dir: attrs.is_dir(),
+            size: if attrs.is_dir() { 0 } else { attrs.size.unwrap_or(0) },
+            modified: attrs.mtime.map(|t| t as i64 * 1000).unwrap_or(0),
+        })
+    }
+
+    async fn read_stream_from(&self, path: &str, offset: u64) -> Result<ChunkStream<'static>, FmError> {
+        SftpService::read_stream_from(self, path, offset).await
+    }
+
+    async fn write_stream(&self, path: &str, _size: u64, data: ChunkStream<'_>) -> Result<(), FmError> {
+        SftpService::write_stream(self, path, data).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), FmError> {
+        SftpService::delete(self, &[path.to_string()]).await
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), FmError> {
+        self.session
+            .rename(from, to)
+            .await
+            .map_err(|e| sftperr(format!("rename '{}' → '{}': {}", from, to, e)))
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), FmError> {
+        self.ensure_remote_dir(path.trim_end_matches('/')).await
+    }
+}
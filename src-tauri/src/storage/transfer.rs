@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use futures::StreamExt;
+
+use crate::bandwidth::throttle;
+use crate::models::{FmError, ProgressEvent, TransferCheckpoint};
+use crate::s3::retry;
+
+use super::backend::{ChunkStream, StorageBackend};
+
+/// One file to copy, as backend-native source and destination paths.
+pub(crate) struct TransferItem {
+    pub src: String,
+    pub dst: String,
+    pub size: u64,
+}
+
+impl TransferItem {
+    pub fn name(&self) -> &str {
+        self.src.rsplit('/').next().unwrap_or(&self.src)
+    }
+}
+
+/// Copy files and directories from `source` into the directory `dest_dir`
+/// of `dest`, which may be a different backend. File contents are streamed
+/// from the source reader straight into the destination writer, so nothing
+/// is staged on local disk.
+///
+/// Returns None on success, Some(checkpoint) on pause; `files_completed`
+/// holds source paths. Passing the checkpoint back skips those files. A file
+/// interrupted by the pause is not kept and is copied again from the start.
+pub async fn transfer(
+    source: &dyn StorageBackend,
+    sources: &[String],
+    dest: &dyn StorageBackend,
+    dest_dir: &str,
+    checkpoint: Option<TransferCheckpoint>,
+    op_id: &str,
+    cancel: &AtomicBool,
+    pause: &AtomicBool,
+    on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+) -> Result<Option<TransferCheckpoint>, FmError> {
+    let (items, empty_dirs) = plan_items(source, sources, dest, dest_dir).await?;
+    for dir in &empty_dirs {
+        dest.mkdir(dir).await?;
+    }
+
+    let mut completed_files: Vec<String> = checkpoint.map(|cp| cp.files_completed).unwrap_or_default();
+    let already_done: HashSet<String> = completed_files.iter().cloned().collect();
+
+    let files_total = items.len() as u32;
+    let bytes_total: u64 = items.iter().map(|item| item.size).sum();
+    let mut bytes_done: u64 = items
+        .iter()
+        .filter(|item| already_done.contains(&item.src))
+        .map(|item| item.size)
+        .sum();
+    let checkpoint = |completed_files: Vec<String>, bytes_done: u64| {
+        Some(TransferCheckpoint {
+            files_done: completed_files.len() as u32,
+            files_completed: completed_files,
+            bytes_done,
+            bytes_total,
+            files_total,
+        })
+    };
+
+    for item in items.iter().filter(|item| !already_done.contains(&item.src)) {
+        let files_done = completed_files.len() as u32;
+        if cancel.load(Ordering::Relaxed) {
+            return Err(FmError::Other("cancelled".into()));
+        }
+        if pause.load(Ordering::Relaxed) {
+            return Ok(checkpoint(completed_files, bytes_done));
+        }
+
+        let base = bytes_done;
+        let file_bytes = AtomicU64::new(0);
+        let report = |done: u64| {
+            on_progress(ProgressEvent {
+                id: op_id.to_string(),
+                bytes_done: base + done,
+                bytes_total,
+                current_file: item.name().to_string(),
+                files_done,
+                files_total,
+                retries: retry::retries(),
+            })
+        };
+        let reader = source.read_stream(&item.src).await?;
+        let counted = tracked(reader, cancel, pause, &file_bytes, &report);
+
+        if let Err(e) = dest.write_stream(&item.dst, item.size, counted).await {
+            if pause.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+                return Ok(checkpoint(completed_files, bytes_done));
+            }
+            return Err(e);
+        }
+
+        bytes_done += file_bytes.load(Ordering::Relaxed);
+        completed_files.push(item.src.clone());
+        on_progress(ProgressEvent {
+            id: op_id.to_string(),
+            bytes_done,
+            bytes_total,
+            current_file: item.name().to_string(),
+            files_done: files_done + 1,
+            files_total,
+            retries: retry::retries(),
+        });
+    }
+
+    Ok(None)
+}
+
+/// Resolve `sources` (files or directories) into the files to copy into
+/// `dest_dir`, plus the destination directories that would stay empty and
+/// must be created explicitly.
+pub(crate) async fn plan_items(
+    source: &dyn StorageBackend,
+    sources: &[String],
+    dest: &dyn StorageBackend,
+    dest_dir: &str,
+) -> Result<(Vec<TransferItem>, Vec<String>), FmError> {
+    let mut items = Vec::new();
+    let mut empty_dirs = Vec::new();
+    for path in sources {
+        let entry = source.stat(path).await?;
+        let target = dest.join(dest_dir, &entry.name);
+        if entry.is_dir {
+            Box::pin(collect_items(source, dest, &entry.path, &target, &mut items, &mut empty_dirs))
+                .await?;
+        } else {
+            items.push(TransferItem {
+                src: entry.path,
+                dst: target,
+                size: entry.size,
+            });
+        }
+    }
+    Ok((items, empty_dirs))
+}
+
+/// Recursively collect the files under `src_dir`, mapped into `dst_dir`.
+async fn collect_items(
+    source: &dyn StorageBackend,
+    dest: &dyn StorageBackend,
+    src_dir: &str,
+    dst_dir: &str,
+    items: &mut Vec<TransferItem>,
+    empty_dirs: &mut Vec<String>,
+) -> Result<(), FmError> {
+    let entries = source.list(src_dir).await?;
+    if entries.is_empty() {
+        empty_dirs.push(dst_dir.to_string());
+    }
+    for entry in entries {
+        let target = dest.join(dst_dir, &entry.name);
+        if entry.is_dir {
+            Box::pin(collect_items(source, dest, &entry.path, &target, items, empty_dirs)).await?;
+        } else {
+            items.push(TransferItem {
+                src: entry.path,
+                dst: target,
+                size: entry.size,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Add each chunk of `reader` to `file_bytes` and pass the new total to
+/// `report`. Cancel and pause are checked per chunk; either one ends the
+/// stream with an error, which stops the writer. This is also where
+/// streamed copies are held to the bandwidth limits.
+pub(crate) fn tracked<'a>(
+    reader: ChunkStream<'a>,
+    cancel: &'a AtomicBool,
+    pause: &'a AtomicBool,
+    file_bytes: &'a AtomicU64,
+    report: &'a (dyn Fn(u64) + Send + Sync),
+) -> ChunkStream<'a> {
+    reader
+        .then(move |chunk| async move {
+            if cancel.load(Ordering::Relaxed) || pause.load(Ordering::Relaxed) {
+                return Err(FmError::Other("cancelled".into()));
+            }
+            let chunk = chunk?;
+            let n = chunk.len() as u64;
+            throttle(n).await;
+            report(file_bytes.fetch_add(n, Ordering::Relaxed) + n);
+            Ok(chunk)
+        })
+        .boxed()
+}
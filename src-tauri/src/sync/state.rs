@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::FmError;
+
+// ── Snapshot types ──────────────────────────────────────────────────────────
+
+/// Size, mtime and etag/MD5 of one side of a file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    pub size: u64,
+    pub modified: i64, // epoch ms
+    pub etag: Option<String>,
+}
+
+/// Both sides of a file as they were after the last successful sync.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub source: FileState,
+    pub dest: FileState,
+}
+
+/// Last-synced state of a (source, dest) pair, persisted as JSON under the
+/// app data directory so two-way sync can tell which side changed.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SyncSnapshot {
+    pub source: String,
+    pub dest: String,
+    pub files: HashMap<String, SnapshotEntry>,
+}
+
+impl SyncSnapshot {
+    /// Load the snapshot for a pair, or an empty one if it was never synced.
+    pub fn load(source: &str, dest: &str) -> Result<Self, FmError> {
+        let path = snapshot_path(source, dest);
+        match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| FmError::Other(format!("corrupt sync state {}: {e}", path.display()))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self {
+                source: source.to_string(),
+                dest: dest.to_string(),
+                files: HashMap::new(),
+            }),
+            Err(e) => Err(FmError::Io(e)),
+        }
+    }
+
+    /// Write the snapshot atomically (temp file + rename).
+    pub fn save(&self) -> Result<(), FmError> {
+        let path = snapshot_path(&self.source, &self.dest);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_vec(self).map_err(|e| FmError::Other(e.to_string()))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// `<data dir>/com.furman.filemanager/sync-state/<md5 of pair>.json`
+fn snapshot_path(source: &str, dest: &str) -> PathBuf {
+    let digest = md5::compute(format!("{source}\n{dest}"));
+    dirs::data_dir()
+        .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join(".local/share"))
+        .join("com.furman.filemanager/sync-state")
+        .join(format!("{:x}.json", digest))
+}
+
+// ── Two-way classification ──────────────────────────────────────────────────
+
+/// Classify one path for two-way sync against its last-synced snapshot.
+///
+/// Returns one of "left_changed", "right_changed", "left_deleted",
+/// "right_deleted", "conflict" or "same" ("left" is the source side), or None
+/// when the file is gone from both sides and there is nothing to report.
+pub fn classify_two_way(
+    base: Option<&SnapshotEntry>,
+    source: Option<&FileState>,
+    dest: Option<&FileState>,
+) -> Option<&'static str> {
+    let Some(base) = base else {
+        // Never synced: whichever side has the file wins; both → compare content.
+        return match (source, dest) {
+            (Some(_), None) => Some("left_changed"),
+            (None, Some(_)) => Some("right_changed"),
+            (Some(s), Some(d)) if same_content(s, d) => Some("same"),
+            (Some(_), Some(_)) => Some("conflict"),
+            (None, None) => None,
+        };
+    };
+
+    let left_changed = changed(source, &base.source);
+    let right_changed = changed(dest, &base.dest);
+
+    match (left_changed, right_changed) {
+        (false, false) => Some("same"),
+        (true, false) if source.is_none() => Some("left_deleted"),
+        (true, false) => Some("left_changed"),
+        (false, true) if dest.is_none() => Some("right_deleted"),
+        (false, true) => Some("right_changed"),
+        (true, true) => match (source, dest) {
+            (None, None) => None,
+            // Both sides ended up with the same content (e.g. an interrupted sync).
+            (Some(s), Some(d)) if same_content(s, d) => Some("same"),
+            _ => Some("conflict"),
+        },
+    }
+}
+
+/// Whether a side differs from its snapshot. ETags only count when both are known.
+fn changed(now: Option<&FileState>, then: &FileState) -> bool {
+    let Some(now) = now else {
+        return true;
+    };
+    if now.size != then.size || now.modified != then.modified {
+        return true;
+    }
+    match (clean_etag(&now.etag), clean_etag(&then.etag)) {
+        (Some(a), Some(b)) => a != b,
+        _ => false,
+    }
+}
+
+/// Best-effort content equality across backends: compare MD5/ETags when both
+/// are plain digests (not S3 multipart), otherwise fall back to size.
+fn same_content(a: &FileState, b: &FileState) -> bool {
+    match (clean_etag(&a.etag), clean_etag(&b.etag)) {
+        (Some(x), Some(y)) if !x.contains('-') && !y.contains('-') => x == y,
+        _ => a.size == b.size,
+    }
+}
+
+fn clean_etag(etag: &Option<String>) -> Option<&str> {
+    etag.as_deref()
+        .map(|e| e.trim_matches('"'))
+        .filter(|e| !e.is_empty())
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fs(size: u64, modified: i64) -> FileState {
+        FileState { size, modified, etag: None }
+    }
+
+    fn base(source: FileState, dest: FileState) -> SnapshotEntry {
+        SnapshotEntry { source, dest }
+    }
+
+    #[test]
+    fn test_unchanged_is_same() {
+        let b = base(fs(10, 1000), fs(10, 2000));
+        assert_eq!(
+            classify_two_way(Some(&b), Some(&fs(10, 1000)), Some(&fs(10, 2000))),
+            Some("same")
+        );
+    }
+
+    #[test]
+    fn test_one_side_changed() {
+        let b = base(fs(10, 1000), fs(10, 2000));
+        assert_eq!(
+            classify_two_way(Some(&b), Some(&fs(12, 3000)), Some(&fs(10, 2000))),
+            Some("left_changed")
+        );
+        assert_eq!(
+            classify_two_way(Some(&b), Some(&fs(10, 1000)), Some(&fs(10, 4000))),
+            Some("right_changed")
+        );
+    }
+
+    #[test]
+    fn test_one_side_deleted() {
+        let b = base(fs(10, 1000), fs(10, 2000));
+        assert_eq!(
+            classify_two_way(Some(&b), None, Some(&fs(10, 2000))),
+            Some("left_deleted")
+        );
+        assert_eq!(
+            classify_two_way(Some(&b), Some(&fs(10, 1000)), None),
+            Some("right_deleted")
+        );
+        assert_eq!(classify_two_way(Some(&b), None, None), None);
+    }
+
+    #[test]
+    fn test_both_changed_is_conflict() {
+        let b = base(fs(10, 1000), fs(10, 2000));
+        assert_eq!(
+            classify_two_way(Some(&b), Some(&fs(11, 3000)), Some(&fs(12, 4000))),
+            Some("conflict")
+        );
+        // Deleted on one side, modified on the other
+        assert_eq!(
+            classify_two_way(Some(&b), None, Some(&fs(12, 4000))),
+            Some("conflict")
+        );
+    }
+
+    #[test]
+    fn test_both_changed_to_same_content() {
+        let b = base(
+            FileState { size: 10, modified: 1000, etag: Some("aaa".into()) },
+            FileState { size: 10, modified: 2000, etag: Some("\"aaa\"".into()) },
+        );
+        let s = FileState { size: 11, modified: 3000, etag: Some("bbb".into()) };
+        let d = FileState { size: 11, modified: 4000, etag: Some("\"bbb\"".into()) };
+        assert_eq!(classify_two_way(Some(&b), Some(&s), Some(&d)), Some("same"));
+    }
+
+    #[test]
+    fn test_no_snapshot() {
+        assert_eq!(classify_two_way(None, Some(&fs(1, 1)), None), Some("left_changed"));
+        assert_eq!(classify_two_way(None, None, Some(&fs(1, 1))), Some("right_changed"));
+        assert_eq!(classify_two_way(None, Some(&fs(5, 1)), Some(&fs(5, 9))), Some("same"));
+        assert_eq!(classify_two_way(None, Some(&fs(5, 1)), Some(&fs(6, 1))), Some("conflict"));
+    }
+
+    #[test]
+    fn test_etag_change_with_same_size_and_mtime() {
+        let b = base(
+            FileState { size: 10, modified: 1000, etag: Some("aaa".into()) },
+            fs(10, 2000),
+        );
+        let s = FileState { size: 10, modified: 1000, etag: Some("ccc".into()) };
+        assert_eq!(
+            classify_two_way(Some(&b), Some(&s), Some(&fs(10, 2000))),
+            Some("left_changed")
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder};
+
+use crate::models::FmError;
+use super::helpers::daverr;
+
+/// Timeout for establishing a connection; transfers themselves are unbounded.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// ── State ────────────────────────────────────────────────────────────────────
+
+pub struct WebDavState(pub Mutex<HashMap<String, WebDavConnection>>);
+
+impl Default for WebDavState {
+    fn default() -> Self {
+        WebDavState(Mutex::new(HashMap::new()))
+    }
+}
+
+#[derive(Clone)]
+pub struct WebDavConnection {
+    pub client: Client,
+    /// Collection URL everything is relative to, without trailing slash,
+    /// e.g. `https://cloud.example.com/remote.php/dav/files/alice`.
+    pub base_url: String,
+    pub auth: DavAuth,
+}
+
+// ── Auth ─────────────────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+pub enum DavAuth {
+    None,
+    Basic { username: String, password: String },
+    Bearer(String),
+}
+
+impl DavAuth {
+    pub fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            DavAuth::None => request,
+            DavAuth::Basic { username, password } => request.basic_auth(username, Some(password)),
+            DavAuth::Bearer(token) => request.bearer_auth(token),
+        }
+    }
+}
+
+/// HTTP client for one connection. Redirects are not followed: a redirected
+/// PUT or MOVE would silently change meaning.
+pub fn build_client(accept_invalid_certs: bool) -> Result<Client, FmError> {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .user_agent(concat!("furman/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| daverr(format!("HTTP client setup failed: {}", e)))
+}
@@ -0,0 +1,247 @@
+use chrono::DateTime;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::models::FmError;
+
+/// Convenience constructor for WebDAV errors.
+pub fn daverr(msg: impl Into<String>) -> FmError {
+    FmError::WebDav(msg.into())
+}
+
+/// Build a `webdav://authority/path` URI; `path` is relative to the
+/// connection's base URL.
+pub fn dav_path(authority: &str, path: &str) -> String {
+    format!("webdav://{}{}", authority, path)
+}
+
+/// Strip the `webdav://authority` prefix, returning just the remote path.
+pub fn strip_dav_prefix(path: &str) -> &str {
+    if let Some(rest) = path.strip_prefix("webdav://") {
+        if let Some(idx) = rest.find('/') {
+            return &rest[idx..];
+        }
+        return "/";
+    }
+    path
+}
+
+/// Percent-encode each segment of a remote path for use in a URL.
+pub fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|seg| urlencoding::encode(seg).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Remote path (relative to the base URL, leading `/`, no trailing `/`) of
+/// an `href`, which servers send either as an absolute path or a full URL.
+pub fn href_to_path(href: &str, base_path: &str) -> String {
+    let raw = match href.find("://") {
+        Some(i) => {
+            let rest = &href[i + 3..];
+            rest.find('/').map(|j| &rest[j..]).unwrap_or("/")
+        }
+        None => href,
+    };
+    let decoded = urlencoding::decode(raw)
+        .map(|p| p.into_owned())
+        .unwrap_or_else(|_| raw.to_string());
+    let base = base_path.trim_end_matches('/');
+    let rel = match decoded.strip_prefix(base) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => decoded.as_str(),
+    };
+    let rel = rel.trim_end_matches('/');
+    if rel.is_empty() {
+        "/".to_string()
+    } else if rel.starts_with('/') {
+        rel.to_string()
+    } else {
+        format!("/{}", rel)
+    }
+}
+
+// ── PROPFIND ─────────────────────────────────────────────────────────────────
+
+/// Properties requested by every PROPFIND.
+pub const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength/>
+    <d:getlastmodified/>
+    <d:getetag/>
+    <d:quota-available-bytes/>
+  </d:prop>
+</d:propfind>"#;
+
+/// One `<response>` of a multistatus reply.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DavEntry {
+    /// Path relative to the base URL, see `href_to_path`.
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Epoch milliseconds, 0 if unknown.
+    pub modified: i64,
+    pub etag: Option<String>,
+    /// Only reported for collections, and not by every server.
+    pub quota_available: Option<u64>,
+}
+
+impl DavEntry {
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or("")
+    }
+}
+
+/// Parse a `207 Multi-Status` PROPFIND reply. Elements are matched by local
+/// name, so any namespace prefix works.
+pub fn parse_multistatus(xml: &str, base_path: &str) -> Result<Vec<DavEntry>, FmError> {
+    let mut reader = Reader::from_str(xml);
+    let mut entries = Vec::new();
+    let mut current: Option<DavEntry> = None;
+    let mut text = String::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| daverr(format!("Invalid PROPFIND reply: {}", e)))?;
+        match event {
+            Event::Start(e) => {
+                if e.local_name().as_ref() == b"response" {
+                    current = Some(DavEntry::default());
+                }
+                text.clear();
+            }
+            Event::Empty(e) if e.local_name().as_ref() == b"collection" => {
+                if let Some(entry) = current.as_mut() {
+                    entry.is_dir = true;
+                }
+            }
+            Event::Text(t) => {
+                text.push_str(&t.decode().map_err(|e| daverr(e.to_string()))?);
+            }
+            Event::CData(t) => {
+                text.push_str(&t.decode().map_err(|e| daverr(e.to_string()))?);
+            }
+            Event::GeneralRef(r) => {
+                if let Ok(Some(c)) = r.resolve_char_ref() {
+                    text.push(c);
+                } else if let Ok(name) = r.decode() {
+                    text.push_str(resolve_predefined_entity(&name).unwrap_or(""));
+                }
+            }
+            Event::End(e) => {
+                let name = e.local_name();
+                let value = text.trim();
+                match (name.as_ref(), current.as_mut()) {
+                    (b"response", Some(_)) => entries.extend(current.take()),
+                    (b"href", Some(entry)) if entry.path.is_empty() => {
+                        entry.path = href_to_path(value, base_path);
+                    }
+                    (b"collection", Some(entry)) => entry.is_dir = true,
+                    (b"getcontentlength", Some(entry)) => {
+                        entry.size = value.parse().unwrap_or(0);
+                    }
+                    (b"getlastmodified", Some(entry)) => {
+                        entry.modified = DateTime::parse_from_rfc2822(value)
+                            .map(|d| d.timestamp_millis())
+                            .unwrap_or(0);
+                    }
+                    (b"getetag", Some(entry)) if !value.is_empty() => {
+                        entry.etag = Some(value.trim_start_matches("W/").trim_matches('"').to_string());
+                    }
+                    (b"quota-available-bytes", Some(entry)) => {
+                        entry.quota_available = value.parse().ok();
+                    }
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+// ── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEXTCLOUD: &str = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns" xmlns:oc="http://owncloud.org/ns">
+ <d:response>
+  <d:href>/remote.php/dav/files/alice/Documents/</d:href>
+  <d:propstat>
+   <d:prop>
+    <d:resourcetype><d:collection/></d:resourcetype>
+    <d:getlastmodified>Tue, 02 Jan 2024 10:00:00 GMT</d:getlastmodified>
+    <d:getetag>&quot;65942b2a1c2d8&quot;</d:getetag>
+    <d:quota-available-bytes>1073741824</d:quota-available-bytes>
+   </d:prop>
+   <d:status>HTTP/1.1 200 OK</d:status>
+  </d:propstat>
+  <d:propstat>
+   <d:prop><d:getcontentlength/></d:prop>
+   <d:status>HTTP/1.1 404 Not Found</d:status>
+  </d:propstat>
+ </d:response>
+ <d:response>
+  <d:href>/remote.php/dav/files/alice/Documents/Q1%20report%20%26%20notes.pdf</d:href>
+  <d:propstat>
+   <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength>48213</d:getcontentlength>
+    <d:getlastmodified>Wed, 03 Jan 2024 08:30:00 GMT</d:getlastmodified>
+    <d:getetag>"a1b2c3"</d:getetag>
+   </d:prop>
+   <d:status>HTTP/1.1 200 OK</d:status>
+  </d:propstat>
+ </d:response>
+</d:multistatus>"#;
+
+    #[test]
+    fn test_nextcloud_multistatus() {
+        let entries = parse_multistatus(NEXTCLOUD, "/remote.php/dav/files/alice").unwrap();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].path, "/Documents");
+        assert!(entries[0].is_dir);
+        assert_eq!(entries[0].etag.as_deref(), Some("65942b2a1c2d8"));
+        assert_eq!(entries[0].quota_available, Some(1073741824));
+
+        assert_eq!(entries[1].path, "/Documents/Q1 report & notes.pdf");
+        assert_eq!(entries[1].name(), "Q1 report & notes.pdf");
+        assert!(!entries[1].is_dir);
+        assert_eq!(entries[1].size, 48213);
+        assert_eq!(entries[1].modified, 1704270600000);
+        assert_eq!(entries[1].etag.as_deref(), Some("a1b2c3"));
+    }
+
+    #[test]
+    fn test_default_namespace_and_full_url_href() {
+        let xml = r#"<multistatus xmlns="DAV:"><response>
+            <href>https://dav.example.com/files/a.txt</href>
+            <propstat><prop><resourcetype/><getcontentlength>5</getcontentlength></prop></propstat>
+            </response></multistatus>"#;
+        let entries = parse_multistatus(xml, "/files").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "/a.txt");
+        assert_eq!(entries[0].size, 5);
+    }
+
+    #[test]
+    fn test_href_to_path() {
+        assert_eq!(href_to_path("/dav/", "/dav"), "/");
+        assert_eq!(href_to_path("/dav/a/b/", "/dav/"), "/a/b");
+        assert_eq!(href_to_path("/davx/a", "/dav"), "/davx/a");
+        assert_eq!(encode_path("/My Files/ä.txt"), "/My%20Files/%C3%A4.txt");
+        assert_eq!(strip_dav_prefix("webdav://cloud.example.com/x/y"), "/x/y");
+    }
+}
@@ -0,0 +1,7 @@
+pub mod client;
+pub mod helpers;
+pub mod service;
+
+pub use client::{WebDavConnection, WebDavState};
+pub use helpers::daverr;
+pub use service::WebDavService;
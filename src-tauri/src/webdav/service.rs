@@ -0,0 +1,573 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures::stream;
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::{Body, Method, RequestBuilder, Response, StatusCode};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::models::{DirListing, FileEntry, FmError, ProgressEvent, TransferCheckpoint};
+use crate::storage::batch::{
+    check_stop, collect_local_files_recursive, join, parent_path, FileOutcome, TransferProgress,
+};
+
+use super::client::{DavAuth, WebDavConnection};
+use super::helpers::{dav_path, daverr, encode_path, parse_multistatus, DavEntry, PROPFIND_BODY};
+
+/// Bytes per read from a response body or local file.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+// ── WebDavService ────────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+pub struct WebDavService {
+    pub client: reqwest::Client,
+    pub base_url: String,
+    pub auth: DavAuth,
+}
+
+impl WebDavService {
+    pub fn new(conn: &WebDavConnection) -> Self {
+        Self {
+            client: conn.client.clone(),
+            base_url: conn.base_url.clone(),
+            auth: conn.auth.clone(),
+        }
+    }
+
+    /// `host[:port]` of the base URL, used in `webdav://` panel paths.
+    pub fn authority(&self) -> &str {
+        let rest = self.base_url.split_once("://").map(|(_, r)| r).unwrap_or(&self.base_url);
+        rest.split('/').next().unwrap_or(rest)
+    }
+
+    /// Decoded path component of the base URL, which servers echo in hrefs.
+    fn base_path(&self) -> String {
+        let rest = self.base_url.split_once("://").map(|(_, r)| r).unwrap_or(&self.base_url);
+        let path = rest.find('/').map(|i| &rest[i..]).unwrap_or("");
+        urlencoding::decode(path)
+            .map(|p| p.into_owned())
+            .unwrap_or_else(|_| path.to_string())
+    }
+
+    fn url(&self, path: &str) -> String {
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{}", path)
+        };
+        format!("{}{}", self.base_url, encode_path(&path))
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.auth.apply(self.client.request(method, self.url(path)))
+    }
+
+    /// Send a request, turning non-success statuses into errors.
+    async fn send(&self, request: RequestBuilder, what: &str) -> Result<Response, FmError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| daverr(format!("{}: {}", what, e)))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                daverr(format!("{}: access denied ({})", what, status))
+            }
+            StatusCode::NOT_FOUND => FmError::NotFound(what.to_string()),
+            StatusCode::PRECONDITION_FAILED => FmError::AlreadyExists(what.to_string()),
+            _ => daverr(format!("{}: {}", what, status)),
+        })
+    }
+
+    /// PROPFIND `path` with the given depth ("0" or "1").
+    pub async fn propfind(&self, path: &str, depth: &str) -> Result<Vec<DavEntry>, FmError> {
+        let method = Method::from_bytes(b"PROPFIND").map_err(|e| daverr(e.to_string()))?;
+        let request = self
+            .request(method, path)
+            .header("Depth", depth)
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY);
+        let response = self.send(request, &format!("PROPFIND '{}'", path)).await?;
+        let xml = response
+            .text()
+            .await
+            .map_err(|e| daverr(format!("PROPFIND '{}': {}", path, e)))?;
+        parse_multistatus(&xml, &self.base_path())
+    }
+
+    /// Check that the base URL is reachable and a collection.
+    pub async fn check(&self) -> Result<(), FmError> {
+        let entries = self.propfind("/", "0").await?;
+        match entries.first() {
+            Some(entry) if entry.is_dir => Ok(()),
+            _ => Err(daverr(format!("{} is not a WebDAV collection", self.base_url))),
+        }
+    }
+
+    /// List directory contents, returning a DirListing with `..` entry.
+    pub async fn list_objects(&self, path: &str) -> Result<DirListing, FmError> {
+        let dir = normalize(path);
+        let listing = self.propfind(&dir, "1").await?;
+        let authority = self.authority();
+
+        let mut entries = Vec::new();
+
+        // Add parent directory entry
+        let parent = parent_path(&dir);
+        entries.push(FileEntry {
+            name: "..".to_string(),
+            path: dav_path(authority, &parent),
+            size: 0,
+            is_dir: true,
+            is_symlink: false,
+            symlink_target: None,
+            modified: 0,
+            permissions: 0,
+            owner: String::new(),
+            group: String::new(),
+            extension: None,
+            git_status: None,
+            storage_class: None,
+        });
+
+        let mut free_space = 0;
+        for entry in listing {
+            // The collection itself is part of a Depth: 1 reply.
+            if entry.path == dir {
+                free_space = entry.quota_available.unwrap_or(0);
+                continue;
+            }
+            let name = entry.name().to_string();
+            let entry_path = if entry.is_dir {
+                format!("{}/", entry.path)
+            } else {
+                entry.path.clone()
+            };
+
+            let extension = if !entry.is_dir {
+                let dot = name.rfind('.');
+                dot.map(|i| name[i + 1..].to_string())
+            } else {
+                None
+            };
+
+            entries.push(FileEntry {
+                name,
+                path: dav_path(authority, &entry_path),
+                size: entry.size,
+                is_dir: entry.is_dir,
+                is_symlink: false,
+                symlink_target: None,
+                modified: entry.modified,
+                permissions: 0,
+                owner: String::new(),
+                group: String::new(),
+                extension,
+                git_status: None,
+                storage_class: None,
+            });
+        }
+
+        Ok(DirListing {
+            path: dav_path(authority, &dir),
+            entries,
+            total_size: 0,
+            free_space,
+        })
+    }
+
+    /// Delete files and directories; servers remove collections recursively.
+    pub async fn delete(&self, paths: &[String]) -> Result<(), FmError> {
+        for path in paths {
+            let clean = normalize(path);
+            self.send(
+                self.request(Method::DELETE, &clean),
+                &format!("DELETE '{}'", clean),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Rename a file or directory.
+    pub async fn rename(&self, old_path: &str, new_name: &str) -> Result<(), FmError> {
+        let clean = normalize(old_path);
+        let new_path = join(&parent_path(&clean), new_name);
+        self.move_or_copy("MOVE", &clean, &new_path, false).await
+    }
+
+    /// Create a directory.
+    pub async fn create_folder(&self, path: &str) -> Result<(), FmError> {
+        let clean = normalize(path);
+        let method = Method::from_bytes(b"MKCOL").map_err(|e| daverr(e.to_string()))?;
+        self.send(self.request(method, &clean), &format!("MKCOL '{}'", clean))
+            .await?;
+        Ok(())
+    }
+
+    /// Copy files and directories into `dest_dir` on the same server.
+    pub async fn copy(&self, paths: &[String], dest_dir: &str, overwrite: bool) -> Result<(), FmError> {
+        for path in paths {
+            let clean = normalize(path);
+            let target = join(&normalize(dest_dir), clean.rsplit('/').next().unwrap_or(""));
+            self.move_or_copy("COPY", &clean, &target, overwrite).await?;
+        }
+        Ok(())
+    }
+
+    /// Move files and directories into `dest_dir` on the same server.
+    pub async fn move_to(&self, paths: &[String], dest_dir: &str, overwrite: bool) -> Result<(), FmError> {
+        for path in paths {
+            let clean = normalize(path);
+            let target = join(&normalize(dest_dir), clean.rsplit('/').next().unwrap_or(""));
+            self.move_or_copy("MOVE", &clean, &target, overwrite).await?;
+        }
+        Ok(())
+    }
+
+    /// MOVE or COPY `from` to `to`; without `overwrite` an existing target
+    /// fails with AlreadyExists.
+    async fn move_or_copy(&self, verb: &str, from: &str, to: &str, overwrite: bool) -> Result<(), FmError> {
+        if from == to || to.starts_with(&format!("{}/", from)) {
+            return Err(FmError::Other(format!("Cannot {} '{}' into itself", verb.to_lowercase(), from)));
+        }
+        let method = Method::from_bytes(verb.as_bytes()).map_err(|e| daverr(e.to_string()))?;
+        let request = self
+            .request(method, from)
+            .header("Destination", self.url(to))
+            .header("Overwrite", if overwrite { "T" } else { "F" })
+            .header("Depth", "infinity");
+        self.send(request, &format!("{} '{}' → '{}'", verb, from, to))
+            .await?;
+        Ok(())
+    }
+
+    /// Recursively list files under `root`, returning (relative path, size,
+    /// modified ms, etag). Walks one level at a time since many servers
+    /// refuse `Depth: infinity`.
+    pub async fn list_files_recursive(
+        &self,
+        root: &str,
+    ) -> Result<Vec<(String, u64, i64, Option<String>)>, FmError> {
+        let root = normalize(root);
+        let prefix = if root == "/" { "/".to_string() } else { format!("{}/", root) };
+        let mut out = Vec::new();
+        let mut pending = vec![root.clone()];
+        while let Some(dir) = pending.pop() {
+            for entry in self.propfind(&dir, "1").await? {
+                if entry.path == dir {
+                    continue;
+                }
+                if entry.is_dir {
+                    pending.push(entry.path);
+                } else if let Some(rel) = entry.path.strip_prefix(&prefix) {
+                    out.push((rel.to_string(), entry.size, entry.modified, entry.etag));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Download remote files to a local destination directory, one file at a
+    /// time. Returns None on success, Some(checkpoint) on pause.
+    pub async fn download(
+        &self,
+        remote_paths: &[String],
+        local_dest: &str,
+        op_id: &str,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+        resume: bool,
+    ) -> Result<Option<TransferCheckpoint>, FmError> {
+        // First pass: collect all files and calculate total size
+        let mut file_list: Vec<(String, String, u64)> = Vec::new(); // (remote_path, local_path, size)
+        for remote_path in remote_paths {
+            let clean = normalize(remote_path);
+            let name = clean.rsplit('/').next().unwrap_or(&clean).to_string();
+            let local_target = format!("{}/{}", local_dest.trim_end_matches('/'), name);
+            let entry = self
+                .propfind(&clean, "0")
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| FmError::NotFound(clean.clone()))?;
+
+            if entry.is_dir {
+                for (rel, size, _, _) in self.list_files_recursive(&clean).await? {
+                    file_list.push((join(&clean, &rel), format!("{}/{}", local_target, rel), size));
+                }
+            } else {
+                file_list.push((clean, local_target, entry.size));
+            }
+        }
+
+        // Ensure parent directories exist
+        for (_, local, _) in &file_list {
+            if let Some(parent) = Path::new(local).parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(FmError::Io)?;
+            }
+        }
+
+        let progress = TransferProgress::new(op_id, &file_list, |(_, _, s)| *s, on_progress);
+        let mut results = Vec::new();
+        for (remote, local, size) in &file_list {
+            let name = remote.rsplit('/').next().unwrap_or(remote);
+            let result = self
+                .download_file(remote, local, *size, resume, cancel, pause, &mut |n| {
+                    progress.add_bytes(n, name)
+                })
+                .await;
+            progress.finish_file(&result, name);
+            let done = matches!(result, Ok(FileOutcome::Done));
+            results.push((remote.clone(), result));
+            if !done {
+                break;
+            }
+        }
+        progress.outcome(results)
+    }
+
+    /// Upload local files to a remote directory, one file at a time. Returns
+    /// None on success, Some(checkpoint) on pause.
+    pub async fn upload(
+        &self,
+        local_paths: &[String],
+        remote_dest: &str,
+        op_id: &str,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_progress: &(dyn Fn(ProgressEvent) + Send + Sync),
+    ) -> Result<Option<TransferCheckpoint>, FmError> {
+        // Collect all local files
+        let mut file_list: Vec<(PathBuf, String, u64)> = Vec::new();
+        let remote_dest = normalize(remote_dest);
+        for local_path in local_paths {
+            let path = Path::new(local_path);
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let remote_target = join(&remote_dest, &name);
+
+            if path.is_dir() {
+                collect_local_files_recursive(path, &remote_target, &mut file_list)?;
+            } else {
+                let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+                file_list.push((path.to_path_buf(), remote_target, size));
+            }
+        }
+
+        // Ensure remote parent directories exist
+        let mut parents: Vec<&str> = file_list
+            .iter()
+            .filter_map(|(_, remote, _)| remote.rsplit_once('/').map(|(p, _)| p))
+            .collect();
+        parents.sort_unstable();
+        parents.dedup();
+        for parent in parents {
+            self.ensure_remote_dir(parent).await?;
+        }
+
+        let progress = TransferProgress::new(op_id, &file_list, |(_, _, s)| *s, on_progress);
+        let mut results = Vec::new();
+        for (local, remote, size) in &file_list {
+            let name = local
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            // A PUT cannot be continued later, so pause is honoured between files.
+            let result = if pause.load(Ordering::Relaxed) {
+                Ok(FileOutcome::Paused)
+            } else {
+                self.upload_file(local, remote, *size, cancel, &mut |n| progress.add_bytes(n, &name))
+                    .await
+                    .map(|()| FileOutcome::Done)
+            };
+            progress.finish_file(&result, &name);
+            let done = matches!(result, Ok(FileOutcome::Done));
+            results.push((local.to_string_lossy().to_string(), result));
+            if !done {
+                break;
+            }
+        }
+        progress.outcome(results)
+    }
+
+    /// Download a remote file to a temp location, returning the local path.
+    pub async fn download_temp(&self, remote_path: &str) -> Result<String, FmError> {
+        let name = remote_path.rsplit('/').next().unwrap_or("file");
+        let tmp_dir = std::env::temp_dir().join("furman-webdav");
+        std::fs::create_dir_all(&tmp_dir).map_err(FmError::Io)?;
+        let local_path = tmp_dir.join(name);
+
+        let what = format!("GET '{}'", remote_path);
+        let response = self
+            .send(self.request(Method::GET, remote_path), &what)
+            .await?;
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| daverr(format!("{}: {}", what, e)))?;
+
+        tokio::fs::write(&local_path, &data)
+            .await
+            .map_err(FmError::Io)?;
+
+        Ok(local_path.to_string_lossy().to_string())
+    }
+
+    /// Write text content to a remote file.
+    pub async fn put_text(&self, remote_path: &str, content: &str) -> Result<(), FmError> {
+        self.send(
+            self.request(Method::PUT, remote_path).body(content.to_string()),
+            &format!("PUT '{}'", remote_path),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// GET one file into `local`. With `resume`, a shorter existing local
+    /// file is continued with a Range request; servers that ignore the range
+    /// send the whole file, which then replaces it.
+    async fn download_file(
+        &self,
+        remote: &str,
+        local: &str,
+        size: u64,
+        resume: bool,
+        cancel: &AtomicBool,
+        pause: &AtomicBool,
+        on_bytes: &mut (dyn FnMut(u64) + Send),
+    ) -> Result<FileOutcome, FmError> {
+        if check_stop(cancel, pause)? {
+            return Ok(FileOutcome::Paused);
+        }
+
+        let existing = match tokio::fs::metadata(local).await {
+            Ok(meta) if resume => meta.len(),
+            _ => 0,
+        };
+        if existing > 0 && existing == size {
+            on_bytes(size);
+            return Ok(FileOutcome::Done);
+        }
+
+        let mut request = self.request(Method::GET, remote);
+        if existing > 0 && existing < size {
+            request = request.header(RANGE, format!("bytes={}-", existing));
+        }
+        let what = format!("GET '{}'", remote);
+        let mut response = self.send(request, &what).await?;
+        let mut file = if response.status() == StatusCode::PARTIAL_CONTENT {
+            on_bytes(existing);
+            tokio::fs::OpenOptions::new().append(true).open(local).await?
+        } else {
+            tokio::fs::File::create(local).await?
+        };
+
+        loop {
+            if check_stop(cancel, pause)? {
+                // The partial file is kept for a later resume.
+                file.flush().await?;
+                return Ok(FileOutcome::Paused);
+            }
+            let chunk = response
+                .chunk()
+                .await
+                .map_err(|e| daverr(format!("{}: {}", what, e)))?;
+            let Some(chunk) = chunk else { break };
+            file.write_all(&chunk).await?;
+            on_bytes(chunk.len() as u64);
+        }
+        file.flush().await?;
+        Ok(FileOutcome::Done)
+    }
+
+    /// PUT one local file, streaming it from disk. Cancelling aborts the
+    /// request mid-body.
+    async fn upload_file(
+        &self,
+        local: &Path,
+        remote: &str,
+        size: u64,
+        cancel: &AtomicBool,
+        on_bytes: &mut (dyn FnMut(u64) + Send),
+    ) -> Result<(), FmError> {
+        let mut file = tokio::fs::File::open(local).await?;
+
+        // The body stream must be 'static, so the file is read here and fed
+        // to it through a channel, which also keeps progress in step with
+        // what the connection has accepted.
+        let (tx, rx) = mpsc::channel::<std::io::Result<Vec<u8>>>(4);
+        let body = Body::wrap_stream(stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        }));
+        let request = self
+            .request(Method::PUT, remote)
+            .header(CONTENT_LENGTH, size)
+            .body(body);
+
+        let feed = async move {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            loop {
+                if cancel.load(Ordering::Relaxed) {
+                    let _ = tx
+                        .send(Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "cancelled")))
+                        .await;
+                    return Err(FmError::Other("cancelled".into()));
+                }
+                let n = file.read(&mut buf).await?;
+                if n == 0 {
+                    return Ok(());
+                }
+                // A closed channel means the request already ended; its
+                // result carries the reason.
+                if tx.send(Ok(buf[..n].to_vec())).await.is_err() {
+                    return Ok(());
+                }
+                on_bytes(n as u64);
+            }
+        };
+        let (fed, sent) =
+            futures::future::join(feed, self.send(request, &format!("PUT '{}'", remote))).await;
+        fed?;
+        sent?;
+        Ok(())
+    }
+
+    /// Create `path` and any missing parents. MKCOL on an existing collection
+    /// fails with 405, which is fine.
+    async fn ensure_remote_dir(&self, path: &str) -> Result<(), FmError> {
+        let method = Method::from_bytes(b"MKCOL").map_err(|e| daverr(e.to_string()))?;
+        let mut current = String::new();
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            current = format!("{}/{}", current, segment);
+            let response = self
+                .request(method.clone(), &current)
+                .send()
+                .await
+                .map_err(|e| daverr(format!("MKCOL '{}': {}", current, e)))?;
+            let status = response.status();
+            if !status.is_success() && status != StatusCode::METHOD_NOT_ALLOWED {
+                return Err(daverr(format!("MKCOL '{}': {}", current, status)));
+            }
+        }
+        Ok(())
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Leading slash, no trailing slash (except for the root).
+fn normalize(path: &str) -> String {
+    let trimmed = path.trim_matches('/');
+    format!("/{}", trimmed)
+}
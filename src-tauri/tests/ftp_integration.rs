@@ -0,0 +1,428 @@
+use app_lib::ftp::client::{FtpConfig, FtpStream, FtpTls};
+use app_lib::ftp::service::FtpService;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Connection settings for the test server (see docker-compose.test.yml).
+///
+///   FTP_TEST_HOST     — default localhost
+///   FTP_TEST_PORT     — default 2121
+///   FTP_TEST_USER     — default furman
+///   FTP_TEST_PASSWORD — default furman
+fn config(tls: FtpTls) -> FtpConfig {
+    FtpConfig {
+        host: std::env::var("FTP_TEST_HOST").unwrap_or_else(|_| "localhost".to_string()),
+        port: std::env::var("FTP_TEST_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(2121),
+        username: std::env::var("FTP_TEST_USER").unwrap_or_else(|_| "furman".to_string()),
+        password: std::env::var("FTP_TEST_PASSWORD").unwrap_or_else(|_| "furman".to_string()),
+        tls,
+        passive: true,
+        // The container generates a self-signed certificate.
+        accept_invalid_certs: true,
+    }
+}
+
+/// Test context that owns a unique directory in the FTP user's home.
+/// Each test gets its own FtpTestContext for isolation.
+struct FtpTestContext {
+    service: FtpService,
+    root: String,
+}
+
+impl FtpTestContext {
+    async fn new() -> Self {
+        Self::with_tls(FtpTls::None).await
+    }
+
+    async fn with_tls(tls: FtpTls) -> Self {
+        let config = config(tls);
+        let mut stream = FtpStream::connect(&config)
+            .await
+            .expect("Failed to connect to the test FTP server");
+        let home = stream.pwd().await.unwrap_or_else(|_| "/".to_string());
+        let root = format!("{}/test-{}", home.trim_end_matches('/'), uuid::Uuid::new_v4());
+
+        let service = FtpService::new(Arc::new(tokio::sync::Mutex::new(stream)), Arc::new(config));
+        service
+            .create_folder(&root)
+            .await
+            .expect("Failed to create test directory");
+        Self { service, root }
+    }
+
+    fn path(&self, name: &str) -> String {
+        format!("{}/{}", self.root, name)
+    }
+
+    async fn put(&self, name: &str, content: &str) {
+        self.service
+            .put_text(&self.path(name), content)
+            .await
+            .unwrap_or_else(|e| panic!("put_text {name} failed: {e}"));
+    }
+
+    async fn read(&self, name: &str) -> String {
+        let local = self
+            .service
+            .download_temp(&self.path(name))
+            .await
+            .unwrap_or_else(|e| panic!("download_temp {name} failed: {e}"));
+        std::fs::read_to_string(local).expect("read temp file")
+    }
+
+    async fn names(&self, dir: &str) -> Vec<String> {
+        let listing = self
+            .service
+            .list_objects(&self.path(dir))
+            .await
+            .expect("list_objects failed");
+        let mut names: Vec<String> = listing
+            .entries
+            .into_iter()
+            .map(|e| e.name)
+            .filter(|n| n != "..")
+            .collect();
+        names.sort();
+        names
+    }
+
+    async fn cleanup(self) {
+        let _ = self.service.delete(std::slice::from_ref(&self.root)).await;
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// P1 — Core operations
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn test_list_and_create_folder() {
+    let ctx = FtpTestContext::new().await;
+
+    ctx.put("file1.txt", "hello").await;
+    ctx.service
+        .create_folder(&ctx.path("subdir"))
+        .await
+        .expect("create_folder failed");
+
+    let listing = ctx
+        .service
+        .list_objects(&ctx.root)
+        .await
+        .expect("list_objects failed");
+    assert!(listing.entries.iter().any(|e| e.name == ".."), "Missing '..' entry");
+
+    let file = listing.entries.iter().find(|e| e.name == "file1.txt").expect("Missing file1.txt");
+    assert!(!file.is_dir);
+    assert_eq!(file.size, 5);
+    assert_eq!(file.extension.as_deref(), Some("txt"));
+
+    let dir = listing.entries.iter().find(|e| e.name == "subdir").expect("Missing subdir");
+    assert!(dir.is_dir);
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_put_text_and_download_temp() {
+    let ctx = FtpTestContext::new().await;
+
+    ctx.put("note.txt", "line one\nline two\n").await;
+    assert_eq!(ctx.read("note.txt").await, "line one\nline two\n");
+
+    // Overwrite
+    ctx.put("note.txt", "replaced").await;
+    assert_eq!(ctx.read("note.txt").await, "replaced");
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_rename() {
+    let ctx = FtpTestContext::new().await;
+
+    ctx.put("old.txt", "content").await;
+    ctx.service
+        .rename(&ctx.path("old.txt"), "new.txt")
+        .await
+        .expect("rename failed");
+
+    assert_eq!(ctx.names("").await, vec!["new.txt"]);
+    assert_eq!(ctx.read("new.txt").await, "content");
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_delete_recursive() {
+    let ctx = FtpTestContext::new().await;
+
+    ctx.put("keep.txt", "keep").await;
+    ctx.put("gone.txt", "gone").await;
+    ctx.service
+        .create_folder(&ctx.path("tree"))
+        .await
+        .expect("create_folder failed");
+    ctx.service
+        .create_folder(&ctx.path("tree/nested"))
+        .await
+        .expect("create_folder failed");
+    ctx.put("tree/a.txt", "a").await;
+    ctx.put("tree/nested/b.txt", "b").await;
+
+    ctx.service
+        .delete(&[ctx.path("gone.txt"), format!("{}/", ctx.path("tree"))])
+        .await
+        .expect("delete failed");
+
+    assert_eq!(ctx.names("").await, vec!["keep.txt"]);
+
+    ctx.cleanup().await;
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// P2 — Transfers
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn test_upload_and_download_tree() {
+    let ctx = FtpTestContext::new().await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+
+    let src = tempfile::tempdir().expect("tempdir");
+    let tree = src.path().join("tree");
+    std::fs::create_dir_all(tree.join("nested")).unwrap();
+    std::fs::write(tree.join("a.txt"), "alpha").unwrap();
+    std::fs::write(tree.join("nested/b.bin"), vec![7u8; 200_000]).unwrap();
+    std::fs::write(src.path().join("single.txt"), "single").unwrap();
+
+    let sources = vec![
+        tree.to_string_lossy().to_string(),
+        src.path().join("single.txt").to_string_lossy().to_string(),
+    ];
+    let result = ctx
+        .service
+        .upload(&sources, &ctx.root, "op-up", &cancel, &pause, &|_| {}, false)
+        .await
+        .expect("upload failed");
+    assert!(result.is_none(), "Should return None on success");
+
+    assert_eq!(ctx.names("").await, vec!["single.txt", "tree"]);
+    assert_eq!(ctx.names("tree").await, vec!["a.txt", "nested"]);
+    assert_eq!(ctx.read("tree/a.txt").await, "alpha");
+
+    let dest = tempfile::tempdir().expect("tempdir");
+    let events = std::sync::Mutex::new(Vec::new());
+    let result = ctx
+        .service
+        .download(
+            &[format!("{}/", ctx.path("tree")), ctx.path("single.txt")],
+            &dest.path().to_string_lossy(),
+            "op-down",
+            &cancel,
+            &pause,
+            &|e| events.lock().unwrap().push(e),
+            false,
+        )
+        .await
+        .expect("download failed");
+    assert!(result.is_none(), "Should return None on success");
+
+    assert_eq!(std::fs::read_to_string(dest.path().join("tree/a.txt")).unwrap(), "alpha");
+    assert_eq!(
+        std::fs::read(dest.path().join("tree/nested/b.bin")).unwrap(),
+        vec![7u8; 200_000]
+    );
+    assert_eq!(std::fs::read_to_string(dest.path().join("single.txt")).unwrap(), "single");
+
+    let events = events.into_inner().unwrap();
+    let last = events.last().expect("no progress events");
+    assert_eq!(last.id, "op-down");
+    assert_eq!(last.files_done, 3);
+    assert_eq!(last.files_total, 3);
+    assert_eq!(last.bytes_done, 200_011);
+    assert_eq!(last.bytes_total, 200_011);
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_upload_resume_appends() {
+    let ctx = FtpTestContext::new().await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+
+    let src = tempfile::tempdir().expect("tempdir");
+    let local = src.path().join("resume.txt");
+    std::fs::write(&local, "0123456789abcdef").unwrap();
+
+    // A partial upload left on the server by an earlier, paused run
+    ctx.put("resume.txt", "01234567").await;
+
+    ctx.service
+        .upload(
+            &[local.to_string_lossy().to_string()],
+            &ctx.root,
+            "op-resume",
+            &cancel,
+            &pause,
+            &|_| {},
+            true,
+        )
+        .await
+        .expect("resumed upload failed");
+
+    assert_eq!(ctx.read("resume.txt").await, "0123456789abcdef");
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_download_resume_continues_local_file() {
+    let ctx = FtpTestContext::new().await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+
+    ctx.put("resume.txt", "0123456789abcdef").await;
+
+    let dest = tempfile::tempdir().expect("tempdir");
+    std::fs::write(dest.path().join("resume.txt"), "01234").unwrap();
+
+    ctx.service
+        .download(
+            &[ctx.path("resume.txt")],
+            &dest.path().to_string_lossy(),
+            "op-resume",
+            &cancel,
+            &pause,
+            &|_| {},
+            true,
+        )
+        .await
+        .expect("resumed download failed");
+
+    assert_eq!(
+        std::fs::read_to_string(dest.path().join("resume.txt")).unwrap(),
+        "0123456789abcdef"
+    );
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_pause_returns_checkpoint() {
+    let ctx = FtpTestContext::new().await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(true);
+
+    ctx.put("a.txt", "a").await;
+
+    let dest = tempfile::tempdir().expect("tempdir");
+    let checkpoint = ctx
+        .service
+        .download(
+            &[ctx.path("a.txt")],
+            &dest.path().to_string_lossy(),
+            "op-pause",
+            &cancel,
+            &pause,
+            &|_| {},
+            false,
+        )
+        .await
+        .expect("paused download failed")
+        .expect("Should return a checkpoint when paused");
+    assert_eq!(checkpoint.files_done, 0);
+    assert_eq!(checkpoint.files_total, 1);
+    assert!(checkpoint.files_completed.is_empty());
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_cancel() {
+    let ctx = FtpTestContext::new().await;
+    let cancel = AtomicBool::new(true);
+    let pause = AtomicBool::new(false);
+
+    let src = tempfile::tempdir().expect("tempdir");
+    let local = src.path().join("cancelled.txt");
+    std::fs::write(&local, "data").unwrap();
+
+    let err = ctx
+        .service
+        .upload(
+            &[local.to_string_lossy().to_string()],
+            &ctx.root,
+            "op-cancel",
+            &cancel,
+            &pause,
+            &|_| {},
+            false,
+        )
+        .await
+        .expect_err("cancelled upload should fail");
+    assert!(err.to_string().contains("cancelled"), "unexpected error: {err}");
+
+    ctx.cleanup().await;
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// P3 — TLS
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn test_explicit_tls_round_trip() {
+    let ctx = FtpTestContext::with_tls(FtpTls::Explicit).await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+
+    // Control and data connections both run over TLS (PROT P).
+    ctx.put("secure.txt", "over tls").await;
+    assert_eq!(ctx.names("").await, vec!["secure.txt"]);
+    assert_eq!(ctx.read("secure.txt").await, "over tls");
+
+    let src = tempfile::tempdir().expect("tempdir");
+    let local = src.path().join("upload.bin");
+    std::fs::write(&local, vec![42u8; 150_000]).unwrap();
+    ctx.service
+        .upload(
+            &[local.to_string_lossy().to_string()],
+            &ctx.root,
+            "op-tls",
+            &cancel,
+            &pause,
+            &|_| {},
+            false,
+        )
+        .await
+        .expect("upload over TLS failed");
+
+    let dest = tempfile::tempdir().expect("tempdir");
+    ctx.service
+        .download(
+            &[ctx.path("upload.bin")],
+            &dest.path().to_string_lossy(),
+            "op-tls-down",
+            &cancel,
+            &pause,
+            &|_| {},
+            false,
+        )
+        .await
+        .expect("download over TLS failed");
+    assert_eq!(std::fs::read(dest.path().join("upload.bin")).unwrap(), vec![42u8; 150_000]);
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_wrong_password_fails() {
+    let mut config = config(FtpTls::None);
+    config.password = "not-the-password".to_string();
+    assert!(FtpStream::connect(&config).await.is_err(), "login should be rejected");
+}
@@ -1,7 +1,8 @@
 mod common;
 
-use app_lib::models::S3PublicAccessBlock;
+use app_lib::models::{S3PublicAccessBlock, TransferCheckpoint};
 use app_lib::s3::client::build_s3_client;
+use app_lib::s3::retry;
 use app_lib::s3::service::{self, S3Service};
 use common::TestContext;
 use std::sync::atomic::AtomicBool;
@@ -249,6 +250,7 @@ async fn test_copy_same_bucket() {
             &ctx.client,
             &ctx.bucket,
             "dest/",
+            "overwrite",
             "op-copy",
             &cancel,
             &pause,
@@ -293,6 +295,7 @@ async fn test_copy_cross_bucket() {
             &ctx.client,
             &dest_bucket,
             "",
+            "overwrite",
             "op-cross",
             &cancel,
             &pause,
@@ -327,6 +330,7 @@ async fn test_copy_prefix() {
             &ctx.client,
             &ctx.bucket,
             "destdir/",
+            "overwrite",
             "op-prefix",
             &cancel,
             &pause,
@@ -347,6 +351,184 @@ async fn test_copy_prefix() {
     ctx.cleanup().await;
 }
 
+#[tokio::test]
+async fn test_copy_prefix_keeps_structure() {
+    let ctx = TestContext::new().await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+
+    ctx.put_object("photos/2024/a.jpg", b"a").await;
+    ctx.put_object("photos/2024/trip/b.jpg", b"b").await;
+    ctx.put_object("photos/2025/b.jpg", b"other b").await;
+
+    ctx.service
+        .copy_objects(
+            &ctx.client,
+            &ctx.bucket,
+            &["photos/".to_string()],
+            &ctx.client,
+            &ctx.bucket,
+            "backup/",
+            "overwrite",
+            "op-tree",
+            &cancel,
+            &pause,
+            &|_| {},
+        )
+        .await
+        .expect("copy prefix tree failed");
+
+    for key in [
+        "backup/photos/2024/a.jpg",
+        "backup/photos/2024/trip/b.jpg",
+        "backup/photos/2025/b.jpg",
+    ] {
+        ctx.service
+            .head_object(key)
+            .await
+            .unwrap_or_else(|_| panic!("{} should exist", key));
+    }
+    assert!(
+        ctx.service.head_object("backup/b.jpg").await.is_err(),
+        "nested objects should not be flattened"
+    );
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_copy_conflict_policies() {
+    let ctx = TestContext::new().await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+
+    ctx.put_object("src/same.txt", b"same").await;
+    ctx.put_object("src/changed.txt", b"new content").await;
+    ctx.put_object("dst/same.txt", b"same").await;
+    ctx.put_object("dst/changed.txt", b"old").await;
+
+    let copy = |policy: &'static str| {
+        let ctx = &ctx;
+        let cancel = &cancel;
+        let pause = &pause;
+        async move {
+            ctx.service
+                .copy_objects(
+                    &ctx.client,
+                    &ctx.bucket,
+                    &["src/same.txt".to_string(), "src/changed.txt".to_string()],
+                    &ctx.client,
+                    &ctx.bucket,
+                    "dst/",
+                    policy,
+                    "op-conflict",
+                    cancel,
+                    pause,
+                    &|_| {},
+                )
+                .await
+                .expect("copy with conflict policy failed")
+        }
+    };
+
+    // skip: existing objects are left alone
+    copy("skip").await;
+    let props = ctx.service.head_object("dst/changed.txt").await.unwrap();
+    assert_eq!(props.size, 3);
+
+    // skip_identical: only the object whose ETag differs is replaced
+    copy("skip_identical").await;
+    let props = ctx.service.head_object("dst/changed.txt").await.unwrap();
+    assert_eq!(props.size, 11);
+
+    // rename: copies land next to the existing objects
+    copy("rename").await;
+    ctx.service
+        .head_object("dst/same (1).txt")
+        .await
+        .expect("renamed copy should exist");
+    copy("rename").await;
+    ctx.service
+        .head_object("dst/changed (2).txt")
+        .await
+        .expect("second renamed copy should exist");
+
+    // unknown policies are rejected
+    let err = ctx
+        .service
+        .copy_objects(
+            &ctx.client,
+            &ctx.bucket,
+            &["src/same.txt".to_string()],
+            &ctx.client,
+            &ctx.bucket,
+            "dst/",
+            "merge",
+            "op-bad",
+            &cancel,
+            &pause,
+            &|_| {},
+        )
+        .await;
+    assert!(err.is_err(), "unknown conflict policy should fail");
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_resume_copy_skips_checkpointed_objects() {
+    let ctx = TestContext::new().await;
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+
+    ctx.put_object("src/done.txt", b"copied before the pause").await;
+    ctx.put_object("src/todo.txt", b"still to copy").await;
+    // Stands in for an object copied before the pause whose ETag no longer
+    // matches its source, as with multipart copies.
+    ctx.put_object("dst/done.txt", b"marker").await;
+
+    let checkpoint = TransferCheckpoint {
+        files_completed: vec!["src/done.txt".to_string()],
+        bytes_done: 23,
+        bytes_total: 36,
+        files_done: 1,
+        files_total: 2,
+    };
+    let events = std::sync::Mutex::new(Vec::new());
+    let result = ctx
+        .service
+        .resume_copy_objects(
+            &ctx.client,
+            &ctx.bucket,
+            &["src/".to_string()],
+            &ctx.client,
+            &ctx.bucket,
+            "dst/",
+            "skip_identical",
+            Some(checkpoint),
+            "op-resume-copy",
+            &cancel,
+            &pause,
+            &|e| events.lock().unwrap().push(e),
+        )
+        .await
+        .expect("resumed copy failed");
+    assert!(result.is_none(), "Should return None on success");
+
+    let props = ctx.service.head_object("dst/done.txt").await.unwrap();
+    assert_eq!(props.size, 6, "checkpointed object should not be copied again");
+    let props = ctx.service.head_object("dst/todo.txt").await.unwrap();
+    assert_eq!(props.size, 13);
+
+    let events = events.into_inner().unwrap();
+    let last = events.last().expect("no progress events");
+    assert_eq!(last.files_done, 2);
+    assert_eq!(last.files_total, 2);
+    assert_eq!(last.bytes_done, 36);
+
+    ctx.cleanup().await;
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // P3 — Multipart
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1129,6 +1311,72 @@ async fn test_upload_files() {
     ctx.cleanup().await;
 }
 
+#[tokio::test]
+async fn test_download_many_objects_concurrently() {
+    let ctx = TestContext::new().await;
+
+    for i in 0..24 {
+        ctx.put_object(&format!("many/f{i:02}.txt"), format!("content {i}").as_bytes())
+            .await;
+    }
+
+    let tmp_dir = tempfile::tempdir().expect("tempdir");
+    let cancel = AtomicBool::new(false);
+    let pause = AtomicBool::new(false);
+    let last = std::sync::Mutex::new((0u64, 0u64, 0u32));
+
+    let result = ctx
+        .service
+        .download(
+            &["many/".to_string()],
+            tmp_dir.path().to_str().unwrap(),
+            "op-many",
+            &cancel,
+            &pause,
+            &|e| *last.lock().unwrap() = (e.bytes_done, e.bytes_total, e.files_done),
+            None,
+        )
+        .await
+        .expect("download failed");
+
+    assert!(result.is_none());
+    for i in 0..24 {
+        let body = std::fs::read_to_string(tmp_dir.path().join(format!("f{i:02}.txt"))).unwrap();
+        assert_eq!(body, format!("content {i}"));
+    }
+    let (bytes_done, bytes_total, files_done) = *last.lock().unwrap();
+    assert_eq!(bytes_done, bytes_total);
+    assert_eq!(files_done, 24);
+
+    ctx.service
+        .delete_objects(&["many/".to_string()])
+        .await
+        .expect("delete failed");
+    let result = ctx.service.head_object("many/f00.txt").await;
+    assert!(result.is_err(), "many/f00.txt should be gone");
+
+    ctx.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_missing_object_is_not_retried() {
+    let ctx = TestContext::new().await;
+    ctx.put_object("retry/ok.txt", b"ok").await;
+
+    retry::counted(async {
+        let result = ctx.service.head_object("retry/missing.txt").await;
+        assert!(result.is_err(), "retry/missing.txt should not exist");
+        ctx.service
+            .head_object("retry/ok.txt")
+            .await
+            .expect("retry/ok.txt should exist");
+        assert_eq!(retry::retries(), 0, "a 404 is final and a success needs no retry");
+    })
+    .await;
+
+    ctx.cleanup().await;
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // P9 — Encryption configuration
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1,5 +1,5 @@
 import { invoke, Channel } from '@tauri-apps/api/core';
-import type { DirListing, FileProperties, ProgressEvent, TransferCheckpoint } from '$lib/types';
+import type { DirListing, FileProperties, ProgressEvent, S3UploadCheckpoint, TransferCheckpoint } from '$lib/types';
 
 export async function sftpConnect(
   id: string,
@@ -71,6 +71,40 @@ export async function sftpUpload(
   });
 }
 
+/** Stream S3 objects into an SFTP directory without a local copy. */
+export async function s3ToSftp(
+  s3Id: string,
+  opId: string,
+  keys: string[],
+  sftpId: string,
+  destination: string,
+  onProgress: (e: ProgressEvent) => void,
+  resume = false,
+): Promise<TransferCheckpoint | null> {
+  const channel = new Channel<ProgressEvent>();
+  channel.onmessage = onProgress;
+  return await invoke<TransferCheckpoint | null>('s3_to_sftp', {
+    opId, s3Id, keys, sftpId, destination, resume, channel,
+  });
+}
+
+/** Stream SFTP files into an S3 prefix without a local copy. */
+export async function sftpToS3(
+  sftpId: string,
+  opId: string,
+  paths: string[],
+  s3Id: string,
+  destination: string,
+  onProgress: (e: ProgressEvent) => void,
+  checkpoint?: S3UploadCheckpoint | null,
+): Promise<S3UploadCheckpoint | null> {
+  const channel = new Channel<ProgressEvent>();
+  channel.onmessage = onProgress;
+  return await invoke<S3UploadCheckpoint | null>('sftp_to_s3', {
+    opId, sftpId, paths, s3Id, destination, checkpoint: checkpoint ?? null, channel,
+  });
+}
+
 export async function sftpDownloadTemp(id: string, path: string): Promise<string> {
   return await invoke<string>('sftp_download_temp', { id, path });
 }
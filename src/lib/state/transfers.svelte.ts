@@ -1,7 +1,7 @@
-import type { ProgressEvent, TransferCheckpoint } from '$lib/types';
+import type { ProgressEvent, S3UploadCheckpoint, TransferCheckpoint } from '$lib/types';
 import { cancelFileOperation, pauseFileOperation, copyFiles, moveFiles, extractArchive } from '$lib/services/tauri';
 import { s3Download, s3Upload, s3CopyObjects, s3UploadEncrypted, type EncryptionConfig } from '$lib/services/s3';
-import { sftpDownload, sftpUpload } from '$lib/services/sftp';
+import { sftpDownload, sftpUpload, s3ToSftp, sftpToS3 } from '$lib/services/sftp';
 import { formatSize } from '$lib/utils/format';
 
 export type TransferStatus = 'queued' | 'running' | 'paused' | 'completed' | 'failed' | 'cancelled';
@@ -348,7 +348,20 @@ class TransfersState {
         });
         return await sftpUpload(t.sftpDestConnectionId!, t.id + '-up', downloaded, t.sftpDestPath!, onProgress);
       }
-      // Cross-protocol: S3 ↔ SFTP (via temp dir)
+      // Cross-protocol: S3 ↔ SFTP, streamed server to server. Encrypted
+      // objects are decrypted locally, so they still go through a temp dir.
+      if (srcBackend === 's3' && destBackend === 'sftp' && !t.encryptionPassword) {
+        return await s3ToSftp(
+          t.s3SrcConnectionId!, t.id, t.sources,
+          t.sftpDestConnectionId!, t.sftpDestPath!, onProgress, !!t.checkpoint,
+        );
+      }
+      if (srcBackend === 'sftp' && destBackend === 's3') {
+        return await sftpToS3(
+          t.sftpSrcConnectionId!, t.id, t.sources,
+          t.s3DestConnectionId!, t.s3DestPrefix!, onProgress, t.checkpoint as S3UploadCheckpoint | null | undefined,
+        );
+      }
       if (srcBackend === 's3' && destBackend === 'sftp') {
         const tempDir = `/tmp/furman-xfer-${t.id}`;
         await s3Download(t.s3SrcConnectionId!, t.id, t.sources, tempDir, onProgress, t.encryptionPassword);
@@ -358,15 +371,6 @@ class TransfersState {
         });
         return await sftpUpload(t.sftpDestConnectionId!, t.id + '-up', downloaded, t.sftpDestPath!, onProgress);
       }
-      if (srcBackend === 'sftp' && destBackend === 's3') {
-        const tempDir = `/tmp/furman-xfer-${t.id}`;
-        await sftpDownload(t.sftpSrcConnectionId!, t.id, t.sources, tempDir, onProgress);
-        const downloaded = t.sources.map((s) => {
-          const name = s.replace(/\/+$/, '').split('/').pop()!;
-          return `${tempDir}/${name}`;
-        });
-        return await s3Upload(t.s3DestConnectionId!, t.id + '-up', downloaded, t.s3DestPrefix!, onProgress);
-      }
     }
 
     if (t.type === 'move') {
@@ -405,7 +409,20 @@ class TransfersState {
         });
         return await sftpUpload(t.sftpDestConnectionId!, t.id + '-up', downloaded, t.sftpDestPath!, onProgress);
       }
-      // Cross-protocol: S3 ↔ SFTP (via temp dir)
+      // Cross-protocol: S3 ↔ SFTP, streamed server to server. Encrypted
+      // objects are decrypted locally, so they still go through a temp dir.
+      if (srcBackend === 's3' && destBackend === 'sftp' && !t.encryptionPassword) {
+        return await s3ToSftp(
+          t.s3SrcConnectionId!, t.id, t.sources,
+          t.sftpDestConnectionId!, t.sftpDestPath!, onProgress, !!t.checkpoint,
+        );
+      }
+      if (srcBackend === 'sftp' && destBackend === 's3') {
+        return await sftpToS3(
+          t.sftpSrcConnectionId!, t.id, t.sources,
+          t.s3DestConnectionId!, t.s3DestPrefix!, onProgress, t.checkpoint as S3UploadCheckpoint | null | undefined,
+        );
+      }
       if (srcBackend === 's3' && destBackend === 'sftp') {
         const tempDir = `/tmp/furman-xfer-${t.id}`;
         await s3Download(t.s3SrcConnectionId!, t.id, t.sources, tempDir, onProgress, t.encryptionPassword);
@@ -415,15 +432,6 @@ class TransfersState {
         });
         return await sftpUpload(t.sftpDestConnectionId!, t.id + '-up', downloaded, t.sftpDestPath!, onProgress);
       }
-      if (srcBackend === 'sftp' && destBackend === 's3') {
-        const tempDir = `/tmp/furman-xfer-${t.id}`;
-        await sftpDownload(t.sftpSrcConnectionId!, t.id, t.sources, tempDir, onProgress);
-        const downloaded = t.sources.map((s) => {
-          const name = s.replace(/\/+$/, '').split('/').pop()!;
-          return `${tempDir}/${name}`;
-        });
-        return await s3Upload(t.s3DestConnectionId!, t.id + '-up', downloaded, t.s3DestPrefix!, onProgress);
-      }
     }
 
     return null;
@@ -36,6 +36,7 @@ export interface ProgressEvent {
   current_file: string;
   files_done: number;
   files_total: number;
+  retries: number; // requests retried so far in this operation
 }
 
 export type TerminalDisplayMode = 'none' | 'bottom' | 'in-pane' | 'quake';
@@ -93,7 +94,17 @@ export type SearchEvent = SearchResult | SearchDone;
 
 export interface SyncEntry {
   relative_path: string;
-  status: 'new' | 'modified' | 'deleted' | 'same';
+  // Two-way statuses: "left" is the source side.
+  status:
+    | 'new'
+    | 'modified'
+    | 'deleted'
+    | 'same'
+    | 'left_changed'
+    | 'right_changed'
+    | 'left_deleted'
+    | 'right_deleted'
+    | 'conflict';
   source_size: number;
   dest_size: number;
   source_modified: number;
@@ -105,7 +116,8 @@ export interface SyncEntry {
 export type SyncEvent =
   | ({ type: 'Entry' } & SyncEntry)
   | { type: 'Progress'; scanned: number }
-  | { type: 'Done'; total: number; new_count: number; modified: number; deleted: number };
+  | { type: 'Warning'; message: string }
+  | { type: 'Done'; total: number; new_count: number; modified: number; deleted: number; conflicts: number };
 
 export interface GitRepoInfo {
   branch: string;